@@ -1,10 +1,12 @@
-use std::{fs, path::Path, process::Command};
+use std::{collections::HashMap, fs, path::Path, process::Command, time::{Duration, Instant}};
 use crate::{
     parsing::{parse_file, MathFile},
     compiling::{
-        compile_directory, add_syndef_to_lib,
+        compile_directory,
+        add_sort_to_lib, add_syndef_to_lib,
         add_axiom_to_lib, add_theo_to_lib,
-        verify_theo
+        verify_theo, infer_theo, fixup_theo, minimize_theo, prove_theo, saturate_theo, decide_tautology, suggest_statements,
+        Library, Reference, CompileWarning, SearchBudget, Decision
     },
     serializing::{read_file, write_lib}
 };
@@ -45,57 +47,75 @@ fn move_entry_to_order_file(filepath: &str, dir: String, subdir: &str) -> Result
         Ok(text) => text,
         Err(e) => return Err( format!("{e:?}") )
     };
-    let mut lines = text.lines();
-    let _ = lines.by_ref()
-        .take_while(|s| s == &"# Syntax Definitions")
-        .collect::<Vec<_>>();
-    let syndefs = lines.by_ref()
-        .take_while(|s| s == &"# Axioms")
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>();
-    let axioms = lines.by_ref()
-        .take_while(|s| s == &"# Theorems")
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>();
-    let theorems = lines
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>();
-    
-    let line_to_add = subdir.to_owned() + &file_name + "\n";
+    let headers = ["# Sorts", "# Syntax Definitions", "# Axioms", "# Deduction", "# Theorems"];
+    let mut sections = vec![Vec::new(); headers.len()];
+    let mut current_section = 0;
+    for line in text.lines() {
+        match headers.iter().position(|header| header == &line) {
+            Some(index) => current_section = index,
+            None if !line.is_empty() => sections[current_section].push(line),
+            None => ()
+        };
+    };
+
+    let line_to_add = subdir.to_owned() + &file_name;
     let target_section = match subdir {
+        r"\sorts\" => 0,
         r"\syntax_definitions\" => 1,
         r"\axioms\" => 2,
-        r"\theorems\" => 3,
+        r"\theorems\" => 4,
         _ => unreachable!()
     };
-    let mut file_data = String::from("# Syntax Definition\n");
-    for line in syndefs {
-        file_data.push_str(line);
+    sections[target_section].push(&line_to_add);
+    let mut file_data = String::new();
+    for (header, lines) in headers.iter().zip(sections) {
+        // The sorts and deduction sections are optional
+        if (header == &"# Sorts" || header == &"# Deduction") && lines.is_empty() { continue; };
+        if !file_data.is_empty() { file_data.push('\n'); };
+        file_data.push_str(header);
         file_data.push('\n');
+        for line in lines {
+            file_data.push_str(line);
+            file_data.push('\n');
+        };
     };
-    if target_section == 1 { file_data.push_str( &line_to_add ) };
-    file_data.push_str("\n# Axioms\n");
-    for line in axioms {
-        file_data.push_str(line);
-        file_data.push('\n');
-    };
-    if target_section == 2 { file_data.push_str( &line_to_add ) };
-    file_data.push_str("\n# Theorems\n");
-    for line in theorems {
-        file_data.push_str(line);
-        file_data.push('\n');
-    };
-    if target_section == 3 { file_data.push_str( &line_to_add ) };
     
     fs::write(dir + r"\order.txt", file_data)
         .map_err(|e| format!("{e:?}"))
 }
 
+// The warnings are displayed after the success message, one per line
+fn with_warnings(message: &str, warnings: Vec<CompileWarning>) -> String {
+    let mut res = message.to_string();
+    for warning in warnings {
+        res.push_str( &format!("\nWARNING: {warning:?}") );
+    };
+    res
+}
+
 pub fn compile(dir: String) -> Result<String, String> {
-    let lib = match compile_directory(dir.clone()) {
-        Ok(lib) => lib,
+    let (lib, warnings) = match compile_directory(dir.clone()) {
+        Ok(compiled) => compiled,
         Err(e) => return Err( format!("{e:?}") )
     };
+    match write_lib(dir + "/library.math", lib) {
+        Ok(()) => Ok(with_warnings("Compilation successful!", warnings)),
+        Err(e) => return Err( format!("{e:?}") )
+    }
+}
+
+pub fn add_sort(dir: String, path: String) -> Result<String, String> {
+    let math_file = get_math_file(&path)?;
+    let (mut lib, _references) = match read_file(dir.clone() + "/library.math") {
+        Ok((lib, references)) => (lib, references),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    match add_sort_to_lib(math_file, &mut lib) {
+        Ok(()) => (),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    try_move_file_to(&path, dir.clone(), r"\sorts\")?;
+    move_entry_to_order_file(&path, dir.clone(), r"\sorts\")?;
     match write_lib(dir + "/library.math", lib) {
         Ok(()) => Ok("Compilation successful!".to_string()),
         Err(e) => return Err( format!("{e:?}") )
@@ -144,14 +164,14 @@ pub fn add_theo(dir: String, path: String) -> Result<String, String> {
         Ok((lib, references)) => (lib, references),
         Err(e) => return Err( format!("{e:?}") )
     };
-    match add_theo_to_lib(math_file, &mut lib, &mut references) {
-        Ok(()) => (),
+    let warnings = match add_theo_to_lib(math_file, &mut lib, &mut references) {
+        Ok(warnings) => warnings,
         Err(e) => return Err( format!("{e:?}") )
     };
     try_move_file_to(&path, dir.clone(), r"\theorems\")?;
     move_entry_to_order_file(&path, dir.clone(), r"\theorems\")?;
     match write_lib(dir + "/library.math", lib) {
-        Ok(()) => Ok("Compilation successful!".to_string()),
+        Ok(()) => Ok(with_warnings("Compilation successful!", warnings)),
         Err(e) => return Err( format!("{e:?}") )
     }
 }
@@ -163,11 +183,301 @@ pub fn verify(dir: String, path: String) -> Result<String, String> {
         Err(e) => return Err( format!("{e:?}") )
     };
     match verify_theo(math_file, &mut lib, &mut references) {
-        Ok(()) => Ok("Theorem is valid".to_string()),
+        Ok(warnings) => Ok(with_warnings("Theorem is valid", warnings)),
         Err(e) => return Err( format!("{e:?}") )
     }
 }
 
+// The lines of the file, with the columns of the ones which are proof lines.
+// Proof lines are the non-empty lines of the "# Proof" sections, the theorem's then the lemmas', in order
+fn split_proof_lines(text: &str) -> Vec<(&str, Option<Vec<&str>>)> {
+    let mut in_proof = false;
+    text.lines()
+        .map(|line| {
+            if line.starts_with('#') {
+                in_proof = line == "# Proof";
+                return (line, None);
+            };
+            match in_proof && !line.is_empty() {
+                true => (line, Some(line.splitn(4, ';').collect())),
+                false => (line, None)
+            }
+        })
+        .collect()
+}
+
+// The rewritten file must hold a valid proof before replacing the original one
+fn verify_rewritten(
+    file_data: &str, rewriting: &str, lib: &mut Library, references: &mut HashMap<String, Reference>
+) -> Result<(), String> {
+    let rewritten_file = match parse_file(file_data.to_owned()) {
+        Ok(rewritten_file) => rewritten_file,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    match verify_theo(rewritten_file, lib, references) {
+        Ok(_) => Ok(()),
+        Err(e) => Err( format!("The {rewriting} proof is invalid: {e:?}") )
+    }
+}
+
+pub fn infer(dir: String, path: String) -> Result<String, String> {
+    let math_file = get_math_file(&path)?;
+    let (mut lib, mut references) = match read_file(dir.clone() + "/library.math") {
+        Ok((lib, references)) => (lib, references),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let formulas = match infer_theo(math_file, &mut lib, &references) {
+        Ok(formulas) => formulas,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let mut formulas = formulas.into_iter();
+    let mut inferred_count = 0;
+    let mut file_data = String::new();
+    for (line, columns) in split_proof_lines(&text) {
+        if let Some(columns) = columns {
+            let formula = formulas.next();
+            match (&columns[..], formula) {
+                ([line_no, used_hypots, theo_ref, fm], Some(formula)) if fm.trim() == "?" => {
+                    let parts = [*line_no, used_hypots, theo_ref, &(" ".to_owned() + &formula)];
+                    file_data.push_str(&parts.join(";"));
+                    file_data.push('\n');
+                    inferred_count += 1;
+                    continue;
+                },
+                _ => ()
+            };
+        };
+        file_data.push_str(line);
+        file_data.push('\n');
+    };
+    verify_rewritten(&file_data, "inferred", &mut lib, &mut references)?;
+    match fs::write(&path, file_data) {
+        Ok(()) => Ok(format!("{inferred_count} formula(s) inferred")),
+        Err(e) => Err( format!("{e:?}") )
+    }
+}
+
+pub fn fixup(dir: String, path: String) -> Result<String, String> {
+    let math_file = get_math_file(&path)?;
+    let (mut lib, mut references) = match read_file(dir.clone() + "/library.math") {
+        Ok((lib, references)) => (lib, references),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let used_lines = match fixup_theo(math_file, &mut lib, &references) {
+        Ok(used_lines) => used_lines,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let mut used_lines = used_lines.into_iter();
+    let mut filled_count = 0;
+    let mut file_data = String::new();
+    for (line, columns) in split_proof_lines(&text) {
+        let found = match columns {
+            Some(_) => used_lines.next(),
+            None => None
+        };
+        match (columns.as_deref(), found) {
+            (Some([line_no, used_hypots, theo_ref, fm]), Some(found))
+                if used_hypots.trim().is_empty() && !found.is_empty() => {
+                let used_hypots = " ".to_owned() + &found;
+                file_data.push_str(&[*line_no, &used_hypots, theo_ref, fm].join(";"));
+                filled_count += 1;
+            },
+            _ => file_data.push_str(line)
+        };
+        file_data.push('\n');
+    };
+    verify_rewritten(&file_data, "fixed", &mut lib, &mut references)?;
+    match fs::write(&path, file_data) {
+        Ok(()) => Ok(format!("{filled_count} line(s) filled")),
+        Err(e) => Err( format!("{e:?}") )
+    }
+}
+
+pub fn minimize(dir: String, path: String, cite_theorems: bool) -> Result<String, String> {
+    let math_file = get_math_file(&path)?;
+    let (mut lib, mut references) = match read_file(dir.clone() + "/library.math") {
+        Ok((lib, references)) => (lib, references),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let minimized_proofs = match minimize_theo(math_file, &mut lib, &references, cite_theorems) {
+        Ok(minimized_proofs) => minimized_proofs,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let lines = split_proof_lines(&text);
+    let mut proofs = Vec::new();
+    for (line, columns) in &lines {
+        if *line == "# Proof" {
+            proofs.push(Vec::new());
+        } else if let (Some(columns), Some(proof)) = (columns, proofs.last_mut()) {
+            proof.push(columns.iter().map(|part| part.trim()).collect::<Vec<_>>());
+        };
+    };
+    let mut removed_count = 0;
+    let mut cited_count = 0;
+    let mut file_data = String::new();
+    let mut minimized_proofs = minimized_proofs.iter().zip(&proofs);
+    for (line, columns) in &lines {
+        if columns.is_some() {
+            continue;
+        };
+        file_data.push_str(line);
+        file_data.push('\n');
+        if *line != "# Proof" {
+            continue;
+        };
+        let Some((minimized_lines, proof)) = minimized_proofs.next() else {
+            return Err( "The proofs of the file could not be matched".to_string() );
+        };
+        removed_count += proof.len() - minimized_lines.len();
+        // Numbered lines take their new number, named lines keep their name
+        let labels = minimized_lines.iter()
+            .enumerate()
+            .map(|(index, minimized)| match proof[minimized.line][0].parse::<usize>() {
+                Ok(_) => (index + 1).to_string(),
+                Err(_) => proof[minimized.line][0].to_owned()
+            })
+            .collect::<Vec<_>>();
+        for (label, minimized) in labels.iter().zip(minimized_lines) {
+            let used_lines = minimized.used_lines.iter()
+                .map(|used| labels[*used].as_str())
+                .collect::<Vec<_>>()
+                .join(",");
+            let (theo_ref, formula) = match (&minimized.citation, &proof[minimized.line][..]) {
+                (Some((theo_name, formula)), [.., "?"]) => (theo_name.as_str(), formula.as_str()),
+                (Some((theo_name, _)), [.., formula]) => (theo_name.as_str(), *formula),
+                (None, [_, _, theo_ref, formula]) => (*theo_ref, *formula),
+                _ => return Err( "The proofs of the file could not be matched".to_string() )
+            };
+            if minimized.citation.is_some() { cited_count += 1; };
+            file_data.push_str( &format!("{label}; {used_lines}; {theo_ref}; {formula}\n") );
+        };
+    };
+    verify_rewritten(&file_data, "minimized", &mut lib, &mut references)?;
+    match fs::write(&path, file_data) {
+        Ok(()) => Ok(format!("{removed_count} line(s) removed, {cited_count} line(s) replaced by a citation")),
+        Err(e) => Err( format!("{e:?}") )
+    }
+}
+
+pub fn prove(dir: String, path: String, max_depth: usize, max_steps: usize, seconds: u64) -> Result<String, String> {
+    let math_file = get_math_file(&path)?;
+    let (mut lib, mut references) = match read_file(dir.clone() + "/library.math") {
+        Ok((lib, references)) => (lib, references),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let budget = SearchBudget { max_depth, max_steps, deadline: Instant::now() + Duration::from_secs(seconds) };
+    let proof_lines = match prove_theo(math_file, &lib, &references, &budget) {
+        Ok(proof_lines) => proof_lines,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    // The proof of the theorem goes in its "# Proof" section, which is added before the lemmas if missing
+    let mut proof = proof_lines.join("\n");
+    proof.push('\n');
+    let mut proof_written = false;
+    let mut file_data = String::new();
+    for line in text.lines() {
+        if !proof_written && line.starts_with("# Lemma ") {
+            file_data.push_str( &format!("# Proof\n{proof}\n") );
+            proof_written = true;
+        };
+        file_data.push_str(line);
+        file_data.push('\n');
+        if !proof_written && line == "# Proof" {
+            file_data.push_str(&proof);
+            proof_written = true;
+        };
+    };
+    if !proof_written {
+        file_data.push_str( &format!("\n# Proof\n{proof}") );
+    };
+    verify_rewritten(&file_data, "found", &mut lib, &mut references)?;
+    match fs::write(&path, file_data) {
+        Ok(()) => Ok(format!("Proof of {} line(s) found", proof_lines.len())),
+        Err(e) => Err( format!("{e:?}") )
+    }
+}
+
+pub fn saturate(dir: String, path: String, max_depth: usize, max_formulas: usize) -> Result<String, String> {
+    let math_file = get_math_file(&path)?;
+    let (lib, references) = match read_file(dir + "/library.math") {
+        Ok((lib, references)) => (lib, references),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let (lines, interrupted) = match saturate_theo(math_file, &lib, &references, max_depth, max_formulas) {
+        Ok(res) => res,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    // The lines are given by increasing depth, each depth under its own header
+    let mut text = Vec::new();
+    let mut current_depth = None;
+    for (depth, line) in lines {
+        if current_depth != Some(depth) {
+            text.push(format!("# Depth {depth}"));
+            current_depth = Some(depth);
+        };
+        text.push(line);
+    };
+    if interrupted {
+        text.push(format!("Stopped after {max_formulas} formula(s)"));
+    };
+    Ok(text.join("\n"))
+}
+
+pub fn taut(dir: String, formula: String) -> Result<String, String> {
+    let (lib, references) = match read_file(dir + "/library.math") {
+        Ok((lib, references)) => (lib, references),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    match decide_tautology(&formula, &lib, &references) {
+        Ok(Decision::Tautology(text)) => Ok(format!("The formula is a tautology, proven by\n{text}")),
+        Ok(Decision::Falsified(values)) => {
+            let values = values.iter()
+                .map(|(variable, value)| format!("{variable} = {value}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Ok(format!("The formula is not a tautology, it is false for {values}"))
+        },
+        Err(e) => Err( format!("{e:?}") )
+    }
+}
+
+pub fn suggest(dir: String, formula: String) -> Result<String, String> {
+    let (lib, _) = match read_file(dir + "/library.math") {
+        Ok((lib, references)) => (lib, references),
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    let suggestions = match suggest_statements(&formula, &lib) {
+        Ok(suggestions) => suggestions,
+        Err(e) => return Err( format!("{e:?}") )
+    };
+    if suggestions.is_empty() {
+        return Ok("No statement proves the formula".to_owned());
+    };
+    // Each statement is followed by its instantiated hypotheses, indented
+    let lines = suggestions.iter()
+        .flat_map(|suggestion| Some(suggestion.name.clone()).into_iter()
+            .chain(suggestion.hypotheses.iter().map(|hypot| format!("    {hypot}")))
+        )
+        .collect::<Vec<_>>();
+    Ok(lines.join("\n"))
+}
+
 pub fn open_editor(dir: String) -> Result<String, String> {
     Command::new("wezterm")
         .arg("start")
@@ -0,0 +1,138 @@
+use std::collections::{HashMap, hash_map::Entry};
+use crate::parsing::FormulaChar;
+use super::{
+    Sort, Syntax, SyntaxType, Placeholder,
+    repetition_group, has_several_parses
+};
+
+// Variables handed out to the placeholders of the candidate formulas
+struct FreshVariables {
+    wffs: usize,
+    objects: usize,
+    sorted: Vec<usize>
+}
+
+fn fresh_variable(placeholder: &Placeholder, sorts: &[Sort], fresh: &mut FreshVariables) -> Option<FormulaChar> {
+    match placeholder {
+        Placeholder::WellFormedFormula(_) => {
+            fresh.wffs += 1;
+            match fresh.wffs <= 25 {  // '𝛼'..'𝜔'
+                true => Some(FormulaChar::Wff(fresh.wffs - 1)),
+                false => None
+            }
+        },
+        Placeholder::Object(_) => {
+            // '𝑎'..'𝑧' misses its 8th letter, 'ℎ' being elsewhere in unicode
+            let id = match fresh.objects >= 7 {
+                true => fresh.objects + 1,
+                false => fresh.objects
+            };
+            fresh.objects += 1;
+            match id < 26 {
+                true => Some(FormulaChar::Object(id)),
+                false => None
+            }
+        },
+        Placeholder::Sorted(sort, _) => {
+            let c = sorts.get(*sort)?.variables.get(fresh.sorted[*sort])?;
+            fresh.sorted[*sort] += 1;
+            Some(FormulaChar::Char(*c))
+        },
+        Placeholder::LiteralChar(_) | Placeholder::Repetition => None
+    }
+}
+
+// Placeholders sharing a key must be filled identically
+fn placeholder_key(placeholder: &Placeholder) -> Option<(bool, usize)> {
+    match placeholder {
+        Placeholder::WellFormedFormula(id) => Some((true, *id)),
+        Placeholder::Object(id) | Placeholder::Sorted(_, id) => Some((false, *id)),
+        Placeholder::LiteralChar(_) | Placeholder::Repetition => None
+    }
+}
+
+// Writes an application of the syntax with fresh variables, a repetition having two elements,
+//  the placeholder at the given position (and its repetitions) being filled with the given formula
+fn instantiate(
+    syntax: &Syntax, filled: Option<(usize, &Vec<FormulaChar>)>,
+    sorts: &[Sort], fresh: &mut FreshVariables
+) -> Option<Vec<FormulaChar>> {
+    let filled_key = filled.and_then(|(k, _)| placeholder_key(&syntax.formula[k]));
+    let mut values = HashMap::new();
+    let mut res = Vec::new();
+    for placeholder in &syntax.formula {
+        let key = match placeholder {
+            Placeholder::LiteralChar(c) => {
+                res.push(FormulaChar::Char(*c));
+                continue;
+            },
+            Placeholder::Repetition => {
+                let (repeated, _) = repetition_group(&syntax.formula)?;
+                res.push(fresh_variable(&syntax.formula[repeated], sorts, fresh)?);
+                continue;
+            },
+            placeholder => placeholder_key(placeholder)?
+        };
+        let value = match values.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(match filled {
+                Some((_, filling)) if filled_key == Some(key) => filling.clone(),
+                _ => vec![fresh_variable(placeholder, sorts, fresh)?]
+            })
+        };
+        res.extend(value.iter().cloned());
+    };
+    Some(res)
+}
+
+// Whether an application of a syntax of this type can fill the placeholder
+fn accepts(placeholder: &Placeholder, syntax_type: &SyntaxType, bound_objects: &[usize]) -> bool {
+    match (placeholder, syntax_type) {
+        (Placeholder::WellFormedFormula(_), SyntaxType::Formula) => true,
+        (Placeholder::Object(id), SyntaxType::Object) => !bound_objects.contains(id),
+        (Placeholder::Sorted(sort1, id), SyntaxType::Sort(sort2)) => sort1 == sort2 && !bound_objects.contains(id),
+        _ => false
+    }
+}
+
+fn formula_to_string(formula: &[FormulaChar]) -> String {
+    formula.iter()
+        .filter_map(|c| match c {
+            FormulaChar::Char(c) => Some(*c),
+            FormulaChar::Wff(id) => char::from_u32('𝛼' as u32 + *id as u32),
+            FormulaChar::Object(id) => char::from_u32('𝑎' as u32 + *id as u32),
+            FormulaChar::RepetitionChar => Some('…')
+        })
+        .collect()
+}
+
+// Looks for a formula with several parses, the new syntax being the last one of 'syntaxes'.
+// The search is bounded at a nesting depth of 2: the new syntax is applied alone, then nested once inside
+//  or around every syntax (itself included), which exposes overlapping prefixes and suffixes.
+// Finding no example doesn't prove the syntaxes unambiguous, deeper formulas may still have several parses
+pub fn find_ambiguous_example(sorts: &[Sort], syntaxes: &Vec<Syntax>) -> Option<String> {
+    let new_id = syntaxes.len().checked_sub(1)?;
+    let new_fresh = || FreshVariables { wffs: 0, objects: 0, sorted: vec![0; sorts.len()] };
+    let mut candidates = Vec::new();
+    candidates.extend(instantiate(&syntaxes[new_id], None, sorts, &mut new_fresh()));
+    for (outer_id, outer) in syntaxes.iter().enumerate() {
+        for (inner_id, inner) in syntaxes.iter().enumerate() {
+            if outer_id != new_id && inner_id != new_id {
+                continue;
+            };
+            for (k, placeholder) in outer.formula.iter().enumerate() {
+                if !accepts(placeholder, &inner.syntax_type, &outer.bound_objects) {
+                    continue;
+                };
+                let mut fresh = new_fresh();
+                let Some(filling) = instantiate(inner, None, sorts, &mut fresh) else {
+                    continue;
+                };
+                candidates.extend(instantiate(outer, Some((k, &filling)), sorts, &mut fresh));
+            };
+        };
+    };
+    candidates.into_iter()
+        .find(|candidate| has_several_parses(candidate.clone(), sorts, syntaxes))
+        .map(|candidate| formula_to_string(&candidate))
+}
@@ -0,0 +1,147 @@
+use super::{
+    Syntax,
+    WellFormedFormula, Object,
+    Substitution
+};
+
+// The ids of the object variables with an occurrence in the formula outside of the binders of these variables
+pub fn free_variables(wff: &WellFormedFormula, syntaxes: &Vec<Syntax>) -> Vec<usize> {
+    let mut variables = Vec::new();
+    collect_free_wff_variables(wff, syntaxes, &mut variables);
+    variables.sort();
+    variables.dedup();
+    variables
+}
+
+// The ids of the object variables with an occurrence in the object outside of the binders of these variables
+pub fn object_free_variables(obj: &Object, syntaxes: &Vec<Syntax>) -> Vec<usize> {
+    let mut variables = Vec::new();
+    collect_free_object_variables(obj, syntaxes, &mut variables);
+    variables.sort();
+    variables.dedup();
+    variables
+}
+
+fn collect_free_wff_variables(wff: &WellFormedFormula, syntaxes: &Vec<Syntax>, variables: &mut Vec<usize>) {
+    match wff {
+        WellFormedFormula::Atomic(_) => (),
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => collect_free_parameter_variables(
+            &syntaxes[*syntax_ref], wff_parameters, object_parameters, syntaxes, variables
+        )
+    }
+}
+
+fn collect_free_object_variables(obj: &Object, syntaxes: &Vec<Syntax>, variables: &mut Vec<usize>) {
+    match obj {
+        Object::Atomic(id) | Object::SortedAtomic(_, id) => variables.push(*id),
+        Object::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => collect_free_parameter_variables(
+            &syntaxes[*syntax_ref], wff_parameters, object_parameters, syntaxes, variables
+        )
+    }
+}
+
+fn collect_free_parameter_variables(
+    syntax: &Syntax, wff_parameters: &[WellFormedFormula], object_parameters: &[Object],
+    syntaxes: &Vec<Syntax>, variables: &mut Vec<usize>
+) {
+    let mut inner_variables = Vec::new();
+    for param_wff in wff_parameters {
+        collect_free_wff_variables(param_wff, syntaxes, &mut inner_variables);
+    };
+    for (id, param_obj) in object_parameters.iter().enumerate() {
+        if syntax.bound_objects.contains(&id) { continue; };
+        collect_free_object_variables(param_obj, syntaxes, &mut inner_variables);
+    };
+    let bound_variables = syntax.bound_objects.iter()
+        .filter_map(|id| match object_parameters.get(*id) {
+            Some(Object::Atomic(var) | Object::SortedAtomic(_, var)) => Some(*var),
+            _ => None
+        })
+        .collect::<Vec<_>>();
+    variables.extend(
+        inner_variables.into_iter().filter(|var| !bound_variables.contains(var))
+    );
+}
+
+// Whether applying the substitution to the pattern would put a free variable
+//  of a substituted object under a binder of the pattern
+pub fn substitution_captures(
+    pattern: &WellFormedFormula, substitution: &Substitution, syntaxes: &Vec<Syntax>
+) -> bool {
+    wff_captures(pattern, substitution, syntaxes, &mut Vec::new())
+}
+
+// 'binders' holds the (pattern variable, substituted variable) pairs of the binders in scope
+fn wff_captures(
+    pattern: &WellFormedFormula, substitution: &Substitution,
+    syntaxes: &Vec<Syntax>, binders: &mut Vec<(usize, usize)>
+) -> bool {
+    match pattern {
+        WellFormedFormula::Atomic(_) => false,
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => parameters_capture(
+            &syntaxes[*syntax_ref], wff_parameters, object_parameters, substitution, syntaxes, binders
+        )
+    }
+}
+
+fn object_captures(
+    pattern: &Object, substitution: &Substitution,
+    syntaxes: &Vec<Syntax>, binders: &mut Vec<(usize, usize)>
+) -> bool {
+    match pattern {
+        Object::Atomic(id) | Object::SortedAtomic(_, id) => {
+            if binders.iter().any(|(pattern_var, _)| pattern_var == id) {
+                return false;  // The variable is bound in the pattern itself
+            };
+            let Some(Some(obj)) = substitution.objects.get(*id) else {
+                return false;
+            };
+            let free = object_free_variables(obj, syntaxes);
+            binders.iter().any(|(_, substituted_var)| free.contains(substituted_var))
+        },
+        Object::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => parameters_capture(
+            &syntaxes[*syntax_ref], wff_parameters, object_parameters, substitution, syntaxes, binders
+        )
+    }
+}
+
+fn parameters_capture(
+    syntax: &Syntax, wff_parameters: &[WellFormedFormula], object_parameters: &[Object],
+    substitution: &Substitution, syntaxes: &Vec<Syntax>, binders: &mut Vec<(usize, usize)>
+) -> bool {
+    let scope_start = binders.len();
+    for id in &syntax.bound_objects {
+        let Some(Object::Atomic(pattern_var) | Object::SortedAtomic(_, pattern_var)) = object_parameters.get(*id) else {
+            return true;
+        };
+        match substitution.objects.get(*pattern_var) {
+            Some(Some(Object::Atomic(substituted_var) | Object::SortedAtomic(_, substituted_var))) => {
+                binders.push((*pattern_var, *substituted_var))
+            },
+            _ => return true  // A bound variable can only be replaced by a variable
+        };
+    };
+    let captures = wff_parameters.iter()
+        .any(|param_wff| wff_captures(param_wff, substitution, syntaxes, binders))
+        || object_parameters.iter().enumerate()
+            .filter(|(id, _)| !syntax.bound_objects.contains(id))
+            .any(|(_, param_obj)| object_captures(param_obj, substitution, syntaxes, binders));
+    binders.truncate(scope_start);
+    captures
+}
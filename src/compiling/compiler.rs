@@ -1,9 +1,19 @@
 use std::{fs, io, collections::HashMap};
-use crate::parsing::{parse_file, MathFile};
+use crate::parsing::{parse_file, parse_formula, parse_proof_line, MathFile};
 use super::{
     Reference,
-    compile_syntax, compile_definition, compile_axiom, compile_theorem,
-    CompileError,
+    compile_deduction_recipe,
+    Theorem, WrittenLine,
+    compile_sort, compile_syntax, compile_definition, compile_axiom, compile_theorem,
+    wff_to_string,
+    MinimizedLine, find_citation, minimize_proof,
+    GoalTree, new_goal_tree, export_proof, append_proof,
+    SearchBudget, search_proof,
+    saturate,
+    Decision, decide_formula,
+    Suggestion, matching_statements,
+    compile_formula, variable_names,
+    CompileError, CompileWarning,
     Library
 };
 
@@ -15,14 +25,27 @@ fn get_file_contents(dir: &mut String, filepath: &str) -> io::Result<String> {
     Ok(contents)
 }
 
+pub fn add_sort_to_lib(math_file: MathFile, lib: &mut Library) -> Result<(), CompileError> {
+    let sort = compile_sort(math_file, &lib.sorts)?;
+    lib.sorts.push(sort);
+    Ok(())
+}
+
 pub fn add_syndef_to_lib(
     math_file: MathFile, lib: &mut Library, references: &mut HashMap<String, Reference>
 ) -> Result<(), CompileError> {
-    let (syntax, maybe_def) = compile_syntax(math_file, &lib.syntaxes)?;
+    let (syntax, maybe_def) = compile_syntax(math_file, &lib.sorts, &lib.syntaxes)?;
     lib.syntaxes.push(syntax);
     match maybe_def {
         Some((name, def)) => {
-            let def = compile_definition(name, def, &lib.syntaxes)?;
+            // A refused definition leaves the library without its syntax
+            let def = match compile_definition(name, def, &lib.sorts, &lib.syntaxes) {
+                Ok(def) => def,
+                Err(e) => {
+                    lib.syntaxes.pop();
+                    return Err(e);
+                }
+            };
             let def_ref = Reference::DefinitionReference(lib.definitions.len());
             references.insert(def.name.clone(), def_ref);
             lib.definitions.push(def);
@@ -35,53 +58,341 @@ pub fn add_syndef_to_lib(
 pub fn add_axiom_to_lib(
     math_file: MathFile, lib: &mut Library, references: &mut HashMap<String, Reference>
 ) -> Result<(), CompileError> {
-    let axiom = compile_axiom(math_file, &lib.syntaxes)?;
+    let axiom = compile_axiom(math_file, &lib.sorts, &lib.syntaxes)?;
     let axiom_ref = Reference::AxiomReference(lib.axioms.len(), 0);
     references.insert(axiom.name.clone(), axiom_ref);
     lib.axioms.push(axiom);
     Ok(())
 }
 
+// The number of theorems of the library the named theorem can cite: a theorem of the library can only cite
+//  the ones before it, and another theorem all of them
+fn citable_theorems(name: &str, lib: &Library, references: &HashMap<String, Reference>) -> usize {
+    match references.get(name) {
+        Some(Reference::TheoremReference(theo_id, _)) => *theo_id,
+        _ => lib.theorems.len()
+    }
+}
+
+// Compiles the theorem with the library, the lemmas of its file being added to the library's
+fn compile_library_theorem(
+    math_file: MathFile, lib: &mut Library, references: &HashMap<String, Reference>, warnings: &mut Vec<CompileWarning>
+) -> Result<(Theorem, Vec<Vec<WrittenLine>>), CompileError> {
+    let mut lemmas = std::mem::take(&mut lib.lemmas);
+    let compiled = compile_theorem(math_file, lib, &mut lemmas, references, warnings);
+    lib.lemmas = lemmas;
+    compiled
+}
+
 pub fn add_theo_to_lib(
     math_file: MathFile, lib: &mut Library, references: &mut HashMap<String, Reference>
-) -> Result<(), CompileError> {
-    let theorem = compile_theorem(
-        math_file, &lib.syntaxes, &lib.definitions, &lib.axioms, &lib.theorems, &references
-    )?;
+) -> Result<Vec<CompileWarning>, CompileError> {
+    let mut warnings = Vec::new();
+    let (theorem, _) = compile_library_theorem(math_file, lib, references, &mut warnings)?;
     let theo_ref = Reference::TheoremReference(lib.theorems.len(), 0);
     references.insert(theorem.name.clone(), theo_ref);
     lib.theorems.push(theorem);
+    Ok(warnings)
+}
+
+const DEDUCTION_ROLES: [&str; 3] = ["modus ponens", "weakening", "distribution"];
+
+// Designates the statements of the "# Deduction" section, given by role with their line in the order file
+pub fn add_deduction_to_lib(
+    roles: &HashMap<String, (String, usize)>, lib: &mut Library, references: &HashMap<String, Reference>
+) -> Result<(), CompileError> {
+    let mut statements = Vec::new();
+    for role in DEDUCTION_ROLES {
+        let Some((name, line_no)) = roles.get(role) else {
+            return Err(CompileError::IncompleteDeductionRecipe);
+        };
+        match references.get(name) {
+            Some(Reference::AxiomReference(id, 0)) => statements.push((Reference::AxiomReference(*id, 0), name.as_str())),
+            Some(Reference::TheoremReference(id, 0)) => statements.push((Reference::TheoremReference(*id, 0), name.as_str())),
+            _ => return Err(CompileError::UnknownTheorem(name.clone(), *line_no))
+        };
+    };
+    let [(modus_ponens, mp_name), (weakening, k_name), (distribution, s_name)]: [_; 3] = statements.try_into()
+        .map_err(|_| CompileError::ShouldNotBeReached)?;
+    let recipe = compile_deduction_recipe(
+        (modus_ponens, weakening, distribution), (mp_name, k_name, s_name),
+        &lib.syntaxes, &lib.definitions, &lib.axioms, &lib.theorems
+    )?;
+    lib.deduction = Some(recipe);
     Ok(())
 }
 
 pub fn verify_theo(
     math_file: MathFile, lib: &mut Library, references: &mut HashMap<String, Reference>
-) -> Result<(), CompileError> {
-    let compilation_result = compile_theorem(
-        math_file, &lib.syntaxes, &lib.definitions, &lib.axioms, &lib.theorems, &references
-    );
+) -> Result<Vec<CompileWarning>, CompileError> {
+    let mut warnings = Vec::new();
+    let compilation_result = compile_library_theorem(math_file, lib, references, &mut warnings);
     match compilation_result {
-        Ok(_) => Ok(()),
+        Ok(_) => Ok(warnings),
         Err(e) => Err(e)
     }
 }
 
+// Writes the formula of every proof line as written, inferred ones included, the lines of the lemmas
+//  following the theorem's
+pub fn infer_theo(
+    math_file: MathFile, lib: &mut Library, references: &HashMap<String, Reference>
+) -> Result<Vec<String>, CompileError> {
+    let first_lemma = lib.lemmas.len();
+    let (theorem, written_lines) = compile_library_theorem(math_file, lib, references, &mut Vec::new())?;
+    let formulas = Some(&theorem).into_iter()
+        .chain(&lib.lemmas[first_lemma..])
+        .zip(&written_lines)
+        .flat_map(|(statement, lines)| lines.iter()
+            .map(|(_, formula)| wff_to_string(formula, &lib.syntaxes, &statement.variable_names))
+        )
+        .collect();
+    Ok(formulas)
+}
+
+// Writes the used lines of every proof line, found ones included, the lines of the lemmas following the theorem's
+pub fn fixup_theo(
+    math_file: MathFile, lib: &mut Library, references: &HashMap<String, Reference>
+) -> Result<Vec<String>, CompileError> {
+    let (_, written_lines) = compile_library_theorem(math_file, lib, references, &mut Vec::new())?;
+    let used_lines = written_lines.iter()
+        .flat_map(|lines| lines.iter()
+            .map(|(used_lines, _)| used_lines.iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+            )
+        )
+        .collect();
+    Ok(used_lines)
+}
+
+// The minimized proofs of the theorem then of its lemmas: duplicate lines are merged and the lines no assertion
+//  relies on are dropped. With 'cite_theorems', a line with a subproof is proven instead by citing a preceding
+//  theorem of the library, if one proves its formula without hypotheses
+pub fn minimize_theo(
+    math_file: MathFile, lib: &mut Library, references: &HashMap<String, Reference>, cite_theorems: bool
+) -> Result<Vec<Vec<MinimizedLine>>, CompileError> {
+    let MathFile::Theorem { name, proof, lemmas, .. } = &math_file else {
+        return Err(CompileError::IncorrectFileType);
+    };
+    let lemma_proofs = lemmas.iter().filter_map(|lemma| match lemma {
+        MathFile::Theorem { proof, .. } => Some(proof),
+        _ => None
+    });
+    let written_references = Some(proof).into_iter()
+        .chain(lemma_proofs)
+        .map(|proof| proof.iter()
+            .map(|(_, _, theorem_name, _, _)| theorem_name.clone())
+            .collect::<Vec<_>>()
+        )
+        .collect::<Vec<_>>();
+    let cited_theorems = citable_theorems(name, lib, references);
+    let first_lemma = lib.lemmas.len();
+    let (theorem, written_lines) = compile_library_theorem(math_file, lib, references, &mut Vec::new())?;
+    let statements = Some(&theorem).into_iter().chain(&lib.lemmas[first_lemma..]);
+    let mut minimized_proofs = Vec::new();
+    for ((statement, written_lines), theorem_names) in statements.zip(written_lines).zip(written_references) {
+        // A statement proven by tactics has no "# Proof" section to minimize
+        if theorem_names.is_empty() {
+            continue;
+        };
+        // The used lines as indices, found ones included
+        let (lines, formulas): (Vec<_>, Vec<_>) = written_lines.into_iter()
+            .zip(theorem_names)
+            .map(|((used_lines, formula), theorem_name)| {
+                let used_lines = used_lines.iter().filter_map(|line| line.checked_sub(1)).collect::<Vec<_>>();
+                ((used_lines, theorem_name), formula)
+            })
+            .unzip();
+        let citations = formulas.iter()
+            .zip(&lines)
+            .map(|(formula, (used_lines, _))| match cite_theorems && !used_lines.is_empty() {
+                true => find_citation(formula, &lib.theorems[..cited_theorems], &lib.syntaxes, statement)
+                    .map(|theo_name| (theo_name, wff_to_string(formula, &lib.syntaxes, &statement.variable_names))),
+                false => None
+            })
+            .collect::<Vec<_>>();
+        minimized_proofs.push(minimize_proof(&lines, &formulas, &statement.assertions, &citations));
+    };
+    Ok(minimized_proofs)
+}
+
+// The lines proving each assertion of a theorem without proof, found by a bounded search over its hypotheses,
+//  the axioms and the theorems of the library. A proof is only kept if it compiles
+pub fn prove_theo(
+    math_file: MathFile, lib: &Library, references: &HashMap<String, Reference>, budget: &SearchBudget
+) -> Result<Vec<String>, CompileError> {
+    let MathFile::Theorem { name, hypotheses, assertions, distinct_variables, proof, tactics, lemmas: _ } = math_file else {
+        return Err(CompileError::IncorrectFileType);
+    };
+    if !proof.is_empty() || !tactics.is_empty() {
+        return Err(CompileError::AlreadyProven);
+    };
+    let statement = compile_axiom(
+        MathFile::Axiom {
+            name: name.clone(),
+            hypotheses: hypotheses.iter().map(|(_, hypot)| hypot.clone()).collect(),
+            assertions: assertions.clone(),
+            distinct_variables: distinct_variables.clone()
+        },
+        &lib.sorts, &lib.syntaxes
+    )?;
+    let cited_theorems = citable_theorems(&name, lib, references);
+    let statements = lib.axioms.iter()
+        .map(|ax| (&ax.name, ax.assertions.len()))
+        .chain(lib.theorems[..cited_theorems].iter().map(|theo| (&theo.name, theo.assertions.len())));
+    let candidates = hypotheses.iter()
+        .map(|(hypot_name, _)| hypot_name.clone())
+        .chain(statements.flat_map(|(statement_name, assertion_count)| (0..assertion_count)
+            .map(move |assert_id| match assert_id {
+                0 => statement_name.clone(),
+                _ => format!("{statement_name}.{assert_id}")
+            })
+        ))
+        .collect::<Vec<_>>();
+    let mut lines = Vec::new();
+    for (assert_id, assertion) in statement.assertions.iter().enumerate() {
+        let named_hypotheses = hypotheses.iter()
+            .map(|(hypot_name, _)| hypot_name.clone())
+            .zip(statement.hypotheses.iter().cloned())
+            .collect();
+        // The proof must compile as a proof of this assertion alone
+        let mut accept = |tree: &GoalTree| {
+            let Ok(proof_lines) = export_proof(tree, &lib.syntaxes, &statement.variable_names) else {
+                return false;
+            };
+            let Ok(proof) = proof_lines.iter().map(|line| parse_proof_line(line)).collect() else {
+                return false;
+            };
+            let file = MathFile::Theorem {
+                name: name.clone(),
+                hypotheses: hypotheses.clone(),
+                assertions: vec![assertions[assert_id].clone()],
+                distinct_variables: distinct_variables.clone(),
+                proof,
+                tactics: Vec::new(),
+                lemmas: Vec::new()
+            };
+            compile_theorem(file, lib, &mut Vec::new(), references, &mut Vec::new()).is_ok()
+        };
+        let tree = new_goal_tree(named_hypotheses, assertion.clone());
+        let Some(proven) = search_proof(tree, &candidates, lib, references, budget, &mut accept) else {
+            return Err(CompileError::ProofNotFound(assert_id));
+        };
+        append_proof(&proven, &lib.syntaxes, &statement.variable_names, &mut lines)?;
+    };
+    Ok(lines)
+}
+
+// The formulas derived from the hypotheses of a theorem by applying the axioms and the theorems of the library,
+//  as proof lines with their depth. Also tells whether the derivation stopped because of 'max_formulas'
+pub fn saturate_theo(
+    math_file: MathFile, lib: &Library, references: &HashMap<String, Reference>, max_depth: usize, max_formulas: usize
+) -> Result<(Vec<(usize, String)>, bool), CompileError> {
+    let MathFile::Theorem { name, hypotheses, assertions, distinct_variables, .. } = math_file else {
+        return Err(CompileError::IncorrectFileType);
+    };
+    let statement = compile_axiom(
+        MathFile::Axiom {
+            name: name.clone(),
+            hypotheses: hypotheses.iter().map(|(_, hypot)| hypot.clone()).collect(),
+            assertions,
+            distinct_variables
+        },
+        &lib.sorts, &lib.syntaxes
+    )?;
+    let cited_theorems = citable_theorems(&name, lib, references);
+    let axioms = lib.axioms.iter()
+        .map(|ax| (&ax.name, &ax.hypotheses, &ax.assertions, &ax.distinct_variables, ax.distinct_wff_count, ax.distinct_object_count));
+    let theorems = lib.theorems[..cited_theorems].iter()
+        .map(|theo| (&theo.name, &theo.hypotheses, &theo.assertions, &theo.distinct_variables, theo.distinct_wff_count, theo.distinct_object_count));
+    let statements = axioms.chain(theorems)
+        .flat_map(|(statement_name, hypots, asserts, distinct, wff_count, object_count)| asserts.iter()
+            .enumerate()
+            .map(move |(assert_id, assertion)| {
+                let cited_name = match assert_id {
+                    0 => statement_name.clone(),
+                    _ => format!("{statement_name}.{assert_id}")
+                };
+                (cited_name, (hypots, assertion, distinct, wff_count, object_count))
+            })
+        )
+        .collect();
+    let named_hypotheses = hypotheses.into_iter()
+        .map(|(hypot_name, _)| hypot_name)
+        .zip(statement.hypotheses.iter().cloned())
+        .collect();
+    let (derived, interrupted) = saturate(
+        named_hypotheses, &statements, &statement.distinct_variables,
+        (statement.distinct_wff_count, statement.distinct_object_count), &lib.syntaxes, max_depth, max_formulas
+    );
+    let lines = derived.iter()
+        .enumerate()
+        .map(|(line, derived_formula)| {
+            let used_lines = derived_formula.used_formulas.iter()
+                .map(|used| (used + 1).to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            let formula = wff_to_string(&derived_formula.formula, &lib.syntaxes, &statement.variable_names);
+            (derived_formula.depth, format!("{}; {used_lines}; {}; {formula}", line + 1, derived_formula.reference))
+        })
+        .collect();
+    Ok((lines, interrupted))
+}
+
+// Decides whether the formula is a tautology, a generated proof being only given once it compiles
+pub fn decide_tautology(
+    formula: &str, lib: &Library, references: &HashMap<String, Reference>
+) -> Result<Decision, CompileError> {
+    let mut wffs = HashMap::new();
+    let mut objects = HashMap::new();
+    let wff = compile_formula(parse_formula(formula), &lib.sorts, &lib.syntaxes, &mut wffs, &mut objects)?;
+    let decision = decide_formula(&wff, &variable_names(&wffs, &objects), lib, references)?;
+    if let Decision::Tautology(text) = &decision {
+        let Ok(math_file) = parse_file(text.clone()) else {
+            return Err(CompileError::ShouldNotBeReached);
+        };
+        compile_theorem(math_file, lib, &mut Vec::new(), references, &mut Vec::new())?;
+    };
+    Ok(decision)
+}
+
+// The statements of the library whose assertion proves the formula, with their hypotheses instantiated
+pub fn suggest_statements(formula: &str, lib: &Library) -> Result<Vec<Suggestion>, CompileError> {
+    let mut wffs = HashMap::new();
+    let mut objects = HashMap::new();
+    let wff = compile_formula(parse_formula(formula), &lib.sorts, &lib.syntaxes, &mut wffs, &mut objects)?;
+    Ok(matching_statements(
+        &wff, &variable_names(&wffs, &objects), (wffs.len(), objects.len()), &lib.syntaxes,
+        &lib.definitions, &lib.axioms, &lib.theorems
+    ))
+}
+
 enum CompilerState {
+    CompilingSorts,
     CompilingSyntaxes,
     CompilingAxioms,
+    DesignatingDeduction,
     CompilingTheorems,
     Waiting
 }
 
-pub fn compile_directory(mut dir: String) -> Result<Library, CompileError> {
+// Also gives the warnings about the proofs of the theorems
+pub fn compile_directory(mut dir: String) -> Result<(Library, Vec<CompileWarning>), CompileError> {
     let mut lib = Library {
+        sorts: Vec::new(),
         syntaxes: Vec::new(),
         definitions: Vec::new(),
         axioms: Vec::new(),
-        theorems: Vec::new()
+        theorems: Vec::new(),
+        lemmas: Vec::new(),
+        deduction: None
     };
     let mut references = HashMap::new();
     let mut state = CompilerState::Waiting;
+    let mut deduction_roles = HashMap::new();
+    let mut warnings = Vec::new();
     let Ok(order) = get_file_contents(&mut dir, "/order.txt") else {
         return Err(CompileError::OrderFileNotFound);
     };
@@ -89,7 +400,11 @@ pub fn compile_directory(mut dir: String) -> Result<Library, CompileError> {
         if line == "" { continue; };
         if line.starts_with('#') {
             match (state, line) {
-                (CompilerState::Waiting, "# Syntax Definitions") => {
+                (CompilerState::Waiting, "# Sorts") => {
+                    state = CompilerState::CompilingSorts;
+                    continue;
+                },
+                (CompilerState::Waiting | CompilerState::CompilingSorts, "# Syntax Definitions") => {
                     state = CompilerState::CompilingSyntaxes;
                     continue;
                 },
@@ -97,13 +412,35 @@ pub fn compile_directory(mut dir: String) -> Result<Library, CompileError> {
                     state = CompilerState::CompilingAxioms;
                     continue;
                 },
+                (CompilerState::CompilingAxioms, "# Deduction") => {
+                    state = CompilerState::DesignatingDeduction;
+                    continue;
+                },
                 (CompilerState::CompilingAxioms, "# Theorems") => {
                     state = CompilerState::CompilingTheorems;
                     continue;
                 },
+                (CompilerState::DesignatingDeduction, "# Theorems") => {
+                    add_deduction_to_lib(&deduction_roles, &mut lib, &references)?;
+                    state = CompilerState::CompilingTheorems;
+                    continue;
+                },
                 _ => return Err(CompileError::InvalidOrderLine(line.to_owned(), line_no+1))
             }
         };
+        // "<role>: <axiom or theorem>"
+        if let CompilerState::DesignatingDeduction = state {
+            let role = match line.split_once(": ") {
+                Some((role, name)) if DEDUCTION_ROLES.contains(&role) => {
+                    deduction_roles.insert(role.to_owned(), (name.trim().to_owned(), line_no+1))
+                },
+                _ => return Err(CompileError::InvalidOrderLine(line.to_owned(), line_no+1))
+            };
+            if role.is_some() {
+                return Err(CompileError::InvalidOrderLine(line.to_owned(), line_no+1));
+            };
+            continue;
+        };
         let file_content = get_file_contents(&mut dir, line)
             .map_err(|e| CompileError::IOError(e, line.to_string(), line_no+1))?;
         let Ok(math_file) = parse_file(file_content) else {
@@ -111,16 +448,23 @@ pub fn compile_directory(mut dir: String) -> Result<Library, CompileError> {
         };
         match state {
             CompilerState::Waiting => (),
+            CompilerState::CompilingSorts => {
+                add_sort_to_lib(math_file, &mut lib)?;
+            },
             CompilerState::CompilingSyntaxes => {
                 add_syndef_to_lib(math_file, &mut lib, &mut references)?;
             },
             CompilerState::CompilingAxioms => {
                 add_axiom_to_lib(math_file, &mut lib, &mut references)?;
             },
+            CompilerState::DesignatingDeduction => (),
             CompilerState::CompilingTheorems => {
-                add_theo_to_lib(math_file, &mut lib, &mut references)?;
+                warnings.append(&mut add_theo_to_lib(math_file, &mut lib, &mut references)?);
             }
         };
     };
-    Ok(lib)
+    if let CompilerState::DesignatingDeduction = state {
+        add_deduction_to_lib(&deduction_roles, &mut lib, &references)?;
+    };
+    Ok((lib, warnings))
 }
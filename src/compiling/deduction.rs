@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use super::{
+    Syntax, SyntaxType, Definition, Axiom, Theorem,
+    Reference, LogicStep, StepKind, DeductionRecipe,
+    WellFormedFormula,
+    cited_statement,
+    find_wff_substitutions,
+    CompileError
+};
+
+pub fn implication(recipe: &DeductionRecipe, antecedent: WellFormedFormula, consequent: WellFormedFormula) -> WellFormedFormula {
+    WellFormedFormula::SyntaxComposite {
+        syntax_ref: recipe.implication,
+        wff_parameters: vec![antecedent, consequent],
+        object_parameters: Vec::new()
+    }
+}
+
+// Whether the formula is the pattern with its variables replaced by distinct variables
+pub fn is_renaming(pattern: &WellFormedFormula, formula: &WellFormedFormula, variable_count: usize) -> bool {
+    let mut wff_mapping = vec![None; variable_count];
+    if find_wff_substitutions(pattern, formula, &mut wff_mapping, &mut Vec::new()).is_err() {
+        return false;
+    };
+    let mut ids = wff_mapping.into_iter()
+        .map(|wff| match wff {
+            Some(WellFormedFormula::Atomic(id)) => Some(*id),
+            _ => None
+        })
+        .collect::<Option<Vec<_>>>();
+    match &mut ids {
+        Some(ids) => {
+            ids.sort();
+            ids.windows(2).all(|pair| pair[0] != pair[1])
+        },
+        None => false
+    }
+}
+
+// Checks the shapes of the designated statements, the implication being the syntax used by modus ponens
+pub fn compile_deduction_recipe(
+    (modus_ponens, weakening, distribution): (Reference, Reference, Reference), names: (&str, &str, &str),
+    syntaxes: &[Syntax], definitions: &[Definition], axioms: &[Axiom], theorems: &[Theorem]
+) -> Result<DeductionRecipe, CompileError> {
+    let statement = |reference: &Reference, name: &str| {
+        match cited_statement(reference, definitions, axioms, theorems, &Vec::new()) {
+            Some((hypotheses, assertion, distinct_variables, _, _)) if distinct_variables.is_empty() => {
+                Ok((hypotheses.clone(), assertion.clone()))
+            },
+            _ => Err(CompileError::InvalidDeductionRecipe(name.to_owned()))
+        }
+    };
+    let (mp_hypotheses, mp_assertion) = statement(&modus_ponens, names.0)?;
+    let implication_ref = match &mp_hypotheses[..] {
+        [_, WellFormedFormula::SyntaxComposite { syntax_ref, .. }] => *syntax_ref,
+        _ => return Err(CompileError::InvalidDeductionRecipe(names.0.to_owned()))
+    };
+    let valid_implication = matches!(syntaxes[implication_ref].syntax_type, SyntaxType::Formula)
+        && syntaxes[implication_ref].distinct_wff_count == 2
+        && syntaxes[implication_ref].distinct_object_count == 0;
+    if !valid_implication {
+        return Err(CompileError::InvalidDeductionRecipe(names.0.to_owned()));
+    };
+    let recipe = DeductionRecipe { implication: implication_ref, modus_ponens, weakening, distribution };
+    let var = |id: usize| WellFormedFormula::Atomic(id);
+    let imp = |antecedent, consequent| implication(&recipe, antecedent, consequent);
+    // Modus ponens, its hypotheses being in this order
+    let valid_mp = is_renaming(&imp(var(0), var(1)), &mp_hypotheses[1], 2)
+        && mp_hypotheses[0] == imp_antecedent(&mp_hypotheses[1])
+        && mp_assertion == imp_consequent(&mp_hypotheses[1]);
+    if !valid_mp {
+        return Err(CompileError::InvalidDeductionRecipe(names.0.to_owned()));
+    };
+    let (k_hypotheses, k_assertion) = statement(&recipe.weakening, names.1)?;
+    if !k_hypotheses.is_empty() || !is_renaming(&imp(var(0), imp(var(1), var(0))), &k_assertion, 2) {
+        return Err(CompileError::InvalidDeductionRecipe(names.1.to_owned()));
+    };
+    let (s_hypotheses, s_assertion) = statement(&recipe.distribution, names.2)?;
+    let s_pattern = imp(
+        imp(var(0), imp(var(1), var(2))),
+        imp(imp(var(0), var(1)), imp(var(0), var(2)))
+    );
+    if !s_hypotheses.is_empty() || !is_renaming(&s_pattern, &s_assertion, 3) {
+        return Err(CompileError::InvalidDeductionRecipe(names.2.to_owned()));
+    };
+    Ok(recipe)
+}
+
+fn imp_antecedent(wff: &WellFormedFormula) -> WellFormedFormula {
+    match wff {
+        WellFormedFormula::SyntaxComposite { wff_parameters, .. } => wff_parameters[0].clone(),
+        WellFormedFormula::Atomic(_) => wff.clone()
+    }
+}
+
+pub fn imp_consequent(wff: &WellFormedFormula) -> WellFormedFormula {
+    match wff {
+        WellFormedFormula::SyntaxComposite { wff_parameters, .. } => wff_parameters[1].clone(),
+        WellFormedFormula::Atomic(_) => wff.clone()
+    }
+}
+
+// The lines used by a step, the proven relation of a "rw" included
+fn step_uses(step: &LogicStep) -> Vec<usize> {
+    let mut uses = step.used_hypotheses.clone();
+    if let (StepKind::Rewrite, Reference::LineReference(line)) = (step.kind, &step.theorem_ref) {
+        uses.push(*line);
+    };
+    uses
+}
+
+fn renumber_step(step: &mut LogicStep, renumber: &dyn Fn(usize) -> usize) {
+    for line in step.used_hypotheses.iter_mut() {
+        *line = renumber(*line);
+    };
+    if let (StepKind::Rewrite, Reference::LineReference(line)) = (step.kind, &mut step.theorem_ref) {
+        *line = renumber(*line);
+    };
+}
+
+// The steps replacing a block, placed from the index 'first' of the proof, each with its line in the source proof
+struct BlockExpansion<'a> {
+    recipe: &'a DeductionRecipe,
+    assumption: WellFormedFormula,
+    first: usize,
+    steps: Vec<LogicStep>,
+    origins: Vec<usize>,
+    plain: HashMap<usize, usize>,  // Lines of the block which don't depend on the assumption, by their new index
+    lifted: HashMap<usize, usize>  // New indexes of the proofs of "(<assumption> ⇒ <line>)"
+}
+
+fn push_step(
+    expansion: &mut BlockExpansion, used_hypotheses: Vec<usize>, theorem_ref: Reference,
+    resulting_formula: WellFormedFormula, origin: usize
+) -> usize {
+    expansion.steps.push(LogicStep { used_hypotheses, theorem_ref, kind: StepKind::Assertion, resulting_formula });
+    expansion.origins.push(origin);
+    expansion.first + expansion.steps.len() - 1
+}
+
+// Proves "(<assumption> ⇒ <assumption>)"
+fn push_identity(expansion: &mut BlockExpansion, origin: usize) -> usize {
+    let recipe = expansion.recipe;
+    let a = expansion.assumption.clone();
+    let a_a = implication(recipe, a.clone(), a.clone());
+    let a_aa_a = implication(recipe, a.clone(), implication(recipe, a_a.clone(), a.clone()));
+    let a_a_a = implication(recipe, a.clone(), a_a.clone());
+    let k1 = push_step(expansion, vec![], recipe.weakening.clone(), a_aa_a.clone(), origin);
+    let distributed = implication(recipe, a_a_a.clone(), a_a.clone());
+    let s = push_step(
+        expansion, vec![], recipe.distribution.clone(), implication(recipe, a_aa_a, distributed.clone()), origin
+    );
+    let mp1 = push_step(expansion, vec![k1, s], recipe.modus_ponens.clone(), distributed, origin);
+    let k2 = push_step(expansion, vec![], recipe.weakening.clone(), a_a_a, origin);
+    push_step(expansion, vec![k2, mp1], recipe.modus_ponens.clone(), a_a, origin)
+}
+
+// The new index of the proof of "(<assumption> ⇒ <line>)", weakening the line if it doesn't depend on the assumption,
+//  the first line being the assumption
+fn lifted_line(expansion: &mut BlockExpansion, proof: &[LogicStep], line: usize, origin: usize) -> usize {
+    if let Some(&lifted) = expansion.lifted.get(&line) {
+        return lifted;
+    };
+    // The assumption itself
+    if line == expansion.first {
+        let identity = push_identity(expansion, origin);
+        expansion.lifted.insert(line, identity);
+        return identity;
+    };
+    let recipe = expansion.recipe;
+    let plain = expansion.plain.get(&line).copied().unwrap_or(line);
+    let formula = proof[line].resulting_formula.clone();
+    let a_formula = implication(recipe, expansion.assumption.clone(), formula.clone());
+    let k = push_step(
+        expansion, vec![], recipe.weakening.clone(), implication(recipe, formula, a_formula.clone()), origin
+    );
+    let lifted = push_step(expansion, vec![plain, k], recipe.modus_ponens.clone(), a_formula, origin);
+    expansion.lifted.insert(line, lifted);
+    lifted
+}
+
+// Expands the block from the assumption on line 'assume' to its discharge on line 'discharge', whose lines
+//  don't contain other blocks. Returns the steps replacing the block and the new index of the discharge
+fn expand_block(
+    proof: &[LogicStep], origins: &[usize], assume: usize, discharge: usize, recipe: &DeductionRecipe
+) -> Result<(Vec<LogicStep>, Vec<usize>, usize), CompileError> {
+    let mut expansion = BlockExpansion {
+        recipe,
+        assumption: proof[assume].resulting_formula.clone(),
+        first: assume,
+        steps: Vec::new(),
+        origins: Vec::new(),
+        plain: HashMap::new(),
+        lifted: HashMap::new()
+    };
+    for line in assume+1..discharge {
+        let step = &proof[line];
+        let origin = origins[line];
+        let uses = step_uses(step);
+        let depends = uses.iter().any(|used| *used >= assume && !expansion.plain.contains_key(used));
+        if !depends {
+            let plain = &expansion.plain;
+            let mut step = LogicStep {
+                used_hypotheses: step.used_hypotheses.clone(),
+                theorem_ref: step.theorem_ref.clone(),
+                kind: step.kind,
+                resulting_formula: step.resulting_formula.clone()
+            };
+            renumber_step(&mut step, &|used| plain.get(&used).copied().unwrap_or(used));
+            expansion.steps.push(step);
+            expansion.origins.push(origin);
+            expansion.plain.insert(line, assume + expansion.steps.len() - 1);
+            continue;
+        };
+        // Only modus ponens goes through the assumption: from (A ⇒ G) and (A ⇒ (G ⇒ F)), distribution gives (A ⇒ F)
+        let is_modus_ponens = step.kind == StepKind::Assertion && step.theorem_ref == recipe.modus_ponens;
+        let (true, [minor, major]) = (is_modus_ponens, &step.used_hypotheses[..]) else {
+            return Err(CompileError::UndischargeableStep(origin));
+        };
+        let a = expansion.assumption.clone();
+        let g = proof[*minor].resulting_formula.clone();
+        let f = step.resulting_formula.clone();
+        let lifted_minor = lifted_line(&mut expansion, proof, *minor, origin);
+        let lifted_major = lifted_line(&mut expansion, proof, *major, origin);
+        let a_g_f = implication(recipe, a.clone(), implication(recipe, g.clone(), f.clone()));
+        let a_g = implication(recipe, a.clone(), g);
+        let a_f = implication(recipe, a, f);
+        let distributed = implication(recipe, a_g, a_f.clone());
+        let s = push_step(
+            &mut expansion, vec![], recipe.distribution.clone(), implication(recipe, a_g_f, distributed.clone()), origin
+        );
+        let mp1 = push_step(&mut expansion, vec![lifted_major, s], recipe.modus_ponens.clone(), distributed, origin);
+        let mp2 = push_step(&mut expansion, vec![lifted_minor, mp1], recipe.modus_ponens.clone(), a_f, origin);
+        expansion.lifted.insert(line, mp2);
+    };
+    let conclusion = proof[discharge].used_hypotheses[0];
+    let discharged = lifted_line(&mut expansion, proof, conclusion, origins[discharge]);
+    Ok((expansion.steps, expansion.origins, discharged))
+}
+
+// Replaces the assume/discharge blocks of a proof, given as (assumption, discharge) lines, by plain proofs
+//  of their implications, from the innermost ones. A line depending on an assumption must use modus ponens
+pub fn expand_assumptions(
+    mut proof: Vec<LogicStep>, mut blocks: Vec<(usize, usize)>, recipe: &DeductionRecipe
+) -> Result<Vec<LogicStep>, CompileError> {
+    let mut origins = (1..=proof.len()).collect::<Vec<_>>();
+    // A block is discharged after the blocks it contains
+    blocks.sort_by_key(|(_, discharge)| *discharge);
+    for k in 0..blocks.len() {
+        let (assume, discharge) = blocks[k];
+        let (steps, step_origins, discharged) = expand_block(&proof, &origins, assume, discharge, recipe)?;
+        let added = steps.len();
+        // The lines of the block can't be used after it, except for the discharge
+        let renumber = |line: usize| match line {
+            line if line > discharge => line + assume + added - discharge - 1,
+            line if line == discharge => discharged,
+            line => line
+        };
+        proof.splice(assume..=discharge, steps);
+        origins.splice(assume..=discharge, step_origins);
+        for step in proof[assume+added..].iter_mut() {
+            renumber_step(step, &renumber);
+        };
+        for block in blocks[k+1..].iter_mut() {
+            *block = (renumber(block.0), renumber(block.1));
+        };
+    };
+    Ok(proof)
+}
@@ -0,0 +1,115 @@
+use super::{
+    Syntax, SyntaxType, SyntaxRole,
+    WellFormedFormula, Object, Variable,
+    collect_wff_variables, collect_object_variables,
+    CompileError
+};
+
+fn wff_syntax_uses(wff: &WellFormedFormula, syntax: usize) -> usize {
+    match wff {
+        WellFormedFormula::Atomic(_) => 0,
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => (*syntax_ref == syntax) as usize
+            + wff_parameters.iter().map(|param_wff| wff_syntax_uses(param_wff, syntax)).sum::<usize>()
+            + object_parameters.iter().map(|param_obj| object_syntax_uses(param_obj, syntax)).sum::<usize>()
+    }
+}
+
+fn object_syntax_uses(obj: &Object, syntax: usize) -> usize {
+    match obj {
+        Object::Atomic(_) | Object::SortedAtomic(_, _) => 0,
+        Object::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => (*syntax_ref == syntax) as usize
+            + wff_parameters.iter().map(|param_wff| wff_syntax_uses(param_wff, syntax)).sum::<usize>()
+            + object_parameters.iter().map(|param_obj| object_syntax_uses(param_obj, syntax)).sum::<usize>()
+    }
+}
+
+// Variables of the definiendum, which must be the defined syntax applied to distinct variables
+fn definiendum_variables(
+    syntax_ref: usize, wff_parameters: &Vec<WellFormedFormula>, object_parameters: &Vec<Object>,
+    defined_syntax: usize
+) -> Option<Vec<Variable>> {
+    if syntax_ref != defined_syntax {
+        return None;
+    };
+    let mut variables = Vec::new();
+    for param_wff in wff_parameters {
+        match param_wff {
+            WellFormedFormula::Atomic(id) => variables.push(Variable::Wff(*id)),
+            _ => return None
+        };
+    };
+    for param_obj in object_parameters {
+        match param_obj {
+            Object::Atomic(id) | Object::SortedAtomic(_, id) => variables.push(Variable::Object(*id)),
+            _ => return None
+        };
+    };
+    for (i, var) in variables.iter().enumerate() {
+        if variables[i+1..].contains(var) {
+            return None;
+        };
+    };
+    Some(variables)
+}
+
+// A definition must be a conservative extension of the library: the defined syntax (the last one)
+//  applied to distinct variables on the left of an equivalence, or of an equality for objects,
+//  and on the right a definiens which neither uses the defined syntax nor introduces new variables
+pub fn check_definition(
+    name: &str, definition: &WellFormedFormula, syntaxes: &[Syntax]
+) -> Result<(), CompileError> {
+    let Some(defined_syntax) = syntaxes.len().checked_sub(1) else {
+        return Err(CompileError::ShouldNotBeReached);
+    };
+    let expected_role = match syntaxes[defined_syntax].syntax_type {
+        SyntaxType::Formula => SyntaxRole::Equivalence,
+        SyntaxType::Object | SyntaxType::Sort(_) => SyntaxRole::Equality
+    };
+    let (role_syntax, wff_parameters, object_parameters) = match definition {
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => (&syntaxes[*syntax_ref], wff_parameters, object_parameters),
+        WellFormedFormula::Atomic(_) => return Err(CompileError::DefinitionNotAnEquivalence(name.to_owned()))
+    };
+    if role_syntax.role != Some(expected_role) {
+        return Err(CompileError::DefinitionNotAnEquivalence(name.to_owned()));
+    };
+    let mut definiens_variables = Vec::new();
+    let definiendum_variables = match (&wff_parameters[..], &object_parameters[..]) {
+        (
+            [WellFormedFormula::SyntaxComposite { syntax_ref, wff_parameters, object_parameters }, definiens],
+            []
+        ) => {
+            collect_wff_variables(definiens, &mut definiens_variables);
+            definiendum_variables(*syntax_ref, wff_parameters, object_parameters, defined_syntax)
+        },
+        (
+            [],
+            [Object::SyntaxComposite { syntax_ref, wff_parameters, object_parameters }, definiens]
+        ) => {
+            collect_object_variables(definiens, &mut definiens_variables);
+            definiendum_variables(*syntax_ref, wff_parameters, object_parameters, defined_syntax)
+        },
+        _ => None
+    };
+    let Some(definiendum_variables) = definiendum_variables else {
+        return Err(CompileError::InvalidDefiniendum(name.to_owned()));
+    };
+    if wff_syntax_uses(definition, defined_syntax) != 1 {
+        return Err(CompileError::CircularDefinition(name.to_owned()));
+    };
+    if definiens_variables.iter().any(|var| !definiendum_variables.contains(var)) {
+        return Err(CompileError::UndefinedDefiniensVariable(name.to_owned()));
+    };
+    Ok(())
+}
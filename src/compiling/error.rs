@@ -6,24 +6,90 @@ pub enum CompileError {
     // In compiler.rs
     OrderFileNotFound,
     InvalidOrderLine(String, usize),
+    IncompleteDeductionRecipe,
+    AlreadyProven,  // Searching a proof for a theorem which has one
+    ProofNotFound(usize),  // No proof of the assertion within the budget of the search
     IOError(io::Error, String, usize),
     UnparsableFile(String, usize),
 
     // In math_file.rs
     IncorrectFileType,
-    AmbiguousSyntax(String),
+    DuplicateSort(String),
+    ReservedName(String),  // A statement named like a keyword of the proof lines, which couldn't be cited
+    InvalidSortVariable(char),
+    UnknownSort(String),
+    AmbiguousSyntax(String, String),  // (syntax name, formula with several parses, at most two applications deep)
+    InvalidBoundVariable(String),
+    InvalidRepetition(String),
+    InvalidSyntaxRole(String),
+    InvalidDistinctVariables(usize),
     MissingProofLine(usize),
+    DuplicateLineLabel(String, usize),
+    UnknownLineLabel(String, usize),
     IncorrectNumberOfHypothesis(usize, usize, usize),
     IncorrectResultingFormula(usize),
+    UninferableFormula(usize),
+    UnfillableHypotheses(usize),  // No accessible preceding lines match the hypotheses of the cited statement
+    DistinctVariablesViolation(usize),
+    VariableCapture(usize),
+    NotARelation(usize),  // Rewriting with something else than an unconditional equivalence or equality
+    UnknownSubstitutedVariable(char, usize),
+    SubstitutionMismatch(char, usize),  // The annotated value of the variable disagrees with the line
+    UnexpectedSubstitution(usize),  // Annotating a line which doesn't instantiate a statement
     UnknownTheorem(String, usize),
+    DuplicateLemma(String),
+    InLemma(String, Box<CompileError>),  // An error in the proof of the lemma
     WeirdReference,
     InaccessibleHypothesis(usize, usize),
     AssertionNotProven(usize),
+    NoDeductionRecipe(usize),  // Assuming a formula in a library which doesn't designate its deduction statements
+    UnmatchedDischarge(usize),
+    UndischargedAssumption(usize),
+
+    // In deduction.rs
+    InvalidDeductionRecipe(String),  // The statement doesn't have the shape of its role
+    UndischargeableStep(usize),  // A line depending on an assumption without being a modus ponens
+
+    // In tautology.rs
+    NoContraposition,  // No deduction recipe, or no statement "((¬𝜑 ⇒ ¬𝜓) ⇒ (𝜓 ⇒ 𝜑))" to find the negation with
+    NotPropositional,  // A formula using other syntaxes than the implication and the negation, or objects
+
+    // In goals.rs
+    UnknownGoal(usize),
+    GoalAlreadyProven(usize),
+    InapplicableStatement(String, usize),  // The assertion (or the hypothesis) doesn't unify with the goal
+    OpenGoal(usize),
+    NotAnImplication(usize),  // Introducing an assumption in a goal which isn't an implication
+    InaccessibleAssumption(usize, usize),  // (goal discharging the assumption, goal which isn't under it)
+
+    // In tactics.rs
+    TacticFailed(usize, Box<CompileError>),  // The error of the tactic, counted from 1
+    NoOpenGoal,  // A tactic run once every goal is proven
+    UnprovenGoals(usize),  // The number of goals the tactics leave open
+
+    // In definition.rs
+    DefinitionNotAnEquivalence(String),
+    InvalidDefiniendum(String),
+    CircularDefinition(String),
+    UndefinedDefiniensVariable(String),
 
     // In formula.rs
     ShouldNotBeReached,
     UncompilableFormula(Vec<PartiallyCompiled>),
-
-    // TODO error
+    NonAssociativeChain,
+    // Competing parses, each given as the (syntax, start, end) applications that differ between them,
+    //  positions being counted in characters without spaces
+    AmbiguousFormula(Vec<Vec<(usize, usize, usize)>>),
+    TooManyParses(usize, usize),  // (start, end) of a part with more readings than the compiler keeps
+    // '…' only has a meaning in syntax definitions
     RepetitionCharacterNotCompilable,
 }
+
+// Accepted by the compiler but likely to be mistakes, given with the name of the theorem or lemma
+#[derive(Debug)]
+pub enum CompileWarning {
+    // In lints.rs
+    UnusedHypothesis(String, String),  // (statement, hypothesis)
+    UnusedStep(String, usize),  // The line doesn't lead to an assertion
+    DuplicateStep(String, usize, usize)  // (statement, line, preceding line proving the same formula)
+}
@@ -0,0 +1,46 @@
+use super::{Syntax, WellFormedFormula, Unifier, new_metavariables, rename_wff, unify_wffs};
+
+// Assigns a line to each remaining hypothesis, backtracking when a later one can't be unified
+fn assign_lines(
+    hypotheses: &[WellFormedFormula], lines: &Vec<(usize, WellFormedFormula)>, unifier: &Unifier,
+    syntaxes: &Vec<Syntax>, assigned: &mut Vec<usize>
+) -> bool {
+    let Some((hypothesis, remaining)) = hypotheses.split_first() else {
+        return true;
+    };
+    for (line, formula) in lines {
+        let mut unifier = unifier.clone();
+        if unify_wffs(hypothesis, formula, &mut unifier, syntaxes).is_err() {
+            continue;
+        };
+        assigned.push(*line);
+        if assign_lines(remaining, lines, &unifier, syntaxes, assigned) {
+            return true;
+        };
+        assigned.pop();
+    };
+    false
+}
+
+// The lines to use for the hypotheses of a cited statement whose assertion gives the resulting formula,
+//  chosen among the given (line number, formula) pairs. Lines are tried in the given order, and the first
+//  assignment whose formulas unify with the hypotheses consistently is kept
+pub fn fill_used_lines(
+    (theo_hypotheses, theo_assertion, wff_count, object_count): (&Vec<WellFormedFormula>, &WellFormedFormula, usize, usize),
+    resulting_formula: &WellFormedFormula, lines: &Vec<(usize, WellFormedFormula)>, unifier: &Unifier,
+    syntaxes: &Vec<Syntax>
+) -> Option<Vec<usize>> {
+    let mut unifier = unifier.clone();
+    let offsets = new_metavariables(&mut unifier, wff_count, object_count);
+    if unify_wffs(&rename_wff(theo_assertion, offsets), resulting_formula, &mut unifier, syntaxes).is_err() {
+        return None;
+    };
+    let hypotheses = theo_hypotheses.iter()
+        .map(|hypot| rename_wff(hypot, offsets))
+        .collect::<Vec<_>>();
+    let mut assigned = Vec::new();
+    match assign_lines(&hypotheses, lines, &unifier, syntaxes, &mut assigned) {
+        true => Some(assigned),
+        false => None
+    }
+}
@@ -2,21 +2,335 @@ use std::collections::HashMap;
 use crate::parsing::FormulaChar;
 use super::{
     PartiallyCompiled,
-    Placeholder,
-    Syntax, SyntaxType,
+    Placeholder, Associativity,
+    Sort, Syntax, SyntaxType,
     WellFormedFormula, Object,
     CompileError
 };
 
-pub fn compile_formula(
-    formula: Vec<FormulaChar>, syntaxes: &Vec<Syntax>,
+pub fn variable_sort(c: char, sorts: &[Sort]) -> Option<usize> {
+    sorts.iter().position(|sort| sort.variables.contains(&c))
+}
+
+// Sort of an object, 'None' being the sort of the '𝑎'..'𝑧' objects
+pub fn object_sort(obj: &Object, syntaxes: &[Syntax]) -> Option<usize> {
+    match obj {
+        Object::Atomic(_) => None,
+        Object::SortedAtomic(sort, _) => Some(*sort),
+        Object::SyntaxComposite { syntax_ref, .. } => match syntaxes[*syntax_ref].syntax_type {
+            SyntaxType::Sort(sort) => Some(sort),
+            _ => None
+        }
+    }
+}
+
+// The repetition '…' repeats the last placeholder before it, along with the characters between them:
+//  '{𝑎, …}' matches '{𝑥}', '{𝑥, 𝑦}', '{𝑥, 𝑦, 𝑧}'...
+// Returns the positions of the repeated placeholder and of the repetition
+pub fn repetition_group(formula: &[Placeholder]) -> Option<(usize, usize)> {
+    let repetition = formula.iter().position(|pl| matches!(pl, Placeholder::Repetition))?;
+    let repeated = formula[..repetition].iter()
+        .rposition(|pl| !matches!(pl, Placeholder::LiteralChar(_)))?;
+    Some((repeated, repetition))
+}
+
+// Fewest characters the placeholders from the k-th one can take, a repetition can stop after its first element
+fn minimal_length(formula: &[Placeholder], k: usize, repetition: Option<(usize, usize)>) -> usize {
+    (k..formula.len())
+        .filter(|i| match repetition {
+            Some((repeated, repetition)) => *i <= repeated || *i > repetition,
+            None => true
+        })
+        .count()
+}
+
+// Syntaxes without a precedence bind tighter than any other
+fn binding_strength(syntax: &Syntax) -> usize {
+    syntax.precedence.unwrap_or(usize::MAX)
+}
+
+fn is_placeholder(placeholder: Option<&Placeholder>) -> bool {
+    matches!(placeholder, Some(Placeholder::WellFormedFormula(_) | Placeholder::Object(_) | Placeholder::Sorted(_, _)))
+}
+
+// Whether a parse whose outermost syntax is 'child' may fill a placeholder of 'parent' at the given edges.
+// Only matters when the child is open on the side facing the outside of the parent:
+//  the one with the highest precedence binds tighter, equal precedences follow the associativity
+pub fn respects_precedence(
+    child: Option<usize>, parent_id: usize, (at_left, at_right): (bool, bool),
+    syntaxes: &[Syntax], nonassociative_chain: &mut bool
+) -> bool {
+    let Some(child_id) = child else {
+        return true;  // Variables and parenthesised groups are closed
+    };
+    let (child, parent) = (&syntaxes[child_id], &syntaxes[parent_id]);
+    let left_edge = at_left && is_placeholder(child.formula.last());
+    let right_edge = at_right && is_placeholder(child.formula.first());
+    if !left_edge && !right_edge {
+        return true;
+    };
+    if child_id == parent_id && repetition_group(&parent.formula).is_some() {
+        return false;  // A chain of a variadic syntax is a single application of it
+    };
+    let (child_strength, parent_strength) = (binding_strength(child), binding_strength(parent));
+    if child_strength != parent_strength {
+        return child_strength > parent_strength;
+    };
+    if child.precedence.is_none() {
+        return true;
+    };
+    let expected = match left_edge {
+        true => Associativity::Left,
+        false => Associativity::Right
+    };
+    if parent.associativity == expected && child.associativity == expected {
+        return true;
+    };
+    if parent.associativity == Associativity::NonAssociative {
+        *nonassociative_chain = true;
+    };
+    false
+}
+
+// A parse of a span of the formula
+#[derive(Clone)]
+struct ChartEntry {
+    element: PartiallyCompiled,  // Either 'CompiledFormula' or 'CompiledObject'
+    top_syntax: Option<usize>,  // 'None' for variables and parenthesised groups
+    nodes: Vec<(usize, usize, usize)>  // (syntax, start, end) of every syntax used in the parse
+}
+
+// chart[start][end] holds the parses of the tokens start..end
+type Chart = Vec<Vec<Vec<ChartEntry>>>;
+
+// Chart cells keep at most this many parses, a span with more of them is refused rather than truncated
+const MAX_PARSES_PER_SPAN: usize = 16;
+
+#[derive(Clone)]
+struct PartialMatch {
+    wffs: Vec<Option<WellFormedFormula>>,
+    objects: Vec<Option<Object>>,
+    repeated_wffs: Vec<WellFormedFormula>,
+    repeated_objects: Vec<Object>,
+    nodes: Vec<(usize, usize, usize)>
+}
+
+fn assign_parameter<T: PartialEq + Clone>(slot: &mut Option<T>, value: &T) -> bool {
+    match slot {
+        Some(assigned) => assigned == value,  // Repeated placeholders must be filled identically
+        None => { *slot = Some(value.clone()); true }
+    }
+}
+
+// A syntax being matched on the span from 'start' to 'end'
+struct SpanMatch<'a> {
+    syntax_id: usize,
+    syntax: &'a Syntax,
+    start: usize,
+    end: usize,
+    tokens: &'a Vec<PartiallyCompiled>,
+    chart: &'a Chart,
+    syntaxes: &'a Vec<Syntax>
+}
+
+// Extends the match of the syntax from its k-th placeholder at 'position', up to the end of the span
+fn extend_match(
+    span: &SpanMatch, k: usize, position: usize, current: PartialMatch,
+    nonassociative_chain: &mut bool, results: &mut Vec<PartialMatch>
+) {
+    let SpanMatch { syntax_id, syntax, start, end, tokens, chart, syntaxes } = *span;
+    if k == syntax.formula.len() {
+        if position == end { results.push(current); };
+        return;
+    };
+    let repetition = repetition_group(&syntax.formula);
+    let remaining = minimal_length(&syntax.formula, k+1, repetition);
+    match &syntax.formula[k] {
+        Placeholder::LiteralChar(c1) => match tokens.get(position) {
+            Some(PartiallyCompiled::NotCompiled(FormulaChar::Char(c2))) if c1 == c2 && position < end => {
+                extend_match(span, k+1, position+1, current, nonassociative_chain, results);
+            },
+            _ => ()
+        },
+        // The separator has been matched, another element must follow
+        Placeholder::Repetition => if let Some((repeated, _)) = repetition {
+            extend_match(span, repeated, position, current, nonassociative_chain, results);
+        },
+        placeholder => {
+            // Every element of a repetition lies at the edges the repetition reaches
+            let (repeating, edges) = match repetition {
+                Some((repeated, repetition)) if repeated == k => (
+                    true, (repeated == 0, repetition + 1 == syntax.formula.len())
+                ),
+                _ => (false, (k == 0, k + 1 == syntax.formula.len()))
+            };
+            let cells = chart[position].iter()
+                .enumerate()
+                .take(end.saturating_sub(remaining) + 1)
+                .skip(position + 1);
+            for (next, cell) in cells {
+                if position == start && next == end {
+                    continue;  // A placeholder can't fill the whole span it belongs to
+                };
+                for entry in cell {
+                    if !respects_precedence(entry.top_syntax, syntax_id, edges, syntaxes, nonassociative_chain) {
+                        continue;
+                    };
+                    let mut extended = current.clone();
+                    let valid = match (placeholder, &entry.element) {
+                        (
+                            Placeholder::WellFormedFormula(id),
+                            PartiallyCompiled::CompiledFormula(wff)
+                        ) => assign_parameter(&mut extended.wffs[*id], wff),
+                        (
+                            Placeholder::Object(id),
+                            PartiallyCompiled::CompiledObject(obj)
+                        ) if object_sort(obj, syntaxes).is_none() => assign_parameter(&mut extended.objects[*id], obj),
+                        (
+                            Placeholder::Sorted(sort, id),
+                            PartiallyCompiled::CompiledObject(obj)
+                        ) if object_sort(obj, syntaxes) == Some(*sort) => assign_parameter(&mut extended.objects[*id], obj),
+                        _ => false
+                    };
+                    if !valid { continue; };
+                    extended.nodes.extend(entry.nodes.iter().copied());
+                    if repeating {
+                        // Elements of the repetition are collected apart, in order
+                        match placeholder {
+                            Placeholder::WellFormedFormula(id) => extended.repeated_wffs.extend(extended.wffs[*id].take()),
+                            Placeholder::Object(id) | Placeholder::Sorted(_, id) => extended.repeated_objects.extend(extended.objects[*id].take()),
+                            _ => ()
+                        };
+                        if let Some((_, repetition)) = repetition {
+                            // Either the repetition stops here or its separator follows
+                            extend_match(span, repetition+1, next, extended.clone(), nonassociative_chain, results);
+                        };
+                    };
+                    extend_match(span, k+1, next, extended, nonassociative_chain, results);
+                };
+            };
+        }
+    };
+}
+
+// Every way the syntax can be applied to the whole span
+fn match_syntax(
+    syntax_id: usize, (start, end): (usize, usize),
+    tokens: &Vec<PartiallyCompiled>, chart: &Chart, syntaxes: &Vec<Syntax>,
+    nonassociative_chain: &mut bool
+) -> Vec<ChartEntry> {
+    let syntax = &syntaxes[syntax_id];
+    let initial = PartialMatch {
+        wffs: vec![None; syntax.distinct_wff_count],
+        objects: vec![None; syntax.distinct_object_count],
+        repeated_wffs: Vec::new(),
+        repeated_objects: Vec::new(),
+        nodes: Vec::new()
+    };
+    let span = SpanMatch { syntax_id, syntax, start, end, tokens, chart, syntaxes };
+    let mut results = Vec::new();
+    extend_match(&span, 0, start, initial, nonassociative_chain, &mut results);
+    let repeated = repetition_group(&syntax.formula).map(|(repeated, _)| &syntax.formula[repeated]);
+    let mut entries = Vec::new();
+    'next_match: for PartialMatch {
+        mut wffs, mut objects, repeated_wffs, repeated_objects, mut nodes
+    } in results {
+        // Bound placeholders can only be filled with variables
+        for bound_id in &syntax.bound_objects {
+            match &objects[*bound_id] {
+                Some(Object::Atomic(_) | Object::SortedAtomic(_, _)) => (),
+                _ => continue 'next_match
+            };
+        };
+        // The elements of a repetition replace its placeholder, which has the last id of its kind
+        match repeated {
+            Some(Placeholder::WellFormedFormula(_)) => { wffs.pop(); },
+            Some(Placeholder::Object(_) | Placeholder::Sorted(_, _)) => { objects.pop(); },
+            _ => ()
+        };
+        let (Some(mut wff_parameters), Some(mut object_parameters)) = (
+            wffs.into_iter().collect::<Option<Vec<_>>>(),
+            objects.into_iter().collect::<Option<Vec<_>>>()
+        ) else {
+            continue 'next_match;
+        };
+        wff_parameters.extend(repeated_wffs);
+        object_parameters.extend(repeated_objects);
+        let element = match syntax.syntax_type {
+            SyntaxType::Formula => PartiallyCompiled::CompiledFormula(
+                WellFormedFormula::SyntaxComposite { syntax_ref: syntax_id, wff_parameters, object_parameters }
+            ),
+            SyntaxType::Object | SyntaxType::Sort(_) => PartiallyCompiled::CompiledObject(
+                Object::SyntaxComposite { syntax_ref: syntax_id, wff_parameters, object_parameters }
+            )
+        };
+        nodes.push((syntax_id, start, end));
+        entries.push(ChartEntry { element, top_syntax: Some(syntax_id), nodes });
+    };
+    entries
+}
+
+fn same_element(element1: &PartiallyCompiled, element2: &PartiallyCompiled) -> bool {
+    match (element1, element2) {
+        (PartiallyCompiled::CompiledFormula(wff1), PartiallyCompiled::CompiledFormula(wff2)) => wff1 == wff2,
+        (PartiallyCompiled::CompiledObject(obj1), PartiallyCompiled::CompiledObject(obj2)) => obj1 == obj2,
+        _ => false
+    }
+}
+
+// Adds the parse unless the cell already has it, fails when the cell is full
+fn add_entry(cell: &mut Vec<ChartEntry>, entry: ChartEntry) -> Result<(), ()> {
+    if cell.iter().any(|e| same_element(&e.element, &entry.element)) {
+        return Ok(());
+    };
+    if cell.len() == MAX_PARSES_PER_SPAN {
+        return Err(());
+    };
+    cell.push(entry);
+    Ok(())
+}
+
+// Covers the formula with the longest parses from left to right, to show how far the compilation went
+fn best_cover(tokens: Vec<PartiallyCompiled>, chart: &[Vec<Vec<ChartEntry>>]) -> Vec<PartiallyCompiled> {
+    let mut res = Vec::new();
+    let mut position = 0;
+    while position < tokens.len() {
+        let longest = (position+1..=tokens.len()).rev()
+            .find_map(|end| chart[position][end].first().map(|entry| (end, entry)));
+        match longest {
+            Some((end, entry)) => {
+                res.push(entry.element.clone());
+                position = end;
+            },
+            None => {
+                res.push(tokens[position].clone());
+                position += 1;
+            }
+        };
+    };
+    res
+}
+
+// Variables of user-declared sorts are stored in 'objects' with their character as key,
+//  which never collides with the ids of '𝑎'..'𝑧' as compile_sort rejects control characters
+fn tokenize(
+    formula: Vec<FormulaChar>, sorts: &[Sort],
     wffs: &mut HashMap<usize, WellFormedFormula>, objects: &mut HashMap<usize, Object>
-) -> Result<WellFormedFormula, CompileError> {
+) -> Result<Vec<PartiallyCompiled>, CompileError> {
     let mut next_wff_index = wffs.len();
     let mut next_object_index = objects.len();
-    let mut partial_compilation = formula.into_iter()
+    formula.into_iter()
         .map(|c| match c {
-            FormulaChar::Char(_) => Ok(PartiallyCompiled::NotCompiled(c)),
+            FormulaChar::Char(ch) => match (variable_sort(ch, sorts), objects.get(&(ch as usize))) {
+                (None, _) => Ok(PartiallyCompiled::NotCompiled(c)),
+                (Some(_), Some(object)) => Ok(PartiallyCompiled::CompiledObject(object.to_owned())),
+                (Some(sort), None) => {
+                    let object = Object::SortedAtomic(sort, next_object_index);
+                    objects.insert(ch as usize, object.clone());
+                    next_object_index += 1;
+                    Ok(PartiallyCompiled::CompiledObject(object))
+                }
+            },
             FormulaChar::RepetitionChar => Err(CompileError::RepetitionCharacterNotCompilable),
             FormulaChar::Wff(id) => match wffs.get(&id) {
                 Some(wff) => Ok(PartiallyCompiled::CompiledFormula(wff.to_owned())),
@@ -37,70 +351,122 @@ pub fn compile_formula(
                 }
             }
         })
-        .collect::<Result<Vec<_>, _>>()?;
-    'try_find_patterns: while partial_compilation.len() > 1 {
-        'try_syntax: for (syntax_id, syntax) in syntaxes.iter().enumerate() {
-            'try_position: for index in 0..partial_compilation.len() {
-                if partial_compilation.len() - index < syntax.formula.len() {
-                    continue 'try_syntax;  // If the syntax does not fit in the rest of the text, go to the next syntax
+        .collect()
+}
+
+// Chart parsing: chart[start][end] holds every parse of the characters start..end (spaces excluded),
+//  also tells whether a chain of non associative syntaxes has been refused
+fn fill_chart(
+    tokens: &Vec<PartiallyCompiled>, syntaxes: &Vec<Syntax>
+) -> Result<(Chart, bool), CompileError> {
+    let n = tokens.len();
+    let mut chart = vec![vec![Vec::new(); n+1]; n+1];
+    let mut nonassociative_chain = false;
+    for length in 1..=n {
+        for start in 0..=n-length {
+            let end = start + length;
+            let too_many_parses = |_| CompileError::TooManyParses(start, end);
+            let mut cell = Vec::new();
+            if length == 1 {
+                match &tokens[start] {
+                    PartiallyCompiled::NotCompiled(_) => (),
+                    variable => add_entry(&mut cell, ChartEntry {
+                        element: variable.clone(), top_syntax: None, nodes: Vec::new()
+                    }).map_err(too_many_parses)?
                 };
-                let mut wffs = vec![None; syntax.distinct_wff_count];
-                let mut objects = vec![None; syntax.distinct_object_count];
-                for (c, pl) in partial_compilation[index..].iter().zip(&syntax.formula) {
-                    let valid = match (c, pl) {
-                        (
-                            PartiallyCompiled::NotCompiled(FormulaChar::Char(c1)),
-                            Placeholder::LiteralChar(c2)
-                        ) => c1 == c2,
-                        (
-                            PartiallyCompiled::CompiledFormula(wff),
-                            Placeholder::WellFormedFormula(id)
-                        ) => match &wffs[*id] {
-                            Some(f) => wff == f,
-                            None => { wffs[*id] = Some(wff.to_owned()); true }
-                        },
-                        (
-                            PartiallyCompiled::CompiledObject(obj),
-                            Placeholder::Object(id)
-                        ) => match &objects[*id] {
-                            Some(o) => obj == o,
-                            None => { objects[*id] = Some(obj.to_owned()); true }
-                        },
-                        _ => false
-                    };
-                    match valid {
-                        true => (),
-                        false => continue 'try_position  // Syntax did not match here, try next position
-                    };
+            };
+            for (syntax_id, syntax) in syntaxes.iter().enumerate() {
+                if minimal_length(&syntax.formula, 0, repetition_group(&syntax.formula)) > length {
+                    continue;
                 };
-                let wff_parameters = wffs.into_iter()
-                    .map(|wff| wff.ok_or(CompileError::ShouldNotBeReached))
-                    .collect::<Result<Vec<_>, _>>()?;
-                let object_parameters = objects.into_iter()
-                    .map(|obj| obj.ok_or(CompileError::ShouldNotBeReached))
-                    .collect::<Result<Vec<_>, _>>()?;
-                let element_to_insert = match syntax.syntax_type {
-                    SyntaxType::Formula => PartiallyCompiled::CompiledFormula(
-                        WellFormedFormula::SyntaxComposite { syntax_ref: syntax_id, wff_parameters, object_parameters }
-                    ),
-                    SyntaxType::Object => PartiallyCompiled::CompiledObject(
-                        Object::SyntaxComposite { syntax_ref: syntax_id, wff_parameters, object_parameters }
-                    )
+                let entries = match_syntax(
+                    syntax_id, (start, end), tokens, &chart, syntaxes, &mut nonassociative_chain
+                );
+                for entry in entries {
+                    add_entry(&mut cell, entry).map_err(too_many_parses)?;
                 };
-                for _ in 0..syntax.formula.len() {
-                    partial_compilation.remove(index);
+            };
+            // Parentheses can group any parse, when no syntax applies to the span
+            let grouped = match (&tokens[start], &tokens[end-1]) {
+                (
+                    PartiallyCompiled::NotCompiled(FormulaChar::Char('(')),
+                    PartiallyCompiled::NotCompiled(FormulaChar::Char(')'))
+                ) => cell.is_empty() && length >= 3,
+                _ => false
+            };
+            if grouped {
+                for entry in &chart[start+1][end-1] {
+                    add_entry(&mut cell, ChartEntry { top_syntax: None, ..entry.clone() }).map_err(too_many_parses)?;
                 };
-                partial_compilation.insert(index, element_to_insert);
-                // Once a syntax has been compiled, we iterate through the list again from the beginning
-                continue 'try_find_patterns;
             };
+            chart[start][end] = cell;
         };
-        // We only can get here if no syntax has matched
-        return Err(CompileError::UncompilableFormula(partial_compilation));
     };
-    match partial_compilation.pop() {
-        Some(PartiallyCompiled::CompiledFormula(wff)) => Ok(wff),
-        Some(other) => Err(CompileError::UncompilableFormula(vec![other])),
-        None => Err(CompileError::ShouldNotBeReached)
+    Ok((chart, nonassociative_chain))
+}
+
+// Whether the formula can be read in several ways, whatever its category, a part with too many parses
+//  counting as several readings
+pub fn has_several_parses(formula: Vec<FormulaChar>, sorts: &[Sort], syntaxes: &Vec<Syntax>) -> bool {
+    let Ok(tokens) = tokenize(formula, sorts, &mut HashMap::new(), &mut HashMap::new()) else {
+        return false;
+    };
+    match fill_chart(&tokens, syntaxes) {
+        Ok((chart, _)) => chart[0][tokens.len()].len() > 1,
+        Err(_) => true
+    }
+}
+
+// The single parse of the whole formula as a formula or as an object, depending on 'is_wff'
+fn compile_element(
+    formula: Vec<FormulaChar>, is_wff: bool, sorts: &[Sort], syntaxes: &Vec<Syntax>,
+    wffs: &mut HashMap<usize, WellFormedFormula>, objects: &mut HashMap<usize, Object>
+) -> Result<PartiallyCompiled, CompileError> {
+    let tokens = tokenize(formula, sorts, wffs, objects)?;
+    let (chart, nonassociative_chain) = fill_chart(&tokens, syntaxes)?;
+    let mut parses = chart[0][tokens.len()].iter()
+        .filter(|entry| matches!(
+            (&entry.element, is_wff),
+            (PartiallyCompiled::CompiledFormula(_), true) | (PartiallyCompiled::CompiledObject(_), false)
+        ))
+        .collect::<Vec<_>>();
+    match parses.len() {
+        0 if nonassociative_chain => Err(CompileError::NonAssociativeChain),
+        0 => Err(CompileError::UncompilableFormula(best_cover(tokens, &chart))),
+        1 => match parses.pop() {
+            Some(entry) => Ok(entry.element.clone()),
+            None => Err(CompileError::ShouldNotBeReached)
+        },
+        _ => {
+            // Only the syntax applications that are not common to every parse are reported
+            let competing = parses.iter()
+                .map(|entry| entry.nodes.iter()
+                    .filter(|node| !parses.iter().all(|other| other.nodes.contains(node)))
+                    .copied()
+                    .collect::<Vec<_>>()
+                )
+                .collect();
+            Err(CompileError::AmbiguousFormula(competing))
+        }
+    }
+}
+
+pub fn compile_formula(
+    formula: Vec<FormulaChar>, sorts: &[Sort], syntaxes: &Vec<Syntax>,
+    wffs: &mut HashMap<usize, WellFormedFormula>, objects: &mut HashMap<usize, Object>
+) -> Result<WellFormedFormula, CompileError> {
+    match compile_element(formula, true, sorts, syntaxes, wffs, objects)? {
+        PartiallyCompiled::CompiledFormula(wff) => Ok(wff),
+        _ => Err(CompileError::ShouldNotBeReached)
+    }
+}
+
+pub fn compile_object(
+    formula: Vec<FormulaChar>, sorts: &[Sort], syntaxes: &Vec<Syntax>,
+    wffs: &mut HashMap<usize, WellFormedFormula>, objects: &mut HashMap<usize, Object>
+) -> Result<Object, CompileError> {
+    match compile_element(formula, false, sorts, syntaxes, wffs, objects)? {
+        PartiallyCompiled::CompiledObject(obj) => Ok(obj),
+        _ => Err(CompileError::ShouldNotBeReached)
     }
 }
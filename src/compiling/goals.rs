@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use super::{
+    Syntax, Reference, WellFormedFormula, Object, VariableNames,
+    Library, DeductionRecipe,
+    CitedStatement, statement_reference, cited_statement, implication,
+    Unifier, is_metavariable, new_metavariables, rename_wff, apply_unifier_wff, has_metavariables, unify_wffs,
+    wff_to_string,
+    CompileError
+};
+
+// How a goal has been proven
+#[derive(Debug, Clone)]
+pub enum Justification {
+    Hypothesis(String),  // A hypothesis of the theorem, by name
+    Statement(String, Vec<usize>),  // A definition, an axiom or a theorem, by name, and the goals for its hypotheses
+    Discharge(WellFormedFormula, usize),  // The assumed antecedent of the implication, and the goal for its consequent
+    Assumption(usize)  // The antecedent assumed by a preceding goal, proven by discharging it
+}
+
+#[derive(Debug, Clone)]
+pub struct Goal {
+    pub formula: WellFormedFormula,  // May contain metavariables, the unifier of the tree gives their values
+    pub justification: Option<Justification>  // 'None' while the goal is open
+}
+
+// Goals are indexed by their position, the root being the first one
+#[derive(Clone)]
+pub struct GoalTree {
+    pub hypotheses: Vec<(String, WellFormedFormula)>,
+    pub goals: Vec<Goal>,
+    pub unifier: Unifier
+}
+
+pub fn new_goal_tree(hypotheses: Vec<(String, WellFormedFormula)>, goal: WellFormedFormula) -> GoalTree {
+    GoalTree {
+        hypotheses,
+        goals: vec![Goal { formula: goal, justification: None }],
+        unifier: Unifier { wffs: Vec::new(), objects: Vec::new() }
+    }
+}
+
+pub fn open_goals(tree: &GoalTree) -> Vec<usize> {
+    tree.goals.iter()
+        .enumerate()
+        .filter(|(_, goal)| goal.justification.is_none())
+        .map(|(goal_id, _)| goal_id)
+        .collect()
+}
+
+// The formula of the goal, as determined so far
+pub fn goal_formula(tree: &GoalTree, goal_id: usize) -> Option<WellFormedFormula> {
+    tree.goals.get(goal_id).map(|goal| apply_unifier_wff(&goal.formula, &tree.unifier))
+}
+
+// Proves an open goal with a hypothesis of the theorem, or with a statement of the library whose assertion
+//  is unified with the goal: its hypotheses, instantiated, become the returned subgoals.
+// Variables of the statement that its assertion doesn't fix stay metavariables until other goals determine them.
+// Distinct variables and captures are checked when the exported proof is compiled.
+// On failure, the tree is left unchanged
+pub fn apply_to_goal(
+    tree: &mut GoalTree, goal_id: usize, name: &str, lib: &Library, references: &HashMap<String, Reference>
+) -> Result<Vec<usize>, CompileError> {
+    if tree.hypotheses.iter().any(|(hypot_name, _)| hypot_name == name) {
+        return apply_hypothesis_to_goal(tree, goal_id, name, &lib.syntaxes).map(|()| Vec::new());
+    };
+    let reference = statement_reference(name, goal_id, references, &HashMap::new())?;
+    let Some(statement) = cited_statement(
+        &reference, &lib.definitions, &lib.axioms, &lib.theorems, &lib.lemmas
+    ) else {
+        return Err(CompileError::UnknownTheorem(name.to_owned(), goal_id));
+    };
+    apply_statement_to_goal(tree, goal_id, name, statement, &lib.syntaxes)
+}
+
+// Proves an open goal with the hypothesis of the theorem of the given name
+pub fn apply_hypothesis_to_goal(
+    tree: &mut GoalTree, goal_id: usize, name: &str, syntaxes: &Vec<Syntax>
+) -> Result<(), CompileError> {
+    let Some(goal) = tree.goals.get(goal_id) else {
+        return Err(CompileError::UnknownGoal(goal_id));
+    };
+    if goal.justification.is_some() {
+        return Err(CompileError::GoalAlreadyProven(goal_id));
+    };
+    let Some((_, hypot)) = tree.hypotheses.iter().find(|(hypot_name, _)| hypot_name == name) else {
+        return Err(CompileError::UnknownTheorem(name.to_owned(), goal_id));
+    };
+    let mut unifier = tree.unifier.clone();
+    if unify_wffs(hypot, &goal.formula, &mut unifier, syntaxes).is_err() {
+        return Err(CompileError::InapplicableStatement(name.to_owned(), goal_id));
+    };
+    tree.unifier = unifier;
+    tree.goals[goal_id].justification = Some(Justification::Hypothesis(name.to_owned()));
+    Ok(())
+}
+
+// Proves an open goal with a statement found by the caller, as 'apply_to_goal' does
+pub fn apply_statement_to_goal(
+    tree: &mut GoalTree, goal_id: usize, name: &str,
+    (hypotheses, assertion, _, wff_count, object_count): CitedStatement,
+    syntaxes: &Vec<Syntax>
+) -> Result<Vec<usize>, CompileError> {
+    let Some(goal) = tree.goals.get(goal_id) else {
+        return Err(CompileError::UnknownGoal(goal_id));
+    };
+    if goal.justification.is_some() {
+        return Err(CompileError::GoalAlreadyProven(goal_id));
+    };
+    let mut unifier = tree.unifier.clone();
+    let offsets = new_metavariables(&mut unifier, wff_count, object_count);
+    if unify_wffs(&rename_wff(assertion, offsets), &goal.formula, &mut unifier, syntaxes).is_err() {
+        return Err(CompileError::InapplicableStatement(name.to_owned(), goal_id));
+    };
+    let first_subgoal = tree.goals.len();
+    tree.goals.extend(hypotheses.iter().map(|hypot| Goal {
+        formula: rename_wff(hypot, offsets),
+        justification: None
+    }));
+    let subgoals = (first_subgoal..tree.goals.len()).collect::<Vec<_>>();
+    tree.unifier = unifier;
+    tree.goals[goal_id].justification = Some(Justification::Statement(name.to_owned(), subgoals.clone()));
+    Ok(subgoals)
+}
+
+// Proves an open implication by assuming its antecedent, which the goals under the returned one can use
+pub fn intro_goal(
+    tree: &mut GoalTree, goal_id: usize, recipe: &DeductionRecipe, syntaxes: &Vec<Syntax>
+) -> Result<usize, CompileError> {
+    let Some(goal) = tree.goals.get(goal_id) else {
+        return Err(CompileError::UnknownGoal(goal_id));
+    };
+    if goal.justification.is_some() {
+        return Err(CompileError::GoalAlreadyProven(goal_id));
+    };
+    let mut unifier = tree.unifier.clone();
+    let (offset, _) = new_metavariables(&mut unifier, 2, 0);
+    let antecedent = WellFormedFormula::Atomic(offset);
+    let consequent = WellFormedFormula::Atomic(offset + 1);
+    let pattern = implication(recipe, antecedent.clone(), consequent.clone());
+    if unify_wffs(&pattern, &goal.formula, &mut unifier, syntaxes).is_err() {
+        return Err(CompileError::NotAnImplication(goal_id));
+    };
+    let subgoal = tree.goals.len();
+    tree.goals.push(Goal { formula: consequent, justification: None });
+    tree.unifier = unifier;
+    tree.goals[goal_id].justification = Some(Justification::Discharge(antecedent, subgoal));
+    Ok(subgoal)
+}
+
+fn is_ancestor(tree: &GoalTree, ancestor: usize, goal_id: usize) -> bool {
+    let children = match &tree.goals[ancestor].justification {
+        Some(Justification::Statement(_, subgoals)) => subgoals.clone(),
+        Some(Justification::Discharge(_, subgoal)) => vec![*subgoal],
+        _ => Vec::new()
+    };
+    children.iter().any(|child| *child == goal_id || is_ancestor(tree, *child, goal_id))
+}
+
+// Proves an open goal with the antecedent assumed by one of the goals it descends from
+pub fn assume_in_goal(
+    tree: &mut GoalTree, goal_id: usize, discharged_goal: usize, syntaxes: &Vec<Syntax>
+) -> Result<(), CompileError> {
+    let Some(goal) = tree.goals.get(goal_id) else {
+        return Err(CompileError::UnknownGoal(goal_id));
+    };
+    if goal.justification.is_some() {
+        return Err(CompileError::GoalAlreadyProven(goal_id));
+    };
+    let antecedent = match tree.goals.get(discharged_goal).map(|goal| &goal.justification) {
+        Some(Some(Justification::Discharge(antecedent, _))) if is_ancestor(tree, discharged_goal, goal_id) => antecedent,
+        _ => return Err(CompileError::InaccessibleAssumption(discharged_goal, goal_id))
+    };
+    let mut unifier = tree.unifier.clone();
+    if unify_wffs(antecedent, &goal.formula, &mut unifier, syntaxes).is_err() {
+        return Err(CompileError::InapplicableStatement("assumption".to_owned(), goal_id));
+    };
+    tree.unifier = unifier;
+    tree.goals[goal_id].justification = Some(Justification::Assumption(discharged_goal));
+    Ok(())
+}
+
+// Wff metavariables that no goal determines can be anything, they become the given variable
+fn ground_wff(wff: WellFormedFormula, variable: usize) -> WellFormedFormula {
+    match wff {
+        WellFormedFormula::Atomic(id) if is_metavariable(id) => WellFormedFormula::Atomic(variable),
+        WellFormedFormula::Atomic(_) => wff,
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters: wff_parameters.into_iter()
+                .map(|param_wff| ground_wff(param_wff, variable))
+                .collect(),
+            object_parameters: object_parameters.into_iter()
+                .map(|param_obj| ground_object(param_obj, variable))
+                .collect()
+        }
+    }
+}
+
+fn ground_object(obj: Object, variable: usize) -> Object {
+    match obj {
+        Object::Atomic(_) | Object::SortedAtomic(_, _) => obj,
+        Object::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => Object::SyntaxComposite {
+            syntax_ref,
+            wff_parameters: wff_parameters.into_iter()
+                .map(|param_wff| ground_wff(param_wff, variable))
+                .collect(),
+            object_parameters: object_parameters.into_iter()
+                .map(|param_obj| ground_object(param_obj, variable))
+                .collect()
+        }
+    }
+}
+
+// The formula of a goal or of an assumption as written in the proof. 'names' ends with the fresh wff variable
+//  the metavariables are grounded to
+fn written_formula(
+    tree: &GoalTree, formula: &WellFormedFormula, syntaxes: &Vec<Syntax>, names: &VariableNames, line_no: usize
+) -> Result<String, CompileError> {
+    let formula = ground_wff(apply_unifier_wff(formula, &tree.unifier), names.wffs.len() - 1);
+    if has_metavariables(&formula) {
+        return Err(CompileError::UninferableFormula(line_no));
+    };
+    Ok(wff_to_string(&formula, syntaxes, names))
+}
+
+// Writes the proof of a goal after the ones of its subgoals, returns its line number.
+// 'assumption_lines' gives the line assuming the antecedent of each discharged goal being written
+fn write_goal_proof(
+    tree: &GoalTree, goal_id: usize, syntaxes: &Vec<Syntax>, names: &VariableNames, lines: &mut Vec<String>,
+    assumption_lines: &mut HashMap<usize, usize>
+) -> Result<usize, CompileError> {
+    let goal = &tree.goals[goal_id];
+    let (name, used_lines) = match &goal.justification {
+        None => return Err(CompileError::OpenGoal(goal_id)),
+        Some(Justification::Hypothesis(name)) => (name.as_str(), Vec::new()),
+        Some(Justification::Statement(name, subgoals)) => {
+            let used_lines = subgoals.iter()
+                .map(|subgoal| write_goal_proof(tree, *subgoal, syntaxes, names, lines, assumption_lines))
+                .collect::<Result<Vec<_>, _>>()?;
+            (name.as_str(), used_lines)
+        },
+        // The goals under the discharged one are proven inside the block of its assumption
+        Some(Justification::Discharge(antecedent, subgoal)) => {
+            let assumption_line = lines.len() + 1;
+            let assumption = written_formula(tree, antecedent, syntaxes, names, assumption_line)?;
+            lines.push(format!("{assumption_line}; ; assume; {assumption}"));
+            assumption_lines.insert(goal_id, assumption_line);
+            let used_line = write_goal_proof(tree, *subgoal, syntaxes, names, lines, assumption_lines)?;
+            assumption_lines.remove(&goal_id);
+            ("discharge", vec![used_line])
+        },
+        Some(Justification::Assumption(discharged_goal)) => match assumption_lines.get(discharged_goal) {
+            Some(assumption_line) => return Ok(*assumption_line),
+            None => return Err(CompileError::InaccessibleAssumption(*discharged_goal, goal_id))
+        }
+    };
+    let line_no = lines.len() + 1;
+    let formula = written_formula(tree, &goal.formula, syntaxes, names, line_no)?;
+    let used_lines = used_lines.iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    lines.push(format!("{line_no}; {used_lines}; {name}; {formula}"));
+    Ok(line_no)
+}
+
+// Writes the lines proving the root goal after the given proof lines, once every goal is proven
+pub fn append_proof(
+    tree: &GoalTree, syntaxes: &Vec<Syntax>, names: &VariableNames, lines: &mut Vec<String>
+) -> Result<(), CompileError> {
+    if let Some(goal_id) = open_goals(tree).first() {
+        return Err(CompileError::OpenGoal(*goal_id));
+    };
+    // The grounded metavariables become a wff variable the statement doesn't use, which only appears in the proof
+    let mut names = VariableNames { wffs: names.wffs.clone(), objects: names.objects.clone() };
+    let Some(fresh_name) = ('𝛼'..='𝜔').find(|c| !names.wffs.contains(c)) else {
+        return Err(CompileError::UninferableFormula(lines.len() + 1));
+    };
+    names.wffs.push(fresh_name);
+    write_goal_proof(tree, 0, syntaxes, &names, lines, &mut HashMap::new())?;
+    Ok(())
+}
+
+// The lines of the "# Proof" section proving the root goal, once every goal is proven
+pub fn export_proof(
+    tree: &GoalTree, syntaxes: &Vec<Syntax>, names: &VariableNames
+) -> Result<Vec<String>, CompileError> {
+    let mut lines = Vec::new();
+    append_proof(tree, syntaxes, names, &mut lines)?;
+    Ok(lines)
+}
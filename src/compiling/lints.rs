@@ -0,0 +1,69 @@
+use super::{
+    Reference, LogicStep,
+    WellFormedFormula,
+    CompileWarning
+};
+
+// The preceding lines a step relies on: its used lines, the proven relation of "rw" and the assumption of a discharge
+fn relied_lines(step: &LogicStep) -> Vec<usize> {
+    let mut lines = step.used_hypotheses.clone();
+    if let Reference::LineReference(line) = step.theorem_ref {
+        lines.push(line);
+    };
+    lines
+}
+
+// Looks for hypotheses no line uses, lines which don't lead to an assertion and lines proving the formula
+//  of a preceding line they could use instead, in a proof whose blocks haven't been expanded yet
+pub fn lint_proof(
+    name: &str, hypot_names: &[String], proof: &Vec<LogicStep>, assertions: &[WellFormedFormula],
+    assumption_blocks: &[(usize, usize)]
+) -> Vec<CompileWarning> {
+    let mut warnings = Vec::new();
+    let mut used_hypotheses = vec![false; hypot_names.len()];
+    for step in proof {
+        if let Reference::HypothesisReference(hypot_id) = step.theorem_ref {
+            used_hypotheses[hypot_id] = true;
+        };
+    };
+    for (hypot_name, used) in hypot_names.iter().zip(used_hypotheses) {
+        if !used {
+            warnings.push(CompileWarning::UnusedHypothesis(name.to_owned(), hypot_name.clone()));
+        };
+    };
+    // The lines leading to the first proof of each assertion, preferably outside of the blocks
+    let in_block = |line: usize| assumption_blocks.iter()
+        .any(|(assume, discharge)| *assume <= line && line < *discharge);
+    let mut needed = vec![false; proof.len()];
+    let mut to_visit = assertions.iter()
+        .filter_map(|assertion| {
+            let proves = |line: &usize| proof[*line].resulting_formula == *assertion;
+            (0..proof.len()).find(|line| proves(line) && !in_block(*line))
+                .or_else(|| (0..proof.len()).find(proves))
+        })
+        .collect::<Vec<_>>();
+    while let Some(line) = to_visit.pop() {
+        if needed[line] {
+            continue;
+        };
+        needed[line] = true;
+        to_visit.extend(relied_lines(&proof[line]));
+    };
+    for (line, _) in needed.iter().enumerate().filter(|(_, needed)| !**needed) {
+        warnings.push(CompileWarning::UnusedStep(name.to_owned(), line + 1));
+    };
+    for (line, step) in proof.iter().enumerate() {
+        // Assuming a formula again opens a new block, which is not a repetition
+        if matches!(step.theorem_ref, Reference::LineReference(assumption) if assumption == line) {
+            continue;
+        };
+        let accessible = |previous: usize| !assumption_blocks.iter()
+            .any(|(assume, discharge)| *assume <= previous && previous < *discharge && *discharge <= line);
+        let duplicated = (0..line)
+            .find(|previous| accessible(*previous) && proof[*previous].resulting_formula == step.resulting_formula);
+        if let Some(previous) = duplicated {
+            warnings.push(CompileWarning::DuplicateStep(name.to_owned(), line + 1, previous + 1));
+        };
+    };
+    warnings
+}
@@ -1,39 +1,191 @@
 use std::collections::HashMap;
-use crate::parsing::{MathFile, FormulaChar, DefinitionType};
+use crate::parsing::{MathFile, FormulaChar, DefinitionType, Formula, LineLabel, parse_proof_line};
 use super::{
-    Syntax, Axiom, Theorem, Definition,
-    SyntaxType, Placeholder, Reference, LogicStep,
-    WellFormedFormula, Object,
-    compile_formula,
-    formula_is_contained,
-    formula_is_substitution,
-    CompileError
+    Sort, Syntax, Axiom, Theorem, Definition, Library,
+    SyntaxType, SyntaxRole, Placeholder, Reference, LogicStep, StepKind, DeductionRecipe,
+    WellFormedFormula, Object, Variable, VariableNames,
+    compile_formula, compile_object, variable_sort, repetition_group,
+    find_ambiguous_example,
+    find_substitution,
+    distinct_variables_requirements,
+    substitution_captures,
+    check_definition,
+    variable_names,
+    Unifier, new_metavariables, rename_wff, apply_unifier_wff, has_metavariables, unify_wffs, unify_objects,
+    Substitution,
+    unfolds_definition, relation_sides, rewrites_with_line, find_rewrite_substitution,
+    implication, expand_assumptions,
+    lint_proof,
+    fill_used_lines,
+    expand_tactics,
+    CompileError, CompileWarning
 };
 
-pub fn compile_syntax(file: MathFile, syntaxes: &Vec<Syntax>)
+// Keywords of the proof lines, which are read before looking up the names of the library
+const RESERVED_NAMES: [&str; 5] = ["unfold", "fold", "rw", "assume", "discharge"];
+
+fn check_name(name: &str) -> Result<(), CompileError> {
+    match RESERVED_NAMES.contains(&name) {
+        true => Err(CompileError::ReservedName(name.to_owned())),
+        false => Ok(())
+    }
+}
+
+fn add_distinct_pair(pairs: &mut Vec<(Variable, Variable)>, pair: (Variable, Variable)) {
+    let pair = (pair.0.min(pair.1), pair.0.max(pair.1));
+    if !pairs.contains(&pair) {
+        pairs.push(pair);
+    };
+}
+
+fn compile_distinct_variables(
+    lines: Vec<Formula>,
+    wffs: &HashMap<usize, WellFormedFormula>, objects: &HashMap<usize, Object>
+) -> Result<Vec<(Variable, Variable)>, CompileError> {
+    let mut pairs = Vec::new();
+    for (line_no, line) in lines.into_iter().enumerate() {
+        let variables = line.into_iter()
+            .map(|c| match c {
+                FormulaChar::Wff(id) => match wffs.get(&id) {
+                    Some(WellFormedFormula::Atomic(n)) => Some(Variable::Wff(*n)),
+                    _ => None
+                },
+                FormulaChar::Object(id) => match objects.get(&id) {
+                    Some(Object::Atomic(n)) => Some(Variable::Object(*n)),
+                    _ => None
+                },
+                FormulaChar::Char(c) => match objects.get(&(c as usize)) {
+                    Some(Object::SortedAtomic(_, n)) => Some(Variable::Object(*n)),
+                    _ => None
+                },
+                _ => None
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(CompileError::InvalidDistinctVariables(line_no+1))?;
+        for (i, var1) in variables.iter().enumerate() {
+            for var2 in &variables[i+1..] {
+                if var1 == var2 {
+                    return Err(CompileError::InvalidDistinctVariables(line_no+1));
+                };
+                add_distinct_pair(&mut pairs, (*var1, *var2));
+            };
+        };
+    };
+    Ok(pairs)
+}
+
+// Gives the repeated placeholder of a variadic syntax the last id of its kind,
+//  so that the elements of the repetition come last in the parameters of the syntax.
+// The repetition must be unique, and its placeholder can neither appear elsewhere nor be bound
+fn move_repeated_placeholder_last(
+    formula: &mut [Placeholder], bound_objects: &mut [usize], wff_count: usize, object_count: usize
+) -> Option<()> {
+    if formula.iter().filter(|pl| matches!(pl, Placeholder::Repetition)).count() != 1 {
+        return None;
+    };
+    let (repeated, _) = repetition_group(formula)?;
+    let (is_wff, repeated_id) = match formula[repeated] {
+        Placeholder::WellFormedFormula(id) => (true, id),
+        Placeholder::Object(id) | Placeholder::Sorted(_, id) => (false, id),
+        _ => return None
+    };
+    let occurrences = formula.iter()
+        .filter(|pl| match pl {
+            Placeholder::WellFormedFormula(id) => is_wff && *id == repeated_id,
+            Placeholder::Object(id) | Placeholder::Sorted(_, id) => !is_wff && *id == repeated_id,
+            _ => false
+        })
+        .count();
+    if occurrences != 1 || (!is_wff && bound_objects.contains(&repeated_id)) {
+        return None;
+    };
+    let last_id = match is_wff {
+        true => wff_count - 1,
+        false => object_count - 1
+    };
+    let renumber = |id: usize| match id {
+        id if id == repeated_id => last_id,
+        id if id > repeated_id => id - 1,
+        id => id
+    };
+    for pl in formula.iter_mut() {
+        match pl {
+            Placeholder::WellFormedFormula(id) if is_wff => *id = renumber(*id),
+            Placeholder::Object(id) | Placeholder::Sorted(_, id) if !is_wff => *id = renumber(*id),
+            _ => ()
+        };
+    };
+    if !is_wff {
+        for id in bound_objects.iter_mut() {
+            *id = renumber(*id);
+        };
+    };
+    Some(())
+}
+
+pub fn compile_sort(file: MathFile, sorts: &[Sort]) -> Result<Sort, CompileError> {
+    let (name, variables) = match file {
+        MathFile::Sort { name, variables } => (name, variables),
+        _ => return Err(CompileError::IncorrectFileType)
+    };
+    if sorts.iter().any(|sort| sort.name == name) {
+        return Err(CompileError::DuplicateSort(name));
+    };
+    for (index, c) in variables.iter().enumerate() {
+        let is_builtin = ('𝑎' <= *c && *c <= '𝑧') || ('𝛼' <= *c && *c <= '𝜔');  // NOT ascii
+        let already_used = variables[..index].contains(c)
+            || sorts.iter().any(|sort| sort.variables.contains(c));
+        // Sort variables are keyed by their character next to the ids 0..26 of '𝑎'..'𝑧', control characters would collide
+        if is_builtin || already_used || *c == '…' || c.is_control() {
+            return Err(CompileError::InvalidSortVariable(*c));
+        };
+    };
+    Ok(Sort { name, variables })
+}
+
+pub fn compile_syntax(file: MathFile, sorts: &[Sort], syntaxes: &[Syntax])
 -> Result<(Syntax, Option<(String, Vec<FormulaChar>)>), CompileError>
 {
-    let (name, def_type, syntax, definition) = match file {
+    let (name, def_type, syntax, spaced_after, bound_variables, precedence, associativity, role, definition) = match file {
         MathFile::SyntaxDefinition {
             name,
             definition_type,
             syntax,
+            spacing,
+            bound_variables,
+            precedence,
+            associativity,
+            role,
             definition
-        } => (name, definition_type, syntax, definition),
+        } => (name, definition_type, syntax, spacing, bound_variables, precedence, associativity, role, definition),
         _ => return Err(CompileError::IncorrectFileType)
     };
+    check_name(&name)?;
     let syntax_type = match def_type {
         DefinitionType::Formula => SyntaxType::Formula,
-        DefinitionType::Object => SyntaxType::Object
+        DefinitionType::Object => SyntaxType::Object,
+        DefinitionType::Sort(sort_name) => match sorts.iter().position(|sort| sort.name == sort_name) {
+            Some(sort) => SyntaxType::Sort(sort),
+            None => return Err(CompileError::UnknownSort(sort_name))
+        }
     };
     let mut wff_mapping = [None; 32];
     let mut next_wff_id = 0;
     let mut obj_mapping = [None; 32];
     let mut next_obj_id = 0;
+    let mut sorted_mapping = HashMap::new();
     let mut formula = Vec::new();
     for ch in syntax {
         match ch {
-            FormulaChar::Char(c) => formula.push(Placeholder::LiteralChar(c)),
+            FormulaChar::Char(c) => match (variable_sort(c, sorts), sorted_mapping.get(&c)) {
+                (None, _) => formula.push(Placeholder::LiteralChar(c)),
+                (Some(sort), Some(n)) => formula.push(Placeholder::Sorted(sort, *n)),
+                (Some(sort), None) => {
+                    formula.push(Placeholder::Sorted(sort, next_obj_id));
+                    sorted_mapping.insert(c, next_obj_id);
+                    next_obj_id += 1;
+                }
+            },
             FormulaChar::RepetitionChar => formula.push(Placeholder::Repetition),
             FormulaChar::Wff(id) => match wff_mapping[id] {
                 Some(n) => formula.push(Placeholder::WellFormedFormula(n)),
@@ -46,195 +198,696 @@ pub fn compile_syntax(file: MathFile, syntaxes: &Vec<Syntax>)
             FormulaChar::Object(id) => match obj_mapping[id] {
                 Some(n) => formula.push(Placeholder::Object(n)),
                 None => {
-                    formula.push(Placeholder::WellFormedFormula(next_obj_id));
+                    formula.push(Placeholder::Object(next_obj_id));
                     obj_mapping[id] = Some(next_obj_id);
                     next_obj_id += 1;
                 }
             }
         };
     };
+    let mut bound_objects = bound_variables.into_iter()
+        .map(|c| match c {
+            FormulaChar::Object(id) => obj_mapping[id],
+            FormulaChar::Char(c) => sorted_mapping.get(&c).copied(),
+            _ => None
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or(CompileError::InvalidBoundVariable(name.clone()))?;
+    if formula.iter().any(|pl| matches!(pl, Placeholder::Repetition)) {
+        move_repeated_placeholder_last(&mut formula, &mut bound_objects, next_wff_id, next_obj_id)
+            .ok_or(CompileError::InvalidRepetition(name.clone()))?;
+    };
+    // An equivalence relates two formulas, an equality two objects
+    let parameters = formula.iter()
+        .filter(|pl| !matches!(pl, Placeholder::LiteralChar(_)))
+        .collect::<Vec<_>>();
+    let valid_role = match (role, &syntax_type, &parameters[..]) {
+        (None, _, _) => true,
+        (
+            Some(SyntaxRole::Equivalence),
+            SyntaxType::Formula,
+            [Placeholder::WellFormedFormula(0), Placeholder::WellFormedFormula(1)]
+        ) => true,
+        (
+            Some(SyntaxRole::Equality),
+            SyntaxType::Formula,
+            [Placeholder::Object(0) | Placeholder::Sorted(_, 0), Placeholder::Object(1) | Placeholder::Sorted(_, 1)]
+        ) => bound_objects.is_empty(),
+        _ => false
+    };
+    if !valid_role {
+        return Err(CompileError::InvalidSyntaxRole(name));
+    };
+    let syntax = Syntax {
+        syntax_type,
+        formula,
+        spaced_after,
+        bound_objects,
+        precedence,
+        associativity,
+        role,
+        distinct_wff_count: next_wff_id,
+        distinct_object_count: next_obj_id
+    };
     // verify syntax doesn't make the compiling ambiguous
-    for other_syntax in syntaxes {
-        let other_formula = &other_syntax.formula;
-        if formula_is_contained(&formula, other_formula) {
-            return Err(CompileError::AmbiguousSyntax(name));
-        };
-        if formula_is_contained(other_formula, &formula) {
-            return Err(CompileError::AmbiguousSyntax(name));
-        }
+    let mut extended_syntaxes = syntaxes.to_vec();
+    extended_syntaxes.push(syntax.clone());
+    if let Some(example) = find_ambiguous_example(sorts, &extended_syntaxes) {
+        return Err(CompileError::AmbiguousSyntax(name, example));
     };
 
     let name_def = match definition {
         Some(def) => Some((name, def)),
         None => None
     };
-    Ok((
-        Syntax {
-            syntax_type,
-            formula,
-            distinct_wff_count: next_wff_id,
-            distinct_object_count: next_obj_id
-        },
-        name_def
-    ))
+    Ok((syntax, name_def))
 }
 
-pub fn compile_definition(name: String, def: Vec<FormulaChar>, syntaxes: &Vec<Syntax>) -> Result<Definition, CompileError> {
+pub fn compile_definition(
+    name: String, def: Vec<FormulaChar>, sorts: &[Sort], syntaxes: &Vec<Syntax>
+) -> Result<Definition, CompileError> {
     let mut wffs = HashMap::<usize, WellFormedFormula>::new();
     let mut objects = HashMap::<usize, Object>::new();
-    let definition = compile_formula(def, syntaxes, &mut wffs, &mut objects)?;
+    let definition = compile_formula(def, sorts, syntaxes, &mut wffs, &mut objects)?;
+    check_definition(&name, &definition, syntaxes)?;
     Ok(
         Definition {
             name,
             definition,
             distinct_wff_count: wffs.len(),
-            distinct_object_count: objects.len()
+            distinct_object_count: objects.len(),
+            variable_names: variable_names(&wffs, &objects)
         }
     )
 }
 
-pub fn compile_axiom(file: MathFile, syntaxes: &Vec<Syntax>) -> Result<Axiom, CompileError> {
+pub fn compile_axiom(file: MathFile, sorts: &[Sort], syntaxes: &Vec<Syntax>) -> Result<Axiom, CompileError> {
     let mut wffs = HashMap::<usize, WellFormedFormula>::new();
     let mut objects = HashMap::<usize, Object>::new();
-    let (name, hypotheses, assertions) = match file {
+    let (name, hypotheses, assertions, distinct_variables) = match file {
         MathFile::Axiom {
             name,
             hypotheses,
-            assertions
-        } => (name, hypotheses, assertions),
+            assertions,
+            distinct_variables
+        } => (name, hypotheses, assertions, distinct_variables),
         _ => return Err(CompileError::IncorrectFileType)
     };
+    check_name(&name)?;
     let compiled_hypotheses = hypotheses.into_iter()
-        .map(|hyp| compile_formula(hyp, syntaxes, &mut wffs, &mut objects))
+        .map(|hyp| compile_formula(hyp, sorts, syntaxes, &mut wffs, &mut objects))
         .collect::<Result<Vec<_>, _>>()?;
     let compiled_assertions = assertions.into_iter()
-        .map(|ass| compile_formula(ass, syntaxes, &mut wffs, &mut objects))
+        .map(|ass| compile_formula(ass, sorts, syntaxes, &mut wffs, &mut objects))
         .collect::<Result<Vec<_>, _>>()?;
+    let distinct_variables = compile_distinct_variables(distinct_variables, &wffs, &objects)?;
     Ok(Axiom {
         name,
         hypotheses: compiled_hypotheses,
         assertions: compiled_assertions,
+        distinct_variables,
         distinct_wff_count: wffs.len(),
-        distinct_object_count: objects.len()
+        distinct_object_count: objects.len(),
+        variable_names: variable_names(&wffs, &objects)
     })
 }
 
+// Only the conditions on the variables of the statement are propagated,
+//  variables that only appear in the proof can always be renamed
+fn propagate_requirements(
+    requirements: Vec<(Variable, Variable)>, distinct_variables: &mut Vec<(Variable, Variable)>,
+    statement_wff_count: usize, statement_object_count: usize
+) {
+    for (var1, var2) in requirements {
+        let in_statement = |var: Variable| match var {
+            Variable::Wff(id) => id < statement_wff_count,
+            Variable::Object(id) => id < statement_object_count
+        };
+        if in_statement(var1) && in_statement(var2) {
+            add_distinct_pair(distinct_variables, (var1, var2));
+        };
+    };
+}
+
+const NO_HYPOTHESES: &Vec<WellFormedFormula> = &Vec::new();
+const NO_DISTINCT_VARIABLES: &Vec<(Variable, Variable)> = &Vec::new();
+
+// A statement as cited in a proof: (hypotheses, assertion, distinct variables, wff count, object count)
+pub type CitedStatement<'a> = (&'a Vec<WellFormedFormula>, &'a WellFormedFormula, &'a Vec<(Variable, Variable)>, usize, usize);
+
+// The statement cited by a proof step
+pub fn cited_statement<'a>(
+    reference: &Reference, definitions: &'a [Definition], axioms: &'a [Axiom], theorems: &'a [Theorem],
+    lemmas: &'a [Theorem]
+) -> Option<CitedStatement<'a>> {
+    match reference {
+        Reference::DefinitionReference(def_id) => {
+            let def = definitions.get(*def_id)?;
+            Some((NO_HYPOTHESES, &def.definition, NO_DISTINCT_VARIABLES, def.distinct_wff_count, def.distinct_object_count))
+        },
+        Reference::AxiomReference(ax_id, assert_id) => {
+            let ax = axioms.get(*ax_id)?;
+            let assertion = ax.assertions.get(*assert_id)?;
+            Some((&ax.hypotheses, assertion, &ax.distinct_variables, ax.distinct_wff_count, ax.distinct_object_count))
+        },
+        Reference::TheoremReference(theo_id, assert_id) | Reference::LemmaReference(theo_id, assert_id) => {
+            let theo = match reference {
+                Reference::LemmaReference(..) => lemmas.get(*theo_id)?,
+                _ => theorems.get(*theo_id)?
+            };
+            let assertion = theo.assertions.get(*assert_id)?;
+            Some((&theo.hypotheses, assertion, &theo.distinct_variables, theo.distinct_wff_count, theo.distinct_object_count))
+        },
+        Reference::HypothesisReference(_) | Reference::LineReference(_) => None
+    }
+}
+
+fn cited_variable_names<'a>(
+    reference: &Reference, definitions: &'a [Definition], axioms: &'a [Axiom], theorems: &'a [Theorem],
+    lemmas: &'a [Theorem]
+) -> Option<&'a VariableNames> {
+    match reference {
+        Reference::DefinitionReference(def_id) => definitions.get(*def_id).map(|def| &def.variable_names),
+        Reference::AxiomReference(ax_id, _) => axioms.get(*ax_id).map(|ax| &ax.variable_names),
+        Reference::TheoremReference(theo_id, _) => theorems.get(*theo_id).map(|theo| &theo.variable_names),
+        Reference::LemmaReference(lemma_id, _) => lemmas.get(*lemma_id).map(|lemma| &lemma.variable_names),
+        Reference::HypothesisReference(_) | Reference::LineReference(_) => None
+    }
+}
+
+// Substitution annotated on a proof line, where the variables are the ones of the cited statement
+//  and the values are compiled with the variables of the theorem
+struct AnnotatedSubstitution {
+    wffs: Vec<(char, usize, WellFormedFormula)>,
+    objects: Vec<(char, usize, Object)>
+}
+
+fn compile_annotation(
+    annotation: Vec<(char, Formula)>, names: &VariableNames, index: usize, sorts: &[Sort], syntaxes: &Vec<Syntax>,
+    wffs: &mut HashMap<usize, WellFormedFormula>, objects: &mut HashMap<usize, Object>
+) -> Result<AnnotatedSubstitution, CompileError> {
+    let mut res = AnnotatedSubstitution { wffs: Vec::new(), objects: Vec::new() };
+    for (variable, value) in annotation {
+        // A variable given twice disagrees with itself
+        let already_given = res.wffs.iter().any(|(c, _, _)| *c == variable)
+            || res.objects.iter().any(|(c, _, _)| *c == variable);
+        if already_given {
+            return Err(CompileError::SubstitutionMismatch(variable, index));
+        };
+        match (names.wffs.iter().position(|c| *c == variable), names.objects.iter().position(|c| *c == variable)) {
+            (Some(id), _) => res.wffs.push((variable, id, compile_formula(value, sorts, syntaxes, wffs, objects)?)),
+            (None, Some(id)) => res.objects.push((variable, id, compile_object(value, sorts, syntaxes, wffs, objects)?)),
+            (None, None) => return Err(CompileError::UnknownSubstitutedVariable(variable, index))
+        };
+    };
+    Ok(res)
+}
+
+// The first annotated variable whose value differs from the one of the substitution
+fn annotation_mismatch(annotation: &AnnotatedSubstitution, substitution: &Substitution) -> Option<char> {
+    let wff_mismatch = annotation.wffs.iter()
+        .find(|(_, id, value)| matches!(substitution.wffs.get(*id), Some(Some(wff)) if *wff != value));
+    let object_mismatch = annotation.objects.iter()
+        .find(|(_, id, value)| matches!(substitution.objects.get(*id), Some(Some(obj)) if *obj != value));
+    match (wff_mismatch, object_mismatch) {
+        (Some((variable, _, _)), _) | (None, Some((variable, _, _))) => Some(*variable),
+        (None, None) => None
+    }
+}
+
+// The formula of a preceding line, which must not depend on metavariables
+fn known_formula(
+    compiled_proof: &[LogicStep], line: usize, index: usize, unifier: &Unifier
+) -> Result<WellFormedFormula, CompileError> {
+    let Some(step) = line.checked_sub(1).and_then(|idx| compiled_proof.get(idx)) else {
+        return Err(CompileError::InaccessibleHypothesis(line, index));
+    };
+    let formula = apply_unifier_wff(&step.resulting_formula, unifier);
+    match has_metavariables(&formula) {
+        true => Err(CompileError::UninferableFormula(line)),
+        false => Ok(formula)
+    }
+}
+
+// Checks a proof step that applies a cited statement as a whole
+pub fn check_assertion_step(
+    resulting_formula: &WellFormedFormula, used_hypotheses: &Vec<WellFormedFormula>,
+    (theo_hypotheses, theo_assertion, theo_distinct, wff_count, object_count): CitedStatement,
+    index: usize, syntaxes: &Vec<Syntax>,
+    distinct_variables: &mut Vec<(Variable, Variable)>, (statement_wff_count, statement_object_count): (usize, usize)
+) -> Result<(), CompileError> {
+    let Some(substitution) = find_substitution(
+        resulting_formula, used_hypotheses, theo_hypotheses, theo_assertion, wff_count, object_count
+    ) else {
+        return Err(CompileError::IncorrectResultingFormula(index));
+    };
+    let captures = theo_hypotheses.iter()
+        .chain(Some(theo_assertion))
+        .any(|pattern| substitution_captures(pattern, &substitution, syntaxes));
+    if captures {
+        return Err(CompileError::VariableCapture(index));
+    };
+    let Ok(requirements) = distinct_variables_requirements(&substitution, theo_distinct) else {
+        return Err(CompileError::DistinctVariablesViolation(index));
+    };
+    propagate_requirements(requirements, distinct_variables, statement_wff_count, statement_object_count);
+    Ok(())
+}
+
+// Resolves "<name>" or "<name>.<assertion id>" to a lemma of the file, a definition, an axiom or a theorem
+pub fn statement_reference(
+    theorem_name: &str, index: usize,
+    references: &HashMap<String, Reference>, lemma_references: &HashMap<String, Reference>
+) -> Result<Reference, CompileError> {
+    let (theo_name, assert_id) = match theorem_name.split_once('.') {
+        None => (theorem_name, 0),
+        Some((name, id)) => {
+            let Ok(id) = id.parse::<usize>() else {
+                return Err(CompileError::UnknownTheorem(theorem_name.to_owned(), index));
+            };
+            (name, id)
+        }
+    };
+    let Some(reference) = lemma_references.get(theo_name).or_else(|| references.get(theo_name)) else {
+        return Err(CompileError::UnknownTheorem(theo_name.to_owned(), index));
+    };
+    match reference {
+        Reference::DefinitionReference(def_id) => Ok(Reference::DefinitionReference(*def_id)),
+        Reference::AxiomReference(ax_id, 0) => Ok(Reference::AxiomReference(*ax_id, assert_id)),
+        Reference::TheoremReference(theo_id, 0) => Ok(Reference::TheoremReference(*theo_id, assert_id)),
+        Reference::LemmaReference(lemma_id, 0) => Ok(Reference::LemmaReference(*lemma_id, assert_id)),
+        _ => Err(CompileError::WeirdReference)
+    }
+}
+
+// An assertion without hypotheses, used to rewrite inside a formula, with its reference
+fn relation_reference<'a>(
+    theorem_name: &str, index: usize, context: &ProofContext<'a>
+) -> Result<(CitedStatement<'a>, Reference), CompileError> {
+    let theo_ref = statement_reference(theorem_name, index, context.references, context.lemma_references)?;
+    let Some(statement) = cited_statement(
+        &theo_ref, context.definitions, context.axioms, context.theorems, context.lemmas
+    ) else {
+        return Err(CompileError::UnknownTheorem(theorem_name.to_owned(), index));
+    };
+    if !statement.0.is_empty() {
+        return Err(CompileError::NotARelation(index));
+    };
+    Ok((statement, theo_ref))
+}
+
+// What a proof can cite, the lemmas of its file included
+pub struct ProofContext<'a> {
+    pub sorts: &'a Vec<Sort>,
+    pub syntaxes: &'a Vec<Syntax>,
+    pub definitions: &'a Vec<Definition>,
+    pub axioms: &'a Vec<Axiom>,
+    pub theorems: &'a Vec<Theorem>,
+    pub lemmas: &'a Vec<Theorem>,
+    pub deduction: &'a Option<DeductionRecipe>,
+    pub references: &'a HashMap<String, Reference>,
+    pub lemma_references: &'a HashMap<String, Reference>
+}
+
+fn proof_context<'a>(
+    lib: &'a Library, lemmas: &'a Vec<Theorem>,
+    references: &'a HashMap<String, Reference>, lemma_references: &'a HashMap<String, Reference>
+) -> ProofContext<'a> {
+    ProofContext {
+        sorts: &lib.sorts,
+        syntaxes: &lib.syntaxes,
+        definitions: &lib.definitions,
+        axioms: &lib.axioms,
+        theorems: &lib.theorems,
+        lemmas,
+        deduction: &lib.deduction,
+        references,
+        lemma_references
+    }
+}
+
+// A proof line as written: (used lines, formula)
+pub type WrittenLine = (Vec<usize>, WellFormedFormula);
+
+// The lemmas of the file are compiled first and added to 'lemmas', each one can use the preceding ones.
+//  'lemmas' stands for the lemmas of the library, the ones of 'lib' are left aside.
+// Also gives the used lines (found ones included) and the formulas of the proof lines as written, for the theorem
+//  then for its lemmas, and adds the warnings about their proofs to 'warnings'
 pub fn compile_theorem(
-    file: MathFile,
-    syntaxes: &Vec<Syntax>,
-    definitions: &Vec<Definition>,
-    axioms: &Vec<Axiom>,
-    theorems: &Vec<Theorem>,
-    references: &HashMap<String, Reference> 
-) -> Result<Theorem, CompileError> {
+    file: MathFile, lib: &Library, lemmas: &mut Vec<Theorem>,
+    references: &HashMap<String, Reference>, warnings: &mut Vec<CompileWarning>
+) -> Result<(Theorem, Vec<Vec<WrittenLine>>), CompileError> {
+    let MathFile::Theorem { name, hypotheses, assertions, distinct_variables, proof, tactics, lemmas: lemma_files } = file else {
+        return Err(CompileError::IncorrectFileType);
+    };
+    let mut lemma_references = HashMap::new();
+    let mut written_lines = vec![Vec::new()];
+    for lemma_file in lemma_files {
+        let MathFile::Theorem { name: lemma_name, .. } = &lemma_file else {
+            return Err(CompileError::IncorrectFileType);
+        };
+        let lemma_name = lemma_name.clone();
+        if lemma_references.contains_key(&lemma_name) {
+            return Err(CompileError::DuplicateLemma(lemma_name));
+        };
+        let context = proof_context(lib, lemmas, references, &lemma_references);
+        let (lemma, lemma_lines) = compile_proven_statement(lemma_file, &context, warnings)
+            .map_err(|e| CompileError::InLemma(lemma_name.clone(), Box::new(e)))?;
+        lemma_references.insert(lemma_name, Reference::LemmaReference(lemmas.len(), 0));
+        lemmas.push(lemma);
+        written_lines.push(lemma_lines);
+    };
+    let file = MathFile::Theorem { name, hypotheses, assertions, distinct_variables, proof, tactics, lemmas: Vec::new() };
+    let context = proof_context(lib, lemmas, references, &lemma_references);
+    let (theorem, theorem_lines) = compile_proven_statement(file, &context, warnings)?;
+    written_lines[0] = theorem_lines;
+    Ok((theorem, written_lines))
+}
+
+fn compile_proven_statement(
+    file: MathFile, context: &ProofContext, warnings: &mut Vec<CompileWarning>
+) -> Result<(Theorem, Vec<WrittenLine>), CompileError> {
+    let ProofContext {
+        sorts, syntaxes, definitions, axioms, theorems, lemmas, deduction, references, lemma_references
+    } = *context;
     let mut wffs = HashMap::<usize, WellFormedFormula>::new();
     let mut objects = HashMap::<usize, Object>::new();
-    let (name, hypotheses, assertions, proof) = match file {
+    let (name, hypotheses, assertions, distinct_variables, proof, tactics) = match file {
         MathFile::Theorem {
             name,
             hypotheses,
             assertions,
-            proof
-        } => (name, hypotheses, assertions, proof),
+            distinct_variables,
+            proof,
+            tactics,
+            lemmas: _
+        } => (name, hypotheses, assertions, distinct_variables, proof, tactics),
         _ => return Err(CompileError::IncorrectFileType)
     };
+    check_name(&name)?;
     let mut hypot_names = HashMap::new();
+    let mut hypot_name_list = Vec::new();
     let mut hypot_list = Vec::new();
     for (index, (hypot_name, hypot)) in hypotheses.into_iter().enumerate() {
-        hypot_names.insert(hypot_name, index);
-        hypot_list.push(compile_formula(hypot, syntaxes, &mut wffs, &mut objects)?);
+        hypot_names.insert(hypot_name.clone(), index);
+        hypot_name_list.push(hypot_name);
+        hypot_list.push(compile_formula(hypot, sorts, syntaxes, &mut wffs, &mut objects)?);
     }
     let assertions = assertions.into_iter()
-        .map(|ass| compile_formula(ass, syntaxes, &mut wffs, &mut objects))
+        .map(|ass| compile_formula(ass, sorts, syntaxes, &mut wffs, &mut objects))
         .collect::<Result<Vec<_>, _>>()?;
+    let mut distinct_variables = compile_distinct_variables(distinct_variables, &wffs, &objects)?;
+    // The tactics are expanded into proof lines, which are then compiled as if they had been written
+    let expanded = !tactics.is_empty();
+    let proof = match expanded {
+        false => proof,
+        true => {
+            let named_hypotheses = hypot_name_list.iter().cloned()
+                .zip(hypot_list.iter().cloned())
+                .collect();
+            let lines = expand_tactics(
+                named_hypotheses, &assertions, &tactics, &variable_names(&wffs, &objects), context
+            )?;
+            let Ok(proof) = lines.iter().map(|line| parse_proof_line(line)).collect() else {
+                return Err(CompileError::ShouldNotBeReached);
+            };
+            proof
+        }
+    };
+    // Variables introduced past this point only appear in the proof
+    let statement_wff_count = wffs.len();
+    let statement_object_count = objects.len();
     // Proof compilation and verification
     let mut compiled_proof = Vec::new();
+    let mut line_labels = HashMap::new();
+    let mut unifier = Unifier { wffs: Vec::new(), objects: Vec::new() };
+    let mut unified_steps = Vec::new();
+    // Assumptions waiting for their discharge, and the (assumption, discharge) lines of the discharged ones
+    let mut open_assumptions = Vec::new();
+    let mut assumption_blocks = Vec::<(usize, usize)>::new();
+    // The used lines of each line as written, or as found when its column is left empty
+    let mut written_used_lines = Vec::new();
     for (
         i,
-        (index, used_hypots, theorem_name, formula)
+        (line_label, used_labels, theorem_name, annotation, formula)
     ) in proof.into_iter().enumerate() {
-        if index != i + 1 { return Err(CompileError::MissingProofLine(i+1)); };
+        let index = i + 1;
+        // Named lines are resolved to their numbers, a name only designating a preceding line
+        let used_hypots = used_labels.into_iter()
+            .map(|label| match label {
+                LineLabel::Number(n) => Ok(n),
+                LineLabel::Name(name) => match line_labels.get(&name) {
+                    Some(&n) => Ok(n),
+                    None => Err(CompileError::UnknownLineLabel(name, index))
+                }
+            }).collect::<Result<Vec<_>, _>>()?;
+        // The lines of a discharged block can only be used inside it
+        let discharged_line = used_hypots.iter()
+            .find(|line| assumption_blocks.iter().any(|(assume, discharge)| assume < *line && *line <= discharge));
+        if let Some(line) = discharged_line {
+            return Err(CompileError::InaccessibleHypothesis(*line, index));
+        };
+        match line_label {
+            LineLabel::Number(n) => if n != index {
+                return Err(CompileError::MissingProofLine(index));
+            },
+            LineLabel::Name(name) => if line_labels.insert(name.clone(), index).is_some() {
+                return Err(CompileError::DuplicateLineLabel(name, index));
+            }
+        };
+        written_used_lines.push(used_hypots.clone());
         // Hypothesis usage
         if let Some(&hypot_id) = hypot_names.get(&theorem_name) {
+            if !annotation.is_empty() {
+                return Err(CompileError::UnexpectedSubstitution(index));
+            };
             if used_hypots.len() != 0 {
                 return Err(CompileError::IncorrectNumberOfHypothesis(used_hypots.len(), 0, index));
             };
-            let resulting_formula = compile_formula(formula, syntaxes, &mut wffs, &mut objects)?;
+            let resulting_formula = match formula {
+                Some(formula) => compile_formula(formula, sorts, syntaxes, &mut wffs, &mut objects)?,
+                None => hypot_list[hypot_id].clone()
+            };
             if resulting_formula != hypot_list[hypot_id] {
                 return Err(CompileError::IncorrectResultingFormula(index));
             };
             compiled_proof.push(LogicStep {
                 used_hypotheses: used_hypots,  // Empty
                 theorem_ref: Reference::HypothesisReference(hypot_id),
+                kind: StepKind::Assertion,
                 resulting_formula
             });
             continue;
         };
-        // Definition/Axiom/Theorem usage
-        let (theo_name, assert_id) = match theorem_name.split_once('.') {
-            None => (theorem_name, 0),
-            Some((name, id)) => {
-                let Ok(id) = id.parse::<usize>() else {
-                    return Err(CompileError::UnknownTheorem(theorem_name, index));
-                };
-                (name.to_owned(), id)
-            }
-        };
-        let Some(reference) = references.get(&theo_name) else {
-            return Err(CompileError::UnknownTheorem(theo_name, index));
-        };
-        let empty_vec = vec![];
-        let (theo_hypotheses, theo_assertion,
-            wff_count, object_count, theo_ref) = match reference {
-            Reference::DefinitionReference(def_id) => {
-                match &definitions[*def_id] {
-                    Definition {
-                        name: _,
-                        definition,
-                        distinct_wff_count,
-                        distinct_object_count
-                    } => {
-                        let def_ref = Reference::DefinitionReference(*def_id);
-                        (&empty_vec, definition.clone(), *distinct_wff_count, *distinct_object_count, def_ref)
-                    }
-                }
-            },
-            Reference::AxiomReference(ax_id, 0) => {
-                match &axioms[*ax_id] {
-                    Axiom {
-                        name: _,
-                        hypotheses,
-                        assertions,
-                        distinct_wff_count,
-                        distinct_object_count
-                    } => {
-                        let Some(assertion) = assertions.get(assert_id) else {
-                            return Err(CompileError::UnknownTheorem(theo_name + "." + &assert_id.to_string(), index));
+        // Opening or closing a block whose lines may use its assumed formula, the discharge proving
+        //  "(<assumption> ⇒ <line>)" for a line of the block. Until the blocks are expanded, an assumption
+        //  refers to itself and a discharge to its assumption
+        if theorem_name == "assume" || theorem_name == "discharge" {
+            let Some(recipe) = deduction else {
+                return Err(CompileError::NoDeductionRecipe(index));
+            };
+            if !annotation.is_empty() {
+                return Err(CompileError::UnexpectedSubstitution(index));
+            };
+            let expected_len = match theorem_name.as_str() {
+                "assume" => 0,
+                _ => 1
+            };
+            if used_hypots.len() != expected_len {
+                return Err(CompileError::IncorrectNumberOfHypothesis(used_hypots.len(), expected_len, index));
+            };
+            let compiled_formula = match formula {
+                Some(formula) => Some(compile_formula(formula, sorts, syntaxes, &mut wffs, &mut objects)?),
+                None => None
+            };
+            let (assumption, resulting_formula) = match (theorem_name.as_str(), compiled_formula) {
+                ("assume", None) => return Err(CompileError::UninferableFormula(index)),
+                ("assume", Some(resulting_formula)) => {
+                    open_assumptions.push(i);
+                    (i, resulting_formula)
+                },
+                (_, compiled_formula) => {
+                    let Some(assumption) = open_assumptions.pop() else {
+                        return Err(CompileError::UnmatchedDischarge(index));
+                    };
+                    let line = used_hypots[0];
+                    if line == 0 || line > i {
+                        return Err(CompileError::InaccessibleHypothesis(line, index));
+                    };
+                    let discharged = implication(
+                        recipe,
+                        compiled_proof[assumption].resulting_formula.clone(),
+                        apply_unifier_wff(&compiled_proof[line - 1].resulting_formula, &unifier)
+                    );
+                    if let Some(resulting_formula) = &compiled_formula {
+                        if unify_wffs(&discharged, resulting_formula, &mut unifier, syntaxes).is_err() {
+                            return Err(CompileError::IncorrectResultingFormula(index));
                         };
-                        let ax_ref = Reference::AxiomReference(*ax_id, assert_id);
-                        (hypotheses, assertion.clone(), *distinct_wff_count, *distinct_object_count, ax_ref)
-                    }
+                    };
+                    assumption_blocks.push((assumption, i));
+                    (assumption, compiled_formula.unwrap_or(discharged))
                 }
-            },
-            Reference::TheoremReference(theo_id, 0) => {
-                match &theorems[*theo_id] {
-                    Theorem {
-                        name: _,
-                        hypotheses,
-                        assertions,
-                        proof: _,
-                        distinct_wff_count,
-                        distinct_object_count
-                    } => {
-                        let Some(assertion) = assertions.get(assert_id) else {
-                            return Err(CompileError::UnknownTheorem(theo_name + "." + &assert_id.to_string(), index));
-                        };
-                        let theo_ref = Reference::TheoremReference(*theo_id, assert_id);
-                        (hypotheses, assertion.clone(), *distinct_wff_count, *distinct_object_count, theo_ref)
-                    }
+            };
+            compiled_proof.push(LogicStep {
+                used_hypotheses: used_hypots.iter().map(|line| line - 1).collect(),
+                theorem_ref: Reference::LineReference(assumption),
+                kind: StepKind::Assertion,
+                resulting_formula
+            });
+            continue;
+        };
+        // Unfolding or folding a definition inside the formula of a previous line
+        let definition_step = match theorem_name.split_once(' ') {
+            Some(("unfold", def_name)) => Some((StepKind::Unfold, def_name.trim())),
+            Some(("fold", def_name)) => Some((StepKind::Fold, def_name.trim())),
+            _ => None
+        };
+        if let Some((kind, def_name)) = definition_step {
+            if !annotation.is_empty() {
+                return Err(CompileError::UnexpectedSubstitution(index));
+            };
+            let Some(&Reference::DefinitionReference(def_id)) = references.get(def_name) else {
+                return Err(CompileError::UnknownTheorem(def_name.to_owned(), index));
+            };
+            let [line] = used_hypots[..] else {
+                return Err(CompileError::IncorrectNumberOfHypothesis(used_hypots.len(), 1, index));
+            };
+            let source = known_formula(&compiled_proof, line, index, &unifier)?;
+            // Several occurrences could be replaced, so the formula can't be inferred
+            let Some(formula) = formula else {
+                return Err(CompileError::UninferableFormula(index));
+            };
+            let resulting_formula = compile_formula(formula, sorts, syntaxes, &mut wffs, &mut objects)?;
+            let valid = match kind {
+                StepKind::Fold => unfolds_definition(
+                    &definitions[def_id], &resulting_formula, &source, syntaxes
+                ),
+                _ => unfolds_definition(
+                    &definitions[def_id], &source, &resulting_formula, syntaxes
+                )
+            };
+            if !valid {
+                return Err(CompileError::IncorrectResultingFormula(index));
+            };
+            compiled_proof.push(LogicStep {
+                used_hypotheses: vec![line - 1],
+                theorem_ref: Reference::DefinitionReference(def_id),
+                kind,
+                resulting_formula
+            });
+            continue;
+        };
+        // Rewriting inside the formula of a previous line, with a proven equivalence or equality:
+        //  "rw" uses the first of two lines, "rw <name>" an axiom or a theorem without hypotheses
+        let rewrite_step = match theorem_name.split_once(' ') {
+            Some(("rw", rel_name)) => Some(Some(rel_name.trim())),
+            None if theorem_name == "rw" => Some(None),
+            _ => None
+        };
+        if let Some(rel_name) = rewrite_step {
+            let expected_len = match rel_name {
+                None => 2,
+                Some(_) => 1
+            };
+            if used_hypots.len() != expected_len {
+                return Err(CompileError::IncorrectNumberOfHypothesis(used_hypots.len(), expected_len, index));
+            };
+            let line = used_hypots[expected_len - 1];
+            let source = known_formula(&compiled_proof, line, index, &unifier)?;
+            // Several occurrences could be replaced, so the formula can't be inferred
+            let Some(formula) = formula else {
+                return Err(CompileError::UninferableFormula(index));
+            };
+            let resulting_formula = compile_formula(formula, sorts, syntaxes, &mut wffs, &mut objects)?;
+            let theorem_ref = match rel_name {
+                None => {
+                    if !annotation.is_empty() {
+                        return Err(CompileError::UnexpectedSubstitution(index));
+                    };
+                    let rel_line = used_hypots[0];
+                    let relation = known_formula(&compiled_proof, rel_line, index, &unifier)?;
+                    if relation_sides(&relation, syntaxes).is_none() {
+                        return Err(CompileError::NotARelation(index));
+                    };
+                    let valid = rewrites_with_line(
+                        &relation, &source, &resulting_formula, syntaxes
+                    );
+                    if !valid {
+                        return Err(CompileError::IncorrectResultingFormula(index));
+                    };
+                    Reference::LineReference(rel_line - 1)
+                },
+                Some(rel_name) => {
+                    let ((_, relation, rel_distinct, wff_count, object_count), rel_ref) = relation_reference(
+                        rel_name, index, context
+                    )?;
+                    if relation_sides(relation, syntaxes).is_none() {
+                        return Err(CompileError::NotARelation(index));
+                    };
+                    let Some(substitution) = find_rewrite_substitution(
+                        relation, wff_count, object_count, &source, &resulting_formula, syntaxes
+                    ) else {
+                        return Err(CompileError::IncorrectResultingFormula(index));
+                    };
+                    let Some(names) = cited_variable_names(&rel_ref, definitions, axioms, theorems, lemmas) else {
+                        return Err(CompileError::ShouldNotBeReached);
+                    };
+                    let annotation = compile_annotation(
+                        annotation, names, index, sorts, syntaxes, &mut wffs, &mut objects
+                    )?;
+                    if let Some(variable) = annotation_mismatch(&annotation, &substitution) {
+                        return Err(CompileError::SubstitutionMismatch(variable, index));
+                    };
+                    if substitution_captures(relation, &substitution, syntaxes) {
+                        return Err(CompileError::VariableCapture(index));
+                    };
+                    let Ok(requirements) = distinct_variables_requirements(&substitution, rel_distinct) else {
+                        return Err(CompileError::DistinctVariablesViolation(index));
+                    };
+                    propagate_requirements(
+                        requirements, &mut distinct_variables, statement_wff_count, statement_object_count
+                    );
+                    rel_ref
                 }
-            },
-            _ => return Err(CompileError::WeirdReference)
+            };
+            compiled_proof.push(LogicStep {
+                used_hypotheses: vec![line - 1],
+                theorem_ref,
+                kind: StepKind::Rewrite,
+                resulting_formula
+            });
+            continue;
+        };
+        // Definition/Axiom/Theorem usage
+        let theo_ref = statement_reference(&theorem_name, index, references, lemma_references)?;
+        let Some(statement) = cited_statement(&theo_ref, definitions, axioms, theorems, lemmas) else {
+            return Err(CompileError::UnknownTheorem(theorem_name, index));
+        };
+        let (theo_hypotheses, theo_assertion, _, wff_count, object_count) = statement;
+        let resulting_formula = match formula {
+            Some(formula) => Some(compile_formula(formula, sorts, syntaxes, &mut wffs, &mut objects)?),
+            None => None
+        };
+        // Without used lines, the accessible preceding lines are searched for the hypotheses, the earliest first,
+        //  so that a line repeating an earlier formula is never preferred to it
+        let used_hypots = match (used_hypots.is_empty() && !theo_hypotheses.is_empty(), &resulting_formula) {
+            (false, _) => used_hypots,
+            (true, None) => return Err(CompileError::UninferableFormula(index)),
+            (true, Some(resulting_formula)) => {
+                let accessible_lines = (1..index)
+                    .filter(|line| !assumption_blocks.iter()
+                        .any(|(assume, discharge)| assume < line && line <= discharge))
+                    .map(|line| (line, compiled_proof[line - 1].resulting_formula.clone()))
+                    .collect();
+                let Some(found_lines) = fill_used_lines(
+                    (theo_hypotheses, theo_assertion, wff_count, object_count),
+                    resulting_formula, &accessible_lines, &unifier, syntaxes
+                ) else {
+                    return Err(CompileError::UnfillableHypotheses(index));
+                };
+                written_used_lines[i] = found_lines.clone();
+                found_lines
+            }
         };
         if used_hypots.len() != theo_hypotheses.len() {
             return Err(CompileError::IncorrectNumberOfHypothesis(used_hypots.len(), theo_hypotheses.len(), index));
@@ -245,18 +898,112 @@ pub fn compile_theorem(
         let used_hypotheses = used_hypots.iter()
             .map(|idx| compiled_proof.get(*idx)
                 .ok_or(CompileError::InaccessibleHypothesis(idx+1, index))
-                .map(|step| step.resulting_formula.clone())
+                .map(|step| apply_unifier_wff(&step.resulting_formula, &unifier))
             ).collect::<Result<Vec<_>, _>>()?;
-        let resulting_formula = compile_formula(formula, syntaxes, &mut wffs, &mut objects)?;
-        if !formula_is_substitution(&resulting_formula, &used_hypotheses, &theo_hypotheses, &theo_assertion, wff_count, object_count) {
-            return Err(CompileError::IncorrectResultingFormula(index));
+        let Some(names) = cited_variable_names(&theo_ref, definitions, axioms, theorems, lemmas) else {
+            return Err(CompileError::ShouldNotBeReached);
+        };
+        let annotation = compile_annotation(annotation, names, index, sorts, syntaxes, &mut wffs, &mut objects)?;
+        let annotated = annotation.wffs.len() + annotation.objects.len() != 0;
+        let resulting_formula = match (resulting_formula, used_hypotheses.iter().any(has_metavariables) || annotated) {
+            (Some(resulting_formula), false) => {
+                check_assertion_step(
+                    &resulting_formula, &used_hypotheses, statement, index, syntaxes,
+                    &mut distinct_variables, (statement_wff_count, statement_object_count)
+                )?;
+                resulting_formula
+            },
+            // The variables of the cited statement become metavariables, which the used lines and the formula
+            //  (or the annotation, or later lines) determine, the step is checked once the whole proof is unified
+            (resulting_formula, _) => {
+                let offsets = new_metavariables(&mut unifier, wff_count, object_count);
+                for (theo_hyp, used_hyp) in theo_hypotheses.iter().zip(&used_hypotheses) {
+                    if unify_wffs(&rename_wff(theo_hyp, offsets), used_hyp, &mut unifier, syntaxes).is_err() {
+                        return Err(CompileError::IncorrectResultingFormula(index));
+                    };
+                };
+                let assertion = rename_wff(theo_assertion, offsets);
+                if let Some(resulting_formula) = &resulting_formula {
+                    if unify_wffs(&assertion, resulting_formula, &mut unifier, syntaxes).is_err() {
+                        return Err(CompileError::IncorrectResultingFormula(index));
+                    };
+                };
+                // The annotation comes last, so that a disagreement is reported on the variable
+                for (variable, id, value) in &annotation.wffs {
+                    let metavariable = WellFormedFormula::Atomic(id + offsets.0);
+                    if unify_wffs(&metavariable, value, &mut unifier, syntaxes).is_err() {
+                        return Err(CompileError::SubstitutionMismatch(*variable, index));
+                    };
+                };
+                for (variable, id, value) in &annotation.objects {
+                    let metavariable = Object::Atomic(id + offsets.1);
+                    if unify_objects(&metavariable, value, &mut unifier, syntaxes).is_err() {
+                        return Err(CompileError::SubstitutionMismatch(*variable, index));
+                    };
+                };
+                unified_steps.push(compiled_proof.len());
+                resulting_formula.unwrap_or(assertion)
+            }
         };
         compiled_proof.push(LogicStep {
             used_hypotheses: used_hypots,
             theorem_ref: theo_ref,
+            kind: StepKind::Assertion,
             resulting_formula
         });
     };
+    if let Some(assumption) = open_assumptions.pop() {
+        return Err(CompileError::UndischargedAssumption(assumption + 1));
+    };
+    // Every metavariable must have been determined
+    for (i, step) in compiled_proof.iter_mut().enumerate() {
+        step.resulting_formula = apply_unifier_wff(&step.resulting_formula, &unifier);
+        if has_metavariables(&step.resulting_formula) {
+            return Err(CompileError::UninferableFormula(i+1));
+        };
+    };
+    for step_id in unified_steps {
+        let step = &compiled_proof[step_id];
+        let used_hypotheses = step.used_hypotheses.iter()
+            .map(|idx| compiled_proof[*idx].resulting_formula.clone())
+            .collect();
+        let Some(statement) = cited_statement(&step.theorem_ref, definitions, axioms, theorems, lemmas) else {
+            return Err(CompileError::ShouldNotBeReached);
+        };
+        check_assertion_step(
+            &step.resulting_formula, &used_hypotheses, statement, step_id + 1, syntaxes,
+            &mut distinct_variables, (statement_wff_count, statement_object_count)
+        )?;
+    };
+    warnings.append(&mut lint_proof(&name, &hypot_name_list, &compiled_proof, &assertions, &assumption_blocks));
+    // A proof expanded from tactics isn't written in the file
+    let written_lines = match expanded {
+        true => Vec::new(),
+        false => written_used_lines.into_iter()
+            .zip(&compiled_proof)
+            .map(|(used_lines, step)| (used_lines, step.resulting_formula.clone()))
+            .collect()
+    };
+    // The expanded proof only uses the statements of the deduction recipe besides the original steps,
+    //  it is checked again as a whole
+    if let (Some(recipe), false) = (deduction, assumption_blocks.is_empty()) {
+        compiled_proof = expand_assumptions(compiled_proof, assumption_blocks, recipe)?;
+        for (step_id, step) in compiled_proof.iter().enumerate() {
+            if step.kind != StepKind::Assertion {
+                continue;
+            };
+            let Some(statement) = cited_statement(&step.theorem_ref, definitions, axioms, theorems, lemmas) else {
+                continue;
+            };
+            let used_hypotheses = step.used_hypotheses.iter()
+                .map(|idx| compiled_proof[*idx].resulting_formula.clone())
+                .collect();
+            check_assertion_step(
+                &step.resulting_formula, &used_hypotheses, statement, step_id + 1, syntaxes,
+                &mut distinct_variables, (statement_wff_count, statement_object_count)
+            )?;
+        };
+    };
     // Verify that assertions have been proven
     let steps = compiled_proof.iter()
         .map(|l| &l.resulting_formula)
@@ -267,12 +1014,15 @@ pub fn compile_theorem(
         };
     };
 
-    Ok(Theorem {
+    let theorem = Theorem {
         name,
         hypotheses: hypot_list,
         assertions,
+        distinct_variables,
         proof: compiled_proof,
         distinct_wff_count: wffs.len(),
-        distinct_object_count: objects.len()
-    })
+        distinct_object_count: objects.len(),
+        variable_names: variable_names(&wffs, &objects)
+    };
+    Ok((theorem, written_lines))
 }
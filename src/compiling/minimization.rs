@@ -0,0 +1,104 @@
+use super::{
+    Syntax, Theorem, WellFormedFormula,
+    check_assertion_step
+};
+
+// A line of a minimized proof
+pub struct MinimizedLine {
+    pub line: usize,  // Index of the line in the original proof
+    pub used_lines: Vec<usize>,  // Indices of its used lines in the minimized proof
+    pub citation: Option<(String, String)>  // (theorem now proving the line on its own, formula of the line)
+}
+
+// The first theorem without hypotheses whose assertion proves the formula in the statement,
+//  without adding conditions on its distinct variables.
+// Variables of the proof are taken as variables of the statement, so they can't get conditions either
+pub fn find_citation(
+    formula: &WellFormedFormula, theorems: &[Theorem], syntaxes: &Vec<Syntax>, statement: &Theorem
+) -> Option<String> {
+    let counts = (statement.distinct_wff_count, statement.distinct_object_count);
+    for theo in theorems.iter().filter(|theo| theo.hypotheses.is_empty()) {
+        for (assert_id, assertion) in theo.assertions.iter().enumerate() {
+            let cited = (&theo.hypotheses, assertion, &theo.distinct_variables, theo.distinct_wff_count, theo.distinct_object_count);
+            let mut distinct_variables = statement.distinct_variables.clone();
+            let checked = check_assertion_step(formula, &Vec::new(), cited, 0, syntaxes, &mut distinct_variables, counts);
+            if checked.is_ok() && distinct_variables.len() == statement.distinct_variables.len() {
+                return Some(match assert_id {
+                    0 => theo.name.clone(),
+                    _ => format!("{}.{assert_id}", theo.name)
+                });
+            };
+        };
+    };
+    None
+}
+
+// Replaces each line by the first preceding line it can use proving the same formula, then keeps the lines
+//  the assertions rely on. A line with a citation no longer relies on its subproof
+pub fn minimize_proof(
+    lines: &[(Vec<usize>, String)], formulas: &[WellFormedFormula], assertions: &[WellFormedFormula],
+    citations: &[Option<(String, String)>]
+) -> Vec<MinimizedLine> {
+    let mut open_assumptions = Vec::new();
+    let mut blocks = Vec::new();
+    let mut assumptions = vec![None; lines.len()];
+    for (line, (_, theo_ref)) in lines.iter().enumerate() {
+        match theo_ref.as_str() {
+            "assume" => open_assumptions.push(line),
+            "discharge" => if let Some(assumption) = open_assumptions.pop() {
+                blocks.push((assumption, line));
+                assumptions[line] = Some(assumption);
+            },
+            _ => ()
+        };
+    };
+    let accessible = |previous: usize, line: usize| !blocks.iter()
+        .any(|(assume, discharge)| *assume <= previous && previous < *discharge && *discharge <= line);
+    let mut representatives = (0..lines.len()).collect::<Vec<_>>();
+    for line in 0..lines.len() {
+        // Assuming a formula again opens a new block
+        if lines[line].1 == "assume" {
+            continue;
+        };
+        let previous = (0..line).find(|previous| {
+            representatives[*previous] == *previous && accessible(*previous, line) && formulas[*previous] == formulas[line]
+        });
+        if let Some(previous) = previous {
+            representatives[line] = previous;
+        };
+    };
+    let used_lines = |line: usize| match citations[line] {
+        Some(_) => Vec::new(),
+        None => lines[line].0.iter().map(|used| representatives[*used]).collect::<Vec<_>>()
+    };
+    // The assertions are preferably proven outside of the blocks
+    let in_block = |line: usize| blocks.iter().any(|(assume, discharge)| *assume <= line && line < *discharge);
+    let mut to_visit = assertions.iter()
+        .filter_map(|assertion| {
+            let proves = |line: &usize| representatives[*line] == *line && formulas[*line] == *assertion;
+            (0..lines.len()).find(|line| proves(line) && !in_block(*line))
+                .or_else(|| (0..lines.len()).find(proves))
+        })
+        .collect::<Vec<_>>();
+    let mut needed = vec![false; lines.len()];
+    while let Some(line) = to_visit.pop() {
+        if needed[line] {
+            continue;
+        };
+        needed[line] = true;
+        to_visit.extend(used_lines(line));
+        if let (Some(assumption), None) = (assumptions[line], &citations[line]) {
+            to_visit.push(assumption);
+        };
+    };
+    let kept_lines = (0..lines.len()).filter(|line| needed[*line]).collect::<Vec<_>>();
+    kept_lines.iter()
+        .map(|line| MinimizedLine {
+            line: *line,
+            used_lines: used_lines(*line).iter()
+                .filter_map(|used| kept_lines.iter().position(|kept| kept == used))
+                .collect(),
+            citation: citations[*line].clone()
+        })
+        .collect()
+}
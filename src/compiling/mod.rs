@@ -1,38 +1,124 @@
 mod compiler;
 pub use compiler::{
+    add_sort_to_lib,
     add_syndef_to_lib,
     add_axiom_to_lib,
     add_theo_to_lib,
     verify_theo,
+    infer_theo,
+    fixup_theo,
+    minimize_theo,
+    prove_theo,
+    saturate_theo,
+    decide_tautology,
+    suggest_statements,
     compile_directory
 };
 
 mod types;
 pub use types::{
+    Sort,
     Syntax, SyntaxType, Placeholder,
-    WellFormedFormula, Object,
+    WellFormedFormula, Object, Variable, VariableNames,
     Definition, Axiom, Theorem,
-    LogicStep, Reference,
+    LogicStep, StepKind, Reference, DeductionRecipe,
     PartiallyCompiled,
     Library
 };
+pub use crate::parsing::{Associativity, SyntaxRole};
 
 mod math_file;
 use math_file::{
+    compile_sort,
     compile_syntax,
     compile_definition,
     compile_axiom,
-    compile_theorem
+    compile_theorem,
+    ProofContext,
+    WrittenLine,
+    statement_reference,
+    CitedStatement,
+    cited_statement,
+    check_assertion_step
 };
 
 mod formula;
-use formula::compile_formula;
+use formula::{compile_formula, compile_object, object_sort, variable_sort, has_several_parses, respects_precedence};
+pub use formula::repetition_group;
 
 mod verification;
 use verification::{
-    formula_is_contained,
-    formula_is_substitution
+    Substitution,
+    find_substitution,
+    find_wff_substitutions,
+    find_object_substitutions,
+    distinct_variables_requirements,
+    collect_wff_variables,
+    collect_object_variables,
+    same_arity
 };
 
+mod printing;
+use printing::{variable_names, wff_to_string};
+
+mod unification;
+use unification::{
+    Unifier, is_metavariable, new_metavariables, rename_wff, apply_unifier_wff, has_metavariables, unify_wffs, unify_objects
+};
+
+mod goals;
+pub use goals::{
+    GoalTree,
+    new_goal_tree, open_goals, goal_formula, apply_to_goal, export_proof, append_proof
+};
+use goals::{apply_hypothesis_to_goal, apply_statement_to_goal, intro_goal, assume_in_goal};
+
+mod tactics;
+use tactics::expand_tactics;
+
+mod search;
+pub use search::SearchBudget;
+use search::search_proof;
+
+mod saturation;
+use saturation::saturate;
+
+mod deduction;
+use deduction::{implication, is_renaming, imp_consequent, compile_deduction_recipe, expand_assumptions};
+
+mod tautology;
+pub use tautology::Decision;
+use tautology::decide_formula;
+
+mod suggestion;
+pub use suggestion::Suggestion;
+use suggestion::matching_statements;
+
+mod filling;
+use filling::fill_used_lines;
+
+mod rewriting;
+use rewriting::{unfolds_definition, relation_sides, rewrites_with_line, find_rewrite_substitution};
+
+mod definition;
+use definition::check_definition;
+
+mod ambiguity;
+use ambiguity::find_ambiguous_example;
+
+mod binding;
+pub use binding::{free_variables, object_free_variables};
+use binding::substitution_captures;
+
+mod minimization;
+pub use minimization::MinimizedLine;
+use minimization::{find_citation, minimize_proof};
+
+mod lints;
+use lints::lint_proof;
+
 mod error;
-pub use error::CompileError;
+pub use error::{CompileError, CompileWarning};
+
+#[cfg(test)]
+mod tests;
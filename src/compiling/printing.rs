@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use super::{
+    Syntax, Placeholder,
+    WellFormedFormula, Object, VariableNames,
+    repetition_group, respects_precedence
+};
+
+// Inverts the variable maps filled by 'compile_formula'
+pub fn variable_names(
+    wffs: &HashMap<usize, WellFormedFormula>, objects: &HashMap<usize, Object>
+) -> VariableNames {
+    let mut names = VariableNames { wffs: vec!['?'; wffs.len()], objects: vec!['?'; objects.len()] };
+    for (key, wff) in wffs {
+        if let (WellFormedFormula::Atomic(id), Some(c)) = (wff, char::from_u32('𝛼' as u32 + *key as u32)) {
+            names.wffs[*id] = c;
+        };
+    };
+    for (key, obj) in objects {
+        // Keys are either object variable ids or the characters of sorted variables
+        let c = match *key < 26 {
+            true => char::from_u32('𝑎' as u32 + *key as u32),
+            false => char::from_u32(*key as u32)
+        };
+        if let (Object::Atomic(id) | Object::SortedAtomic(_, id), Some(c)) = (obj, c) {
+            names.objects[*id] = c;
+        };
+    };
+    names
+}
+
+enum Parameter<'a> {
+    Wff(&'a WellFormedFormula),
+    Object(&'a Object)
+}
+
+fn top_syntax(param: &Parameter) -> Option<usize> {
+    match param {
+        Parameter::Wff(WellFormedFormula::SyntaxComposite { syntax_ref, .. })
+        | Parameter::Object(Object::SyntaxComposite { syntax_ref, .. }) => Some(*syntax_ref),
+        _ => None
+    }
+}
+
+fn is_open(placeholder: Option<&Placeholder>) -> bool {
+    !matches!(placeholder, Some(Placeholder::LiteralChar(_)) | None)
+}
+
+// Parameters that could be parsed as part of the parent syntax are parenthesised
+fn write_parameter(
+    param: Parameter, parent_id: usize, edges: (bool, bool),
+    syntaxes: &Vec<Syntax>, names: &VariableNames, res: &mut String
+) {
+    let needs_parentheses = match top_syntax(&param) {
+        None => false,
+        Some(child_id) => {
+            let child = &syntaxes[child_id].formula;
+            let facing_open = (edges.0 && is_open(child.last())) || (edges.1 && is_open(child.first()));
+            let facing_repetition = (edges.0 && matches!(child.last(), Some(Placeholder::Repetition)))
+                || (edges.1 && matches!(child.first(), Some(Placeholder::Repetition)));
+            facing_open && (facing_repetition || !respects_precedence(Some(child_id), parent_id, edges, syntaxes, &mut false))
+        }
+    };
+    if needs_parentheses { res.push('('); };
+    match param {
+        Parameter::Wff(wff) => write_wff(wff, syntaxes, names, res),
+        Parameter::Object(obj) => write_object(obj, syntaxes, names, res)
+    };
+    if needs_parentheses { res.push(')'); };
+}
+
+fn write_composite(
+    syntax_ref: usize, wff_parameters: &[WellFormedFormula], object_parameters: &[Object],
+    syntaxes: &Vec<Syntax>, names: &VariableNames, res: &mut String
+) {
+    let formula = &syntaxes[syntax_ref].formula;
+    let repetition = repetition_group(formula);
+    let last = formula.len().saturating_sub(1);
+    let parameters = |placeholder: &Placeholder, repeated: bool| -> Vec<Parameter> {
+        let (id, is_wff) = match placeholder {
+            Placeholder::WellFormedFormula(id) => (*id, true),
+            Placeholder::Object(id) | Placeholder::Sorted(_, id) => (*id, false),
+            Placeholder::LiteralChar(_) | Placeholder::Repetition => return vec![]
+        };
+        // The elements of a repetition are the last parameters
+        let end = match repeated {
+            true => usize::MAX,
+            false => id + 1
+        };
+        match is_wff {
+            true => wff_parameters.iter().skip(id).take(end - id).map(Parameter::Wff).collect(),
+            false => object_parameters.iter().skip(id).take(end - id).map(Parameter::Object).collect()
+        }
+    };
+    // Spaces are written where the syntax definition has them
+    let spaced_after = &syntaxes[syntax_ref].spaced_after;
+    let mut k = 0;
+    while k < formula.len() {
+        let (repeated, end) = match repetition {
+            Some((repeated_idx, repetition_idx)) if repeated_idx == k => (true, repetition_idx),
+            _ => (false, k)
+        };
+        let elements = match formula[k] {
+            Placeholder::LiteralChar(c) => {
+                res.push(c);
+                vec![]
+            },
+            _ => parameters(&formula[k], repeated)
+        };
+        let count = elements.len();
+        for (i, element) in elements.into_iter().enumerate() {
+            if i > 0 {
+                // The elements are separated by the literals between the repeated placeholder and the '…'
+                for (j, placeholder) in formula.iter().enumerate().take(end).skip(k) {
+                    if let Placeholder::LiteralChar(c) = placeholder {
+                        res.push(*c);
+                    };
+                    if spaced_after.contains(&j) {
+                        res.push(' ');
+                    };
+                };
+            };
+            let edges = (k == 0 && i == 0, end == last && i + 1 == count);
+            write_parameter(element, syntax_ref, edges, syntaxes, names, res);
+        };
+        if end < last && spaced_after.contains(&end) {
+            res.push(' ');
+        };
+        k = end + 1;
+    };
+}
+
+pub fn write_wff(wff: &WellFormedFormula, syntaxes: &Vec<Syntax>, names: &VariableNames, res: &mut String) {
+    match wff {
+        WellFormedFormula::Atomic(id) => res.push(names.wffs.get(*id).copied().unwrap_or('?')),
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => write_composite(*syntax_ref, wff_parameters, object_parameters, syntaxes, names, res)
+    }
+}
+
+pub fn write_object(obj: &Object, syntaxes: &Vec<Syntax>, names: &VariableNames, res: &mut String) {
+    match obj {
+        Object::Atomic(id) | Object::SortedAtomic(_, id) => res.push(names.objects.get(*id).copied().unwrap_or('?')),
+        Object::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => write_composite(*syntax_ref, wff_parameters, object_parameters, syntaxes, names, res)
+    }
+}
+
+pub fn wff_to_string(wff: &WellFormedFormula, syntaxes: &Vec<Syntax>, names: &VariableNames) -> String {
+    let mut res = String::new();
+    write_wff(wff, syntaxes, names, &mut res);
+    res
+}
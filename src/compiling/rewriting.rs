@@ -0,0 +1,188 @@
+use super::{
+    Syntax, Definition,
+    WellFormedFormula, Object,
+    Substitution,
+    find_wff_substitutions, find_object_substitutions,
+    free_variables, object_free_variables, substitution_captures
+};
+
+// A sub-formula or a sub-object, so that both can be walked together
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Term<'a> {
+    Wff(&'a WellFormedFormula),
+    Object(&'a Object)
+}
+
+fn composite_parts<'a>(term: Term<'a>) -> Option<(usize, &'a Vec<WellFormedFormula>, &'a Vec<Object>)> {
+    match term {
+        Term::Wff(WellFormedFormula::SyntaxComposite { syntax_ref, wff_parameters, object_parameters })
+        | Term::Object(Object::SyntaxComposite { syntax_ref, wff_parameters, object_parameters }) => {
+            Some((*syntax_ref, wff_parameters, object_parameters))
+        },
+        _ => None
+    }
+}
+
+// Accepts a (source, target) pair of terms, given the object variables bound around them
+type Replacement<'a, 'f, T> = dyn Fn(Term<'a>, Term<'a>, &[usize]) -> Option<T> + 'f;
+
+// Whether 'source' and 'target' only differ at one position, where 'replaces' accepts the two terms given
+//  the object variables bound around that position. A bound position itself can't differ
+pub fn differ_by_one_replacement<'a, T>(
+    source: Term<'a>, target: Term<'a>, syntaxes: &[Syntax], replaces: &Replacement<'a, '_, T>
+) -> Option<T> {
+    replacement_in_scope(source, target, syntaxes, replaces, &mut Vec::new())
+}
+
+fn replacement_in_scope<'a, T>(
+    source: Term<'a>, target: Term<'a>, syntaxes: &[Syntax], replaces: &Replacement<'a, '_, T>,
+    bound_variables: &mut Vec<usize>
+) -> Option<T> {
+    if let Some(res) = replaces(source, target, bound_variables) {
+        return Some(res);
+    };
+    let (
+        Some((syntax1, wffs1, objects1)),
+        Some((syntax2, wffs2, objects2))
+    ) = (composite_parts(source), composite_parts(target)) else {
+        return None;
+    };
+    if syntax1 != syntax2 || wffs1.len() != wffs2.len() || objects1.len() != objects2.len() {
+        return None;
+    };
+    let syntax = &syntaxes[syntax1];
+    let differing_object = objects1.iter().zip(objects2).position(|(obj1, obj2)| obj1 != obj2);
+    if differing_object.is_some_and(|id| syntax.bound_objects.contains(&id)) {
+        return None;
+    };
+    let differing = wffs1.iter().zip(wffs2)
+        .map(|(wff1, wff2)| (Term::Wff(wff1), Term::Wff(wff2)))
+        .chain(objects1.iter().zip(objects2).map(|(obj1, obj2)| (Term::Object(obj1), Term::Object(obj2))))
+        .filter(|(term1, term2)| term1 != term2)
+        .collect::<Vec<_>>();
+    let [(term1, term2)] = differing[..] else {
+        return None;
+    };
+    // The binders of the syntax scope over all of its other parameters
+    let scope_start = bound_variables.len();
+    bound_variables.extend(syntax.bound_objects.iter().filter_map(|id| match objects1.get(*id) {
+        Some(Object::Atomic(var) | Object::SortedAtomic(_, var)) => Some(*var),
+        _ => None
+    }));
+    let res = replacement_in_scope(term1, term2, syntaxes, replaces, bound_variables);
+    bound_variables.truncate(scope_start);
+    res
+}
+
+// Whether a binder around the replaced position binds a free variable of one of the two terms
+fn captures_replaced_terms(source: Term, target: Term, bound_variables: &[usize], syntaxes: &Vec<Syntax>) -> bool {
+    let free = |term: Term| match term {
+        Term::Wff(wff) => free_variables(wff, syntaxes),
+        Term::Object(obj) => object_free_variables(obj, syntaxes)
+    };
+    free(source).into_iter()
+        .chain(free(target))
+        .any(|var| bound_variables.contains(&var))
+}
+
+// Matches the pattern against the term, extending the substitution
+pub fn match_term<'a>(
+    pattern: Term, term: Term<'a>,
+    wff_mapping: &mut Vec<Option<&'a WellFormedFormula>>, object_mapping: &mut Vec<Option<&'a Object>>
+) -> bool {
+    match (pattern, term) {
+        (Term::Wff(pattern), Term::Wff(wff)) => find_wff_substitutions(pattern, wff, wff_mapping, object_mapping).is_ok(),
+        (Term::Object(pattern), Term::Object(obj)) => find_object_substitutions(pattern, obj, wff_mapping, object_mapping).is_ok(),
+        _ => false
+    }
+}
+
+// Sides of a formula built with an equivalence or an equality syntax
+pub fn relation_sides<'a>(wff: &'a WellFormedFormula, syntaxes: &[Syntax]) -> Option<(Term<'a>, Term<'a>)> {
+    match wff {
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => {
+            syntaxes[*syntax_ref].role?;
+            match (&wff_parameters[..], &object_parameters[..]) {
+                ([left, right], []) => Some((Term::Wff(left), Term::Wff(right))),
+                ([], [left, right]) => Some((Term::Object(left), Term::Object(right))),
+                _ => None
+            }
+        },
+        WellFormedFormula::Atomic(_) => None
+    }
+}
+
+// Whether 'unfolded' is 'folded' with one instance of the definiendum replaced by the same instance of the definiens
+pub fn unfolds_definition(
+    definition: &Definition, folded: &WellFormedFormula, unfolded: &WellFormedFormula, syntaxes: &Vec<Syntax>
+) -> bool {
+    let Some((definiendum, definiens)) = relation_sides(&definition.definition, syntaxes) else {
+        return false;
+    };
+    // A definition holds for any value of its variables, it can be unfolded under a binder
+    let replaces = |folded_term: Term, unfolded_term: Term, _: &[usize]| {
+        let mut wff_mapping = vec![None; definition.distinct_wff_count];
+        let mut object_mapping = vec![None; definition.distinct_object_count];
+        if !match_term(definiendum, folded_term, &mut wff_mapping, &mut object_mapping) {
+            return None;
+        };
+        // The variables of the definiens all appear in the definiendum, so they are already mapped
+        if !match_term(definiens, unfolded_term, &mut wff_mapping, &mut object_mapping) {
+            return None;
+        };
+        let substitution = Substitution { wffs: wff_mapping, objects: object_mapping };
+        match substitution_captures(&definition.definition, &substitution, syntaxes) {
+            true => None,
+            false => Some(())
+        }
+    };
+    differ_by_one_replacement(Term::Wff(folded), Term::Wff(unfolded), syntaxes, &replaces).is_some()
+}
+
+// Whether 'target' is 'source' with one side of the proven relation replaced by the other side,
+//  outside of the binders of the variables the relation is about
+pub fn rewrites_with_line(
+    relation: &WellFormedFormula, source: &WellFormedFormula, target: &WellFormedFormula, syntaxes: &Vec<Syntax>
+) -> bool {
+    let Some((left, right)) = relation_sides(relation, syntaxes) else {
+        return false;
+    };
+    let replaces = |source_term: Term, target_term: Term, bound_variables: &[usize]| {
+        let sides_match = (source_term == left && target_term == right) || (source_term == right && target_term == left);
+        match sides_match && !captures_replaced_terms(source_term, target_term, bound_variables, syntaxes) {
+            true => Some(()),
+            false => None
+        }
+    };
+    differ_by_one_replacement(Term::Wff(source), Term::Wff(target), syntaxes, &replaces).is_some()
+}
+
+// Looks for an instance of the relation (an assertion without hypotheses) which turns 'source'
+//  into 'target' by replacing one of its sides by the other, outside of the binders of the variables of
+//  the instance, and returns its substitution
+pub fn find_rewrite_substitution<'a>(
+    relation: &WellFormedFormula, wff_count: usize, object_count: usize,
+    source: &'a WellFormedFormula, target: &'a WellFormedFormula, syntaxes: &Vec<Syntax>
+) -> Option<Substitution<'a>> {
+    let (left, right) = relation_sides(relation, syntaxes)?;
+    let replaces = |source_term: Term<'a>, target_term: Term<'a>, bound_variables: &[usize]| {
+        if captures_replaced_terms(source_term, target_term, bound_variables, syntaxes) {
+            return None;
+        };
+        [(left, right), (right, left)].into_iter().find_map(|(from, to)| {
+            let mut wff_mapping = vec![None; wff_count];
+            let mut object_mapping = vec![None; object_count];
+            let matches = match_term(from, source_term, &mut wff_mapping, &mut object_mapping)
+                && match_term(to, target_term, &mut wff_mapping, &mut object_mapping);
+            match matches {
+                true => Some(Substitution { wffs: wff_mapping, objects: object_mapping }),
+                false => None
+            }
+        })
+    };
+    differ_by_one_replacement(Term::Wff(source), Term::Wff(target), syntaxes, &replaces)
+}
@@ -0,0 +1,105 @@
+use super::{
+    Syntax, WellFormedFormula, Variable, CitedStatement,
+    Unifier, new_metavariables, rename_wff, apply_unifier_wff, has_metavariables, unify_wffs,
+    check_assertion_step
+};
+
+// A formula known from the hypotheses, with the way it was derived
+pub struct DerivedFormula {
+    pub formula: WellFormedFormula,
+    pub depth: usize,  // 0 for the hypotheses
+    pub used_formulas: Vec<usize>,  // Indices of the derived formulas matched with the hypotheses of the reference
+    pub reference: String  // The hypothesis, or the axiom or theorem applied
+}
+
+// Every way to match the remaining hypotheses with known formulas, at least one of the matched formulas
+//  being at least as recent as 'first_new' so that each derivation is only found once
+fn match_hypotheses(
+    hypotheses: &[WellFormedFormula], known: &[DerivedFormula], first_new: usize, unifier: &Unifier,
+    syntaxes: &Vec<Syntax>, assigned: &mut Vec<usize>, matches: &mut Vec<(Vec<usize>, Unifier)>
+) {
+    let Some((hypothesis, remaining)) = hypotheses.split_first() else {
+        if assigned.iter().any(|used| *used >= first_new) {
+            matches.push((assigned.clone(), unifier.clone()));
+        };
+        return;
+    };
+    for (used, derived) in known.iter().enumerate() {
+        let mut unifier = unifier.clone();
+        if unify_wffs(hypothesis, &derived.formula, &mut unifier, syntaxes).is_err() {
+            continue;
+        };
+        assigned.push(used);
+        match_hypotheses(remaining, known, first_new, &unifier, syntaxes, assigned, matches);
+        assigned.pop();
+    };
+}
+
+// Applies the statements to the known formulas round after round, each round using at least one formula of the
+//  previous one, until no formula is new, 'max_depth' rounds have been made or 'max_formulas' formulas are known.
+// Statements are applied as a whole, their assertion having to be fixed by their hypotheses, and without adding
+//  distinct variables conditions. Also tells whether the saturation stopped because of 'max_formulas'
+pub fn saturate(
+    hypotheses: Vec<(String, WellFormedFormula)>, statements: &Vec<(String, CitedStatement)>,
+    distinct_variables: &[(Variable, Variable)], counts: (usize, usize), syntaxes: &Vec<Syntax>,
+    max_depth: usize, max_formulas: usize
+) -> (Vec<DerivedFormula>, bool) {
+    let mut known = Vec::new();
+    for (name, hypot) in hypotheses {
+        if known.iter().any(|derived: &DerivedFormula| derived.formula == hypot) {
+            continue;
+        };
+        known.push(DerivedFormula { formula: hypot, depth: 0, used_formulas: Vec::new(), reference: name });
+    };
+    let mut first_new = 0;
+    for depth in 1..=max_depth {
+        let round_start = known.len();
+        for (name, statement) in statements {
+            let (theo_hypotheses, theo_assertion, _, wff_count, object_count) = *statement;
+            if theo_hypotheses.is_empty() {
+                continue;
+            };
+            let mut unifier = Unifier { wffs: Vec::new(), objects: Vec::new() };
+            let offsets = new_metavariables(&mut unifier, wff_count, object_count);
+            // The hypotheses which are a single variable match anything, they are matched last
+            let mut order = (0..theo_hypotheses.len()).collect::<Vec<_>>();
+            order.sort_by_key(|hypot_id| matches!(theo_hypotheses[*hypot_id], WellFormedFormula::Atomic(_)));
+            let renamed_hypotheses = order.iter()
+                .map(|hypot_id| rename_wff(&theo_hypotheses[*hypot_id], offsets))
+                .collect::<Vec<_>>();
+            let mut matches = Vec::new();
+            // Formulas derived during this round are left for the next one
+            let previous = &known[..round_start];
+            match_hypotheses(&renamed_hypotheses, previous, first_new, &unifier, syntaxes, &mut Vec::new(), &mut matches);
+            for (matched_formulas, unifier) in matches {
+                let mut used_formulas = vec![0; order.len()];
+                for (hypot_id, used) in order.iter().zip(matched_formulas) {
+                    used_formulas[*hypot_id] = used;
+                };
+                let formula = apply_unifier_wff(&rename_wff(theo_assertion, offsets), &unifier);
+                if has_metavariables(&formula) || known.iter().any(|derived| derived.formula == formula) {
+                    continue;
+                };
+                let used_hypotheses = used_formulas.iter()
+                    .map(|used| known[*used].formula.clone())
+                    .collect();
+                let mut requirements = distinct_variables.to_vec();
+                let checked = check_assertion_step(
+                    &formula, &used_hypotheses, *statement, 0, syntaxes, &mut requirements, counts
+                );
+                if checked.is_err() || requirements.len() != distinct_variables.len() {
+                    continue;
+                };
+                if known.len() >= max_formulas {
+                    return (known, true);
+                };
+                known.push(DerivedFormula { formula, depth, used_formulas, reference: name.clone() });
+            };
+        };
+        if known.len() == round_start {
+            break;
+        };
+        first_new = round_start;
+    };
+    (known, false)
+}
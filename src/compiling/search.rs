@@ -0,0 +1,90 @@
+use std::{collections::HashMap, time::Instant};
+use super::{
+    Reference, WellFormedFormula, Library,
+    GoalTree, open_goals, goal_formula, apply_to_goal,
+    is_metavariable
+};
+
+// Limits of a proof search: the depth of the goals, the number of attempted applications and the time
+pub struct SearchBudget {
+    pub max_depth: usize,
+    pub max_steps: usize,
+    pub deadline: Instant
+}
+
+fn is_undetermined(formula: &Option<WellFormedFormula>) -> bool {
+    matches!(formula, Some(WellFormedFormula::Atomic(id)) if is_metavariable(*id))
+}
+
+fn is_exhausted(budget: &SearchBudget, steps: usize) -> bool {
+    steps >= budget.max_steps || Instant::now() >= budget.deadline
+}
+
+// What a proof search draws on, 'steps' counting the attempted applications
+struct Search<'a, F: FnMut(&GoalTree) -> bool> {
+    candidates: &'a [String],
+    lib: &'a Library,
+    references: &'a HashMap<String, Reference>,
+    budget: &'a SearchBudget,
+    steps: usize,
+    accept: &'a mut F
+}
+
+// Depth-first search of a proof of the open goals, where only the goals above the depth limit can get subgoals.
+// 'depths' gives the depth of each goal of the tree
+fn search_goals<F: FnMut(&GoalTree) -> bool>(
+    tree: &GoalTree, depths: &[usize], depth_limit: usize, search: &mut Search<F>
+) -> Option<GoalTree> {
+    let open = open_goals(tree);
+    let Some(&first_open) = open.first() else {
+        return match (search.accept)(tree) {
+            true => Some(tree.clone()),
+            false => None
+        };
+    };
+    // A goal whose formula is known narrows the search the most, any statement applies to an undetermined one
+    let goal_id = open.iter()
+        .copied()
+        .find(|goal_id| !is_undetermined(&goal_formula(tree, *goal_id)))
+        .unwrap_or(first_open);
+    let undetermined = is_undetermined(&goal_formula(tree, goal_id));
+    for name in search.candidates {
+        if is_exhausted(search.budget, search.steps) {
+            return None;
+        };
+        search.steps += 1;
+        let mut next_tree = tree.clone();
+        let Ok(subgoals) = apply_to_goal(&mut next_tree, goal_id, name, search.lib, search.references) else {
+            continue;
+        };
+        if !subgoals.is_empty() && (undetermined || depths[goal_id] >= depth_limit) {
+            continue;
+        };
+        let mut next_depths = depths.to_vec();
+        next_depths.extend(subgoals.iter().map(|_| depths[goal_id] + 1));
+        let proven = search_goals(&next_tree, &next_depths, depth_limit, search);
+        if proven.is_some() {
+            return proven;
+        };
+    };
+    None
+}
+
+// Backward search of a proof of the root goal with the candidate hypotheses and statements, by iterative
+//  deepening on the depth of the goals. The first complete tree that 'accept' approves is returned
+pub fn search_proof<F: FnMut(&GoalTree) -> bool>(
+    tree: GoalTree, candidates: &[String], lib: &Library, references: &HashMap<String, Reference>,
+    budget: &SearchBudget, accept: &mut F
+) -> Option<GoalTree> {
+    let mut search = Search { candidates, lib, references, budget, steps: 0, accept };
+    for depth_limit in 0..=budget.max_depth {
+        let proven = search_goals(&tree, &[0], depth_limit, &mut search);
+        if proven.is_some() {
+            return proven;
+        };
+        if is_exhausted(budget, search.steps) {
+            break;
+        };
+    };
+    None
+}
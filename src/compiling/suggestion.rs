@@ -0,0 +1,104 @@
+use super::{
+    Syntax, WellFormedFormula, Object, VariableNames,
+    Definition, Axiom, Theorem,
+    find_substitution, check_assertion_step,
+    Unifier, new_metavariables, rename_wff, apply_unifier_wff,
+    wff_to_string
+};
+
+// A statement whose assertion proves the formula, with its hypotheses instantiated
+pub struct Suggestion {
+    pub name: String,  // As cited in a proof, with the id of the assertion if it isn't the first one
+    pub hypotheses: Vec<String>
+}
+
+// A name for a variable that the assertion leaves free: its own name in the statement if the formula doesn't
+//  use it, otherwise the first unused one of the range
+fn fresh_name(own_name: char, used_names: &[char], range: (char, char)) -> char {
+    match used_names.contains(&own_name) {
+        false => own_name,
+        true => (range.0..=range.1)
+            .find(|c| !used_names.contains(c))
+            .unwrap_or('?')
+    }
+}
+
+// The hypotheses instantiated by the substitution matching the assertion with the formula.
+// The variables the assertion doesn't fix become new variables of the formula
+fn instantiate_hypotheses(
+    formula: &WellFormedFormula, (hypotheses, assertion, wff_count, object_count):
+        (&Vec<WellFormedFormula>, &WellFormedFormula, usize, usize),
+    syntaxes: &Vec<Syntax>, names: &VariableNames, statement_names: &VariableNames
+) -> Option<Vec<String>> {
+    let no_hypotheses = Vec::new();
+    let substitution = find_substitution(formula, &no_hypotheses, &no_hypotheses, assertion, wff_count, object_count)?;
+    let mut names = VariableNames { wffs: names.wffs.clone(), objects: names.objects.clone() };
+    // The statement's variables are renamed to metavariables, assigned to their values in the formula
+    let mut unifier = Unifier { wffs: Vec::new(), objects: Vec::new() };
+    let offsets = new_metavariables(&mut unifier, wff_count, object_count);
+    for (id, value) in substitution.wffs.iter().enumerate() {
+        unifier.wffs[id] = Some(match value {
+            Some(wff) => (*wff).clone(),
+            None => {
+                let own_name = statement_names.wffs.get(id).copied().unwrap_or('?');
+                names.wffs.push(fresh_name(own_name, &names.wffs, ('𝛼', '𝜔')));
+                WellFormedFormula::Atomic(names.wffs.len() - 1)
+            }
+        });
+    };
+    for (id, value) in substitution.objects.iter().enumerate() {
+        unifier.objects[id] = Some(match value {
+            Some(obj) => (*obj).clone(),
+            None => {
+                let own_name = statement_names.objects.get(id).copied().unwrap_or('?');
+                names.objects.push(fresh_name(own_name, &names.objects, ('𝑎', '𝑧')));
+                Object::Atomic(names.objects.len() - 1)
+            }
+        });
+    };
+    Some(hypotheses.iter()
+        .map(|hypot| wff_to_string(&apply_unifier_wff(&rename_wff(hypot, offsets), &unifier), syntaxes, &names))
+        .collect())
+}
+
+// Every definition, axiom and theorem whose assertion can be applied to prove the formula,
+//  without capturing variables nor breaking its distinct variables conditions
+pub fn matching_statements(
+    formula: &WellFormedFormula, names: &VariableNames, counts: (usize, usize), syntaxes: &Vec<Syntax>,
+    definitions: &[Definition], axioms: &[Axiom], theorems: &[Theorem]
+) -> Vec<Suggestion> {
+    let no_hypotheses = Vec::new();
+    let no_distinct_variables = Vec::new();
+    let definitions = definitions.iter()
+        .map(|def| (&def.name, &no_hypotheses, vec![&def.definition], &no_distinct_variables, def.distinct_wff_count,
+            def.distinct_object_count, &def.variable_names));
+    let axioms = axioms.iter()
+        .map(|ax| (&ax.name, &ax.hypotheses, ax.assertions.iter().collect(), &ax.distinct_variables,
+            ax.distinct_wff_count, ax.distinct_object_count, &ax.variable_names));
+    let theorems = theorems.iter()
+        .map(|theo| (&theo.name, &theo.hypotheses, theo.assertions.iter().collect(), &theo.distinct_variables,
+            theo.distinct_wff_count, theo.distinct_object_count, &theo.variable_names));
+    let mut suggestions = Vec::new();
+    let statements = definitions.chain(axioms).chain(theorems);
+    for (name, hypotheses, assertions, distinct, wff_count, object_count, statement_names) in statements {
+        for (assert_id, assertion) in assertions.into_iter().enumerate() {
+            // The hypotheses are left out, they are what the suggestion gives
+            let cited = (&no_hypotheses, assertion, distinct, wff_count, object_count);
+            if check_assertion_step(formula, &Vec::new(), cited, 0, syntaxes, &mut Vec::new(), counts).is_err() {
+                continue;
+            };
+            let statement = (hypotheses, assertion, wff_count, object_count);
+            let Some(hypotheses) = instantiate_hypotheses(formula, statement, syntaxes, names, statement_names) else {
+                continue;
+            };
+            suggestions.push(Suggestion {
+                name: match assert_id {
+                    0 => name.clone(),
+                    _ => format!("{name}.{assert_id}")
+                },
+                hypotheses
+            });
+        };
+    };
+    suggestions
+}
@@ -0,0 +1,143 @@
+use crate::parsing::Tactic;
+use super::{
+    WellFormedFormula, VariableNames,
+    ProofContext, statement_reference, cited_statement,
+    GoalTree, new_goal_tree, append_proof,
+    apply_hypothesis_to_goal, apply_statement_to_goal, intro_goal, assume_in_goal,
+    CompileError
+};
+
+// A tactic repeated more often than this is stopped, as "repeat apply mp" never fails
+const MAX_REPETITIONS: usize = 100;
+
+// One goal tree per assertion. The open goals are kept in the order the tactics work on them
+#[derive(Clone)]
+struct TacticState {
+    trees: Vec<GoalTree>,
+    open_goals: Vec<(usize, usize)>,  // (tree, goal), the current goal first
+    assumptions: Vec<(Option<String>, usize, usize)>  // (name, tree, goal discharging it), the innermost last
+}
+
+// Proves the goal with a hypothesis of the theorem, or with a statement whose hypotheses become its subgoals
+fn apply_named(
+    tree: &mut GoalTree, goal_id: usize, name: &str, context: &ProofContext
+) -> Result<Vec<usize>, CompileError> {
+    if tree.hypotheses.iter().any(|(hypot_name, _)| hypot_name == name) {
+        return apply_hypothesis_to_goal(tree, goal_id, name, context.syntaxes).map(|()| Vec::new());
+    };
+    let reference = statement_reference(name, goal_id, context.references, context.lemma_references)?;
+    let Some(statement) = cited_statement(
+        &reference, context.definitions, context.axioms, context.theorems, context.lemmas
+    ) else {
+        return Err(CompileError::UnknownTheorem(name.to_owned(), goal_id));
+    };
+    apply_statement_to_goal(tree, goal_id, name, statement, context.syntaxes)
+}
+
+// Proves the goal without new goals: with a named assumption, a hypothesis or a statement, or without a name,
+//  with the first assumption (the innermost first) or hypothesis that fits
+fn exact(
+    state: &mut TacticState, (tree_id, goal_id): (usize, usize), name: &Option<String>, context: &ProofContext
+) -> Result<(), CompileError> {
+    let assumptions = state.assumptions.iter()
+        .rev()
+        .filter(|(assumption_name, assumption_tree, _)| {
+            *assumption_tree == tree_id && (name.is_none() || assumption_name == name)
+        })
+        .map(|(_, _, discharged_goal)| *discharged_goal)
+        .collect::<Vec<_>>();
+    let tree = &mut state.trees[tree_id];
+    for discharged_goal in assumptions {
+        if assume_in_goal(tree, goal_id, discharged_goal, context.syntaxes).is_ok() {
+            return Ok(());
+        };
+    };
+    let names = match name {
+        Some(name) => vec![name.clone()],
+        None => tree.hypotheses.iter().map(|(hypot_name, _)| hypot_name.clone()).collect()
+    };
+    for name in names {
+        let mut next_tree = tree.clone();
+        if let Ok(subgoals) = apply_named(&mut next_tree, goal_id, &name, context) {
+            if subgoals.is_empty() {
+                *tree = next_tree;
+                return Ok(());
+            };
+        };
+    };
+    let name = name.clone().unwrap_or("exact".to_owned());
+    Err(CompileError::InapplicableStatement(name, goal_id))
+}
+
+fn run_tactic(state: &mut TacticState, tactic: &Tactic, context: &ProofContext) -> Result<(), CompileError> {
+    // A failed tactic leaves the state as it was
+    match tactic {
+        Tactic::Repeat(tactic) => {
+            for _ in 0..MAX_REPETITIONS {
+                let previous_state = state.clone();
+                if run_tactic(state, tactic, context).is_err() {
+                    *state = previous_state;
+                    break;
+                };
+            };
+            return Ok(());
+        },
+        Tactic::Try(tactic) => {
+            let previous_state = state.clone();
+            if run_tactic(state, tactic, context).is_err() {
+                *state = previous_state;
+            };
+            return Ok(());
+        },
+        _ => ()
+    };
+    let Some(&(tree_id, goal_id)) = state.open_goals.first() else {
+        return Err(CompileError::NoOpenGoal);
+    };
+    match tactic {
+        Tactic::Apply(name) => {
+            let subgoals = apply_named(&mut state.trees[tree_id], goal_id, name, context)?;
+            state.open_goals.splice(0..1, subgoals.into_iter().map(|subgoal| (tree_id, subgoal)));
+        },
+        Tactic::Exact(name) => {
+            exact(state, (tree_id, goal_id), name, context)?;
+            state.open_goals.remove(0);
+        },
+        Tactic::Intro(name) => {
+            let Some(recipe) = context.deduction else {
+                return Err(CompileError::NoDeductionRecipe(goal_id));
+            };
+            let subgoal = intro_goal(&mut state.trees[tree_id], goal_id, recipe, context.syntaxes)?;
+            state.open_goals[0] = (tree_id, subgoal);
+            state.assumptions.push((name.clone(), tree_id, goal_id));
+        },
+        Tactic::Repeat(_) | Tactic::Try(_) => ()
+    };
+    Ok(())
+}
+
+// Runs the tactics on the assertions, in order, and writes the proof lines they lead to
+pub fn expand_tactics(
+    hypotheses: Vec<(String, WellFormedFormula)>, assertions: &[WellFormedFormula], tactics: &[Tactic],
+    names: &VariableNames, context: &ProofContext
+) -> Result<Vec<String>, CompileError> {
+    let mut state = TacticState {
+        trees: assertions.iter()
+            .map(|assertion| new_goal_tree(hypotheses.clone(), assertion.clone()))
+            .collect(),
+        open_goals: (0..assertions.len()).map(|tree_id| (tree_id, 0)).collect(),
+        assumptions: Vec::new()
+    };
+    for (index, tactic) in tactics.iter().enumerate() {
+        run_tactic(&mut state, tactic, context)
+            .map_err(|e| CompileError::TacticFailed(index + 1, Box::new(e)))?;
+    };
+    if !state.open_goals.is_empty() {
+        return Err(CompileError::UnprovenGoals(state.open_goals.len()));
+    };
+    let mut lines = Vec::new();
+    for tree in &state.trees {
+        append_proof(tree, context.syntaxes, names, &mut lines)?;
+    };
+    Ok(lines)
+}
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use super::{
+    Syntax, Reference, DeductionRecipe, WellFormedFormula, VariableNames,
+    Library,
+    implication, is_renaming, imp_consequent,
+    wff_to_string,
+    CompileError
+};
+
+// The outcome of the truth table of a formula
+pub enum Decision {
+    Tautology(String),  // A theorem file proving the formula, its lemmas included
+    Falsified(Vec<(char, bool)>)  // Values of the variables for which the formula is false
+}
+
+// The statements a propositional proof is made of, by name, along with the syntaxes of the connectives
+struct PropositionalLogic<'a> {
+    recipe: &'a DeductionRecipe,
+    negation: usize,
+    modus_ponens: String,
+    weakening: String,
+    contraposition: String,  // ((¬𝜑 ⇒ ¬𝜓) ⇒ (𝜓 ⇒ 𝜑))
+    not_not_elimination: String,
+    not_not_introduction: String,
+    absurdity: String,
+    false_implication: String,
+    cases: String
+}
+
+// A line of a generated proof: (used lines, counted from 1, reference, formula)
+type WrittenLine = (Vec<usize>, String, WellFormedFormula);
+
+// Preferred names of the lemmas of the generated file, each one only citing the axioms and the preceding lemmas
+const NOT_NOT_ELIMINATION: &str = "notnotr";  // (¬¬𝜑 ⇒ 𝜑)
+const NOT_NOT_INTRODUCTION: &str = "notnot";  // (𝜑 ⇒ ¬¬𝜑)
+const ABSURDITY: &str = "absurd";  // (¬𝜑 ⇒ (𝜑 ⇒ 𝜓))
+const FALSE_IMPLICATION: &str = "negimp";  // (𝜑 ⇒ (¬𝜓 ⇒ ¬(𝜑 ⇒ 𝜓)))
+const CASES: &str = "cases";  // ((𝜑 ⇒ 𝜓) ⇒ ((¬𝜑 ⇒ 𝜓) ⇒ 𝜓))
+
+fn statement_name(reference: &Reference, references: &HashMap<String, Reference>) -> Option<String> {
+    references.iter()
+        .find(|(_, named_reference)| *named_reference == reference)
+        .map(|(name, _)| name.clone())
+}
+
+// The preferred name, numbered if a statement of the library already has it
+fn unused_name(name: &str, references: &HashMap<String, Reference>) -> String {
+    let mut res = name.to_owned();
+    let mut number = 1;
+    while references.contains_key(&res) {
+        res = format!("{name}{number}");
+        number += 1;
+    };
+    res
+}
+
+fn negation(logic: &PropositionalLogic, wff: WellFormedFormula) -> WellFormedFormula {
+    WellFormedFormula::SyntaxComposite {
+        syntax_ref: logic.negation,
+        wff_parameters: vec![wff],
+        object_parameters: Vec::new()
+    }
+}
+
+// The negation is the syntax of the first axiom or theorem of the library stating the contraposition
+fn propositional_logic<'a>(
+    lib: &'a Library, references: &HashMap<String, Reference>
+) -> Result<PropositionalLogic<'a>, CompileError> {
+    let Some(recipe) = &lib.deduction else {
+        return Err(CompileError::NoContraposition);
+    };
+    let statements = lib.axioms.iter()
+        .map(|ax| (&ax.name, &ax.hypotheses, &ax.assertions, &ax.distinct_variables))
+        .chain(lib.theorems.iter().map(|theo| (&theo.name, &theo.hypotheses, &theo.assertions, &theo.distinct_variables)));
+    for (name, hypotheses, assertions, distinct_variables) in statements {
+        let ([assertion], true, true) = (&assertions[..], hypotheses.is_empty(), distinct_variables.is_empty()) else {
+            continue;
+        };
+        let WellFormedFormula::SyntaxComposite { syntax_ref, wff_parameters, .. } = assertion else {
+            continue;
+        };
+        let negation_ref = match (*syntax_ref == recipe.implication, wff_parameters.first()) {
+            (true, Some(WellFormedFormula::SyntaxComposite { wff_parameters, .. })) => match wff_parameters.first() {
+                Some(WellFormedFormula::SyntaxComposite { syntax_ref, wff_parameters, object_parameters })
+                    if wff_parameters.len() == 1 && object_parameters.is_empty() => *syntax_ref,
+                _ => continue
+            },
+            _ => continue
+        };
+        let (Some(modus_ponens), Some(weakening)) = (
+            statement_name(&recipe.modus_ponens, references), statement_name(&recipe.weakening, references)
+        ) else {
+            return Err(CompileError::NoContraposition);
+        };
+        let logic = PropositionalLogic {
+            recipe, negation: negation_ref, modus_ponens, weakening, contraposition: name.clone(),
+            not_not_elimination: unused_name(NOT_NOT_ELIMINATION, references),
+            not_not_introduction: unused_name(NOT_NOT_INTRODUCTION, references),
+            absurdity: unused_name(ABSURDITY, references),
+            false_implication: unused_name(FALSE_IMPLICATION, references),
+            cases: unused_name(CASES, references)
+        };
+        let var = |id: usize| WellFormedFormula::Atomic(id);
+        let pattern = implication(
+            recipe,
+            implication(recipe, negation(&logic, var(0)), negation(&logic, var(1))),
+            implication(recipe, var(1), var(0))
+        );
+        if is_renaming(&pattern, assertion, 2) {
+            return Ok(logic);
+        };
+    };
+    Err(CompileError::NoContraposition)
+}
+
+// The truth value of the formula, its variables taking the given values
+fn evaluate(logic: &PropositionalLogic, wff: &WellFormedFormula, values: &Vec<bool>) -> Result<bool, CompileError> {
+    match wff {
+        WellFormedFormula::Atomic(id) => Ok(values[*id]),
+        WellFormedFormula::SyntaxComposite { syntax_ref, wff_parameters, object_parameters } => {
+            match (&wff_parameters[..], object_parameters.is_empty()) {
+                ([negated], true) if *syntax_ref == logic.negation => Ok(!evaluate(logic, negated, values)?),
+                ([antecedent, consequent], true) if *syntax_ref == logic.recipe.implication => {
+                    Ok(!evaluate(logic, antecedent, values)? || evaluate(logic, consequent, values)?)
+                },
+                _ => Err(CompileError::NotPropositional)
+            }
+        }
+    }
+}
+
+fn push_line(lines: &mut Vec<WrittenLine>, used_lines: Vec<usize>, reference: &str, formula: WellFormedFormula) -> usize {
+    lines.push((used_lines, reference.to_owned(), formula));
+    lines.len()
+}
+
+// Modus ponens with the line proving the antecedent of the implication proven on the other line
+fn push_mp(logic: &PropositionalLogic, lines: &mut Vec<WrittenLine>, antecedent: usize, implication: usize) -> usize {
+    let formula = imp_consequent(&lines[implication - 1].2);
+    push_line(lines, vec![antecedent, implication], &logic.modus_ponens, formula)
+}
+
+fn push_discharge(lines: &mut Vec<WrittenLine>, recipe: &DeductionRecipe, assumption: usize, line: usize) -> usize {
+    let formula = implication(recipe, lines[assumption - 1].2.clone(), lines[line - 1].2.clone());
+    push_line(lines, vec![line], "discharge", formula)
+}
+
+// The lemmas the proofs of tautologies rely on, with their assertion, in 𝜑 and 𝜓, and their proof
+fn lemmas(logic: &PropositionalLogic) -> Vec<(String, WellFormedFormula, Vec<WrittenLine>)> {
+    let imp = |antecedent, consequent| implication(logic.recipe, antecedent, consequent);
+    let not = |wff| negation(logic, wff);
+    let (ph, ps) = (WellFormedFormula::Atomic(0), WellFormedFormula::Atomic(1));
+    let mut res = Vec::new();
+
+    let mut lines = Vec::new();
+    let assumption = push_line(&mut lines, Vec::new(), "assume", not(not(ph.clone())));
+    let weakened = push_line(&mut lines, Vec::new(), &logic.weakening, imp(
+        not(not(ph.clone())), imp(not(not(not(not(ph.clone())))), not(not(ph.clone())))
+    ));
+    let line = push_mp(logic, &mut lines, assumption, weakened);
+    let contraposed = push_line(&mut lines, Vec::new(), &logic.contraposition, imp(
+        imp(not(not(not(not(ph.clone())))), not(not(ph.clone()))), imp(not(ph.clone()), not(not(not(ph.clone()))))
+    ));
+    let line = push_mp(logic, &mut lines, line, contraposed);
+    let contraposed = push_line(&mut lines, Vec::new(), &logic.contraposition, imp(
+        imp(not(ph.clone()), not(not(not(ph.clone())))), imp(not(not(ph.clone())), ph.clone())
+    ));
+    let line = push_mp(logic, &mut lines, line, contraposed);
+    let line = push_mp(logic, &mut lines, assumption, line);
+    push_discharge(&mut lines, logic.recipe, assumption, line);
+    res.push((logic.not_not_elimination.clone(), imp(not(not(ph.clone())), ph.clone()), lines));
+
+    let mut lines = Vec::new();
+    let eliminated = push_line(&mut lines, Vec::new(), &logic.not_not_elimination, imp(not(not(not(ph.clone()))), not(ph.clone())));
+    let contraposed = push_line(&mut lines, Vec::new(), &logic.contraposition, imp(
+        imp(not(not(not(ph.clone()))), not(ph.clone())), imp(ph.clone(), not(not(ph.clone())))
+    ));
+    push_mp(logic, &mut lines, eliminated, contraposed);
+    res.push((logic.not_not_introduction.clone(), imp(ph.clone(), not(not(ph.clone()))), lines));
+
+    let mut lines = Vec::new();
+    let assumption = push_line(&mut lines, Vec::new(), "assume", not(ph.clone()));
+    let weakened = push_line(&mut lines, Vec::new(), &logic.weakening, imp(not(ph.clone()), imp(not(ps.clone()), not(ph.clone()))));
+    let line = push_mp(logic, &mut lines, assumption, weakened);
+    let contraposed = push_line(&mut lines, Vec::new(), &logic.contraposition, imp(
+        imp(not(ps.clone()), not(ph.clone())), imp(ph.clone(), ps.clone())
+    ));
+    let line = push_mp(logic, &mut lines, line, contraposed);
+    push_discharge(&mut lines, logic.recipe, assumption, line);
+    res.push((logic.absurdity.clone(), imp(not(ph.clone()), imp(ph.clone(), ps.clone())), lines));
+
+    // Assuming ¬¬(𝜑 ⇒ 𝜓) leads to ¬¬𝜓, so ¬𝜓 leads to ¬(𝜑 ⇒ 𝜓)
+    let mut lines = Vec::new();
+    let implied = imp(ph.clone(), ps.clone());
+    let antecedent = push_line(&mut lines, Vec::new(), "assume", ph.clone());
+    let assumption = push_line(&mut lines, Vec::new(), "assume", not(not(implied.clone())));
+    let eliminated = push_line(&mut lines, Vec::new(), &logic.not_not_elimination, imp(not(not(implied.clone())), implied.clone()));
+    let line = push_mp(logic, &mut lines, assumption, eliminated);
+    let line = push_mp(logic, &mut lines, antecedent, line);
+    let introduced = push_line(&mut lines, Vec::new(), &logic.not_not_introduction, imp(ps.clone(), not(not(ps.clone()))));
+    let line = push_mp(logic, &mut lines, line, introduced);
+    let line = push_discharge(&mut lines, logic.recipe, assumption, line);
+    let contraposed = push_line(&mut lines, Vec::new(), &logic.contraposition, imp(
+        imp(not(not(implied.clone())), not(not(ps.clone()))), imp(not(ps.clone()), not(implied.clone()))
+    ));
+    let line = push_mp(logic, &mut lines, line, contraposed);
+    push_discharge(&mut lines, logic.recipe, antecedent, line);
+    res.push((logic.false_implication.clone(), imp(ph.clone(), imp(not(ps.clone()), not(implied.clone()))), lines));
+
+    // Both cases give ¬𝜓 ⇒ ¬𝜑 and ¬𝜑, so ¬𝜓 leads to a contradiction, here ¬(𝜓 ⇒ 𝜓)
+    let mut lines = Vec::new();
+    let positive = push_line(&mut lines, Vec::new(), "assume", imp(ph.clone(), ps.clone()));
+    let negative = push_line(&mut lines, Vec::new(), "assume", imp(not(ph.clone()), ps.clone()));
+    let assumption = push_line(&mut lines, Vec::new(), "assume", not(not(ph.clone())));
+    let eliminated = push_line(&mut lines, Vec::new(), &logic.not_not_elimination, imp(not(not(ph.clone())), ph.clone()));
+    let line = push_mp(logic, &mut lines, assumption, eliminated);
+    let line = push_mp(logic, &mut lines, line, positive);
+    let introduced = push_line(&mut lines, Vec::new(), &logic.not_not_introduction, imp(ps.clone(), not(not(ps.clone()))));
+    let line = push_mp(logic, &mut lines, line, introduced);
+    let line = push_discharge(&mut lines, logic.recipe, assumption, line);
+    let contraposed = push_line(&mut lines, Vec::new(), &logic.contraposition, imp(
+        imp(not(not(ph.clone())), not(not(ps.clone()))), imp(not(ps.clone()), not(ph.clone()))
+    ));
+    let contraposition = push_mp(logic, &mut lines, line, contraposed);
+    let identity = imp(ps.clone(), ps.clone());
+    let assumption = push_line(&mut lines, Vec::new(), "assume", not(ps.clone()));
+    let line = push_mp(logic, &mut lines, assumption, contraposition);
+    let proven = push_mp(logic, &mut lines, line, negative);
+    let absurd = push_line(&mut lines, Vec::new(), &logic.absurdity, imp(not(ps.clone()), imp(ps.clone(), not(identity.clone()))));
+    let line = push_mp(logic, &mut lines, assumption, absurd);
+    let line = push_mp(logic, &mut lines, proven, line);
+    let line = push_discharge(&mut lines, logic.recipe, assumption, line);
+    let contraposed = push_line(&mut lines, Vec::new(), &logic.contraposition, imp(
+        imp(not(ps.clone()), not(identity.clone())), imp(identity.clone(), ps.clone())
+    ));
+    let line = push_mp(logic, &mut lines, line, contraposed);
+    let identity_assumption = push_line(&mut lines, Vec::new(), "assume", ps.clone());
+    let identity_line = push_discharge(&mut lines, logic.recipe, identity_assumption, identity_assumption);
+    let line = push_mp(logic, &mut lines, identity_line, line);
+    let line = push_discharge(&mut lines, logic.recipe, negative, line);
+    push_discharge(&mut lines, logic.recipe, positive, line);
+    res.push((logic.cases.clone(), imp(imp(ph.clone(), ps.clone()), imp(imp(not(ph.clone()), ps.clone()), ps.clone())), lines));
+    res
+}
+
+// Proves the formula if it is true, its negation otherwise, from the lines proving the variables or their
+//  negations according to their values
+fn prove_literal(
+    logic: &PropositionalLogic, wff: &WellFormedFormula, values: &Vec<bool>, assumptions: &Vec<usize>,
+    lines: &mut Vec<WrittenLine>
+) -> Result<usize, CompileError> {
+    let imp = |antecedent, consequent| implication(logic.recipe, antecedent, consequent);
+    let not = |wff| negation(logic, wff);
+    let wff_parameters = match wff {
+        WellFormedFormula::Atomic(id) => return Ok(assumptions[*id]),
+        WellFormedFormula::SyntaxComposite { wff_parameters, .. } => wff_parameters
+    };
+    match &wff_parameters[..] {
+        [negated] => {
+            let line = prove_literal(logic, negated, values, assumptions, lines)?;
+            if !evaluate(logic, negated, values)? {
+                return Ok(line);
+            };
+            let introduced = push_line(lines, Vec::new(), &logic.not_not_introduction, imp(negated.clone(), not(wff.clone())));
+            Ok(push_mp(logic, lines, line, introduced))
+        },
+        [antecedent, consequent] => {
+            if evaluate(logic, consequent, values)? {
+                let line = prove_literal(logic, consequent, values, assumptions, lines)?;
+                let weakened = push_line(lines, Vec::new(), &logic.weakening, imp(consequent.clone(), wff.clone()));
+                return Ok(push_mp(logic, lines, line, weakened));
+            };
+            let antecedent_line = prove_literal(logic, antecedent, values, assumptions, lines)?;
+            if !evaluate(logic, antecedent, values)? {
+                let absurd = push_line(lines, Vec::new(), &logic.absurdity, imp(not(antecedent.clone()), wff.clone()));
+                return Ok(push_mp(logic, lines, antecedent_line, absurd));
+            };
+            let consequent_line = prove_literal(logic, consequent, values, assumptions, lines)?;
+            let false_implication = push_line(lines, Vec::new(), &logic.false_implication, imp(
+                antecedent.clone(), imp(not(consequent.clone()), not(wff.clone()))
+            ));
+            let line = push_mp(logic, lines, antecedent_line, false_implication);
+            Ok(push_mp(logic, lines, consequent_line, line))
+        },
+        _ => Err(CompileError::NotPropositional)
+    }
+}
+
+// Proves the tautology by assuming each value of the next variable in turn, the proofs of both cases
+//  being combined once discharged
+fn prove_cases(
+    logic: &PropositionalLogic, wff: &WellFormedFormula, variable_count: usize,
+    values: &mut Vec<bool>, assumptions: &mut Vec<usize>, lines: &mut Vec<WrittenLine>
+) -> Result<usize, CompileError> {
+    let variable = values.len();
+    if variable == variable_count {
+        return prove_literal(logic, wff, values, assumptions, lines);
+    };
+    let imp = |antecedent, consequent| implication(logic.recipe, antecedent, consequent);
+    let literal = WellFormedFormula::Atomic(variable);
+    let mut cases = Vec::new();
+    for (value, assumed) in [(true, literal.clone()), (false, negation(logic, literal.clone()))] {
+        let assumption = push_line(lines, Vec::new(), "assume", assumed);
+        values.push(value);
+        assumptions.push(assumption);
+        let line = prove_cases(logic, wff, variable_count, values, assumptions, lines)?;
+        values.pop();
+        assumptions.pop();
+        cases.push(push_discharge(lines, logic.recipe, assumption, line));
+    };
+    let combined = push_line(lines, Vec::new(), &logic.cases, imp(
+        imp(literal.clone(), wff.clone()), imp(imp(negation(logic, literal), wff.clone()), wff.clone())
+    ));
+    let line = push_mp(logic, lines, cases[0], combined);
+    Ok(push_mp(logic, lines, cases[1], line))
+}
+
+fn write_proof(lines: &[WrittenLine], syntaxes: &Vec<Syntax>, names: &VariableNames) -> String {
+    let mut res = String::new();
+    for (index, (used_lines, reference, formula)) in lines.iter().enumerate() {
+        let used_lines = used_lines.iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        res.push_str( &format!("{}; {used_lines}; {reference}; {}\n", index + 1, wff_to_string(formula, syntaxes, names)) );
+    };
+    res
+}
+
+// Decides the formula by its truth table, the implication being the one of the deduction recipe.
+// A tautology gets a theorem file whose proof only cites the statements of the recipe, the contraposition
+//  and its lemmas, themselves proven from these statements
+pub fn decide_formula(
+    wff: &WellFormedFormula, names: &VariableNames, lib: &Library, references: &HashMap<String, Reference>
+) -> Result<Decision, CompileError> {
+    let logic = propositional_logic(lib, references)?;
+    let variable_count = names.wffs.len();
+    if !names.objects.is_empty() {
+        return Err(CompileError::NotPropositional);
+    };
+    for assignment in 0..(1usize << variable_count) {
+        let values = (0..variable_count)
+            .map(|variable| assignment & (1 << (variable_count - 1 - variable)) == 0)
+            .collect::<Vec<_>>();
+        if !evaluate(&logic, wff, &values)? {
+            return Ok(Decision::Falsified(names.wffs.iter().copied().zip(values).collect()));
+        };
+    };
+    let mut lines = Vec::new();
+    prove_cases(&logic, wff, variable_count, &mut Vec::new(), &mut Vec::new(), &mut lines)?;
+    let mut file = format!(
+        "## Theorem taut\n# Hypotheses\n\n# Assertion\n{}\n\n# Proof\n{}",
+        wff_to_string(wff, &lib.syntaxes, names), write_proof(&lines, &lib.syntaxes, names)
+    );
+    let lemma_names = VariableNames { wffs: vec!['𝜑', '𝜓'], objects: Vec::new() };
+    for (lemma_name, assertion, lines) in lemmas(&logic) {
+        file.push_str( &format!(
+            "\n# Lemma {lemma_name}\n# Hypotheses\n\n# Assertion\n{}\n\n# Proof\n{}",
+            wff_to_string(&assertion, &lib.syntaxes, &lemma_names), write_proof(&lines, &lib.syntaxes, &lemma_names)
+        ) );
+    };
+    Ok(Decision::Tautology(file))
+}
@@ -0,0 +1,647 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+use crate::parsing::{parse_file, MathFile};
+use super::compiler::{add_sort_to_lib, add_syndef_to_lib, add_axiom_to_lib, add_theo_to_lib, add_deduction_to_lib};
+use crate::parsing::parse_formula;
+use super::{
+    verify_theo, infer_theo, fixup_theo, minimize_theo, prove_theo, saturate_theo, suggest_statements, SearchBudget, decide_tautology, Decision, Reference, CompileError, Library, WellFormedFormula, Object, VariableNames,
+    Syntax, SyntaxType, Placeholder, Associativity, Unifier, new_metavariables, rename_wff, apply_unifier_wff, unify_wffs,
+    compile_formula, compile_object, variable_names, wff_to_string, free_variables, object_free_variables
+};
+
+type TestLibrary = (Library, HashMap<String, Reference>);
+
+// Designates the deduction statements, given as (modus ponens, weakening, distribution)
+fn designate_deduction((mp, k, s): (&str, &str, &str), lib: &mut Library, references: &HashMap<String, Reference>) {
+    let roles = [("modus ponens", mp), ("weakening", k), ("distribution", s)].into_iter()
+        .map(|(role, name)| (role.to_owned(), (name.to_owned(), 0)))
+        .collect();
+    add_deduction_to_lib(&roles, lib, references).expect("invalid deduction statements");
+}
+
+// Compiles the files in order, as listed in an order file. The deduction statements are designated
+//  before the first theorem, or after the last file without theorems
+fn library(files: &[&str], deduction: Option<(&str, &str, &str)>) -> TestLibrary {
+    let mut lib = Library {
+        sorts: Vec::new(),
+        syntaxes: Vec::new(),
+        definitions: Vec::new(),
+        axioms: Vec::new(),
+        theorems: Vec::new(),
+        lemmas: Vec::new(),
+        deduction: None
+    };
+    let mut references = HashMap::new();
+    let mut deduction = deduction;
+    for file in files {
+        let math_file = parse_file(file.to_string()).expect("unparsable library file");
+        if let MathFile::Theorem { .. } = math_file {
+            if let Some(statements) = deduction.take() {
+                designate_deduction(statements, &mut lib, &references);
+            };
+        };
+        let added = match math_file {
+            MathFile::Sort { .. } => add_sort_to_lib(math_file, &mut lib),
+            MathFile::SyntaxDefinition { .. } => add_syndef_to_lib(math_file, &mut lib, &mut references),
+            MathFile::Axiom { .. } => add_axiom_to_lib(math_file, &mut lib, &mut references),
+            MathFile::Theorem { .. } => add_theo_to_lib(math_file, &mut lib, &mut references).map(|_| ())
+        };
+        added.expect("uncompilable library file");
+    };
+    if let Some(statements) = deduction {
+        designate_deduction(statements, &mut lib, &references);
+    };
+    (lib, references)
+}
+
+fn verify(text: &str, (lib, references): &mut TestLibrary) -> Result<(), CompileError> {
+    let math_file = parse_file(text.to_owned()).expect("unparsable theorem");
+    verify_theo(math_file, lib, references).map(|_| ())
+}
+
+// Verifies each theorem with the library, expecting "Ok" for a valid theorem and the debug form of the error otherwise
+fn check_theorems(lib: &mut TestLibrary, cases: &[(&str, &str)]) {
+    for (text, expected) in cases {
+        let outcome = match verify(text, lib) {
+            Ok(()) => "Ok".to_owned(),
+            Err(e) => format!("{e:?}")
+        };
+        assert_eq!(outcome, *expected, "for\n{text}");
+    };
+}
+
+const IMPLICATION: &str = "## Syntax Definition (formula) im\n# Syntax\n(𝜑 ⇒ 𝜓)\n";
+const FOR_ALL: &str = "## Syntax Definition (formula, binds 𝑥) al\n# Syntax\n∀𝑥 𝜑\n";
+const EQUALITY: &str = "## Syntax Definition (formula, equality) eq\n# Syntax\n𝑥 = 𝑦\n";
+const EQUALITY_ID: &str = "## Axiom eqid\n# Hypotheses\n# Assertion\n∀𝑥 𝑥 = 𝑥\n";
+
+#[test]
+fn rewrite_refuses_binding_a_variable_of_the_equality() {
+    check_theorems(&mut library(&[IMPLICATION, FOR_ALL, EQUALITY, EQUALITY_ID], None), &[
+        (
+            "## Theorem bad\n# Hypotheses\nh: 𝑥 = 𝑦\n# Assertion\n∀𝑥 𝑦 = 𝑥\n\n# Proof\n\
+            1; ; h; 𝑥 = 𝑦\n2; ; eqid; ∀𝑥 𝑥 = 𝑥\n3; 1,2; rw; ∀𝑥 𝑦 = 𝑥\n",
+            "IncorrectResultingFormula(3)"
+        ),
+        (
+            "## Theorem good\n# Hypotheses\nh1: 𝑥 = 𝑦\nh2: ∀𝑧 𝑥 = 𝑧\n# Assertion\n∀𝑧 𝑦 = 𝑧\n\n# Proof\n\
+            1; ; h1; 𝑥 = 𝑦\n2; ; h2; ∀𝑧 𝑥 = 𝑧\n3; 1,2; rw; ∀𝑧 𝑦 = 𝑧\n",
+            "Ok"
+        )
+    ]);
+    let (mut lib, mut references) = library(&[IMPLICATION], None);
+    let axiom = parse_file("## Axiom rw\n# Hypotheses\n# Assertion\n(𝜑 ⇒ 𝜑)\n".to_owned()).unwrap();
+    assert_eq!(format!("{:?}", add_axiom_to_lib(axiom, &mut lib, &mut references)), "Err(ReservedName(\"rw\"))");
+}
+
+#[test]
+fn sorted_variables_can_be_bound() {
+    let mut lib = library(&[
+        "## Sort setvar\n# Variables\nu v w\n",
+        "## Syntax Definition (formula, binds u) sal\n# Syntax\n∀u 𝜑\n",
+        "## Syntax Definition (formula) seq\n# Syntax\nu ≐ v\n",
+        "## Axiom seqid\n# Hypotheses\n# Assertion\n∀u u ≐ u\n",
+        "## Axiom seqv\n# Hypotheses\n# Assertion\n∀u v ≐ v\n"
+    ], None);
+    check_theorems(&mut lib, &[
+        ("## Theorem renamed\n# Hypotheses\n# Assertion\n∀w w ≐ w\n\n# Proof\n1; ; seqid; ∀w w ≐ w\n", "Ok"),
+        ("## Theorem captured\n# Hypotheses\n# Assertion\n∀u u ≐ u\n\n# Proof\n1; ; seqv; ∀u u ≐ u\n", "VariableCapture(1)")
+    ]);
+}
+
+const NEGATION: &str = "## Syntax Definition (formula) no\n# Syntax\n¬𝜑\n";
+const MODUS_PONENS: &str = "## Axiom mp\n# Hypotheses\n𝜑\n(𝜑 ⇒ 𝜓)\n# Assertion\n𝜓\n";
+const WEAKENING: &str = "## Axiom a1\n# Hypotheses\n# Assertion\n(𝜑 ⇒ (𝜓 ⇒ 𝜑))\n";
+const DISTRIBUTION: &str = "## Axiom a2\n# Hypotheses\n# Assertion\n((𝜑 ⇒ (𝜓 ⇒ 𝜒)) ⇒ ((𝜑 ⇒ 𝜓) ⇒ (𝜑 ⇒ 𝜒)))\n";
+const CONTRAPOSITION: &str = "## Axiom a3\n# Hypotheses\n# Assertion\n((¬𝜑 ⇒ ¬𝜓) ⇒ (𝜓 ⇒ 𝜑))\n";
+
+fn propositional_library() -> TestLibrary {
+    library(
+        &[IMPLICATION, NEGATION, FOR_ALL, EQUALITY, MODUS_PONENS, WEAKENING, DISTRIBUTION, CONTRAPOSITION],
+        Some(("mp", "a1", "a2"))
+    )
+}
+
+#[test]
+fn exported_proofs_ground_free_metavariables_to_a_fresh_variable() {
+    // The statement has no wff variable, the weakened formula of a1 is left free by the goals
+    check_theorems(&mut propositional_library(), &[(
+        "## Theorem idx\n# Hypotheses\n# Assertion\n(𝑥 = 𝑥 ⇒ 𝑥 = 𝑥)\n\n# Tactics\n\
+        apply mp\napply a1\napply mp\napply a1\napply a2\n",
+        "Ok"
+    )]);
+}
+
+#[test]
+fn tautology_lemmas_avoid_the_names_of_the_library() {
+    let (mut lib, mut references) = propositional_library();
+    for axiom in [
+        "## Axiom notnot\n# Hypotheses\n# Assertion\n(𝜑 ⇒ 𝜑)\n",
+        "## Axiom cases\n# Hypotheses\n# Assertion\n(𝜑 ⇒ 𝜑)\n"
+    ] {
+        add_axiom_to_lib(parse_file(axiom.to_owned()).unwrap(), &mut lib, &mut references).unwrap();
+    };
+    let decision = decide_tautology("((𝜑 ⇒ 𝜓) ⇒ (¬𝜓 ⇒ ¬𝜑))", &lib, &references);
+    let Ok(Decision::Tautology(file)) = decision else {
+        panic!("{:?}", decision.err());
+    };
+    assert!(file.contains("# Lemma notnot1\n") && file.contains("# Lemma cases1\n"));
+    assert!(!file.contains("# Lemma notnot\n") && !file.contains("# Lemma cases\n"));
+}
+
+fn compile(formula: &str, (lib, _): &TestLibrary) -> Result<WellFormedFormula, CompileError> {
+    compile_formula(parse_formula(formula), &lib.sorts, &lib.syntaxes, &mut HashMap::new(), &mut HashMap::new())
+}
+
+// Compiles the formula and writes it back
+fn reprint(formula: &str, (lib, _): &TestLibrary) -> String {
+    let mut wffs = HashMap::new();
+    let mut objects = HashMap::new();
+    let wff = compile_formula(parse_formula(formula), &lib.sorts, &lib.syntaxes, &mut wffs, &mut objects)
+        .expect("uncompilable formula");
+    wff_to_string(&wff, &lib.syntaxes, &variable_names(&wffs, &objects))
+}
+
+#[test]
+fn formulas_are_printed_with_the_spacing_of_their_syntaxes() {
+    let lib = library(&[
+        IMPLICATION, FOR_ALL, EQUALITY,
+        "## Syntax Definition (formula) ex\n# Syntax\n∃𝑥𝜑\n",
+        "## Syntax Definition (object) app\n# Syntax\n𝐹(𝑎 ; 𝑏 …)\n"
+    ], None);
+    assert_eq!(reprint("∀𝑥(∃𝑦𝜑⇒𝑥=𝑦)", &lib), "∀𝑥 (∃𝑦𝜑 ⇒ 𝑥 = 𝑦)");
+    assert_eq!(reprint("𝐹(𝑥;𝑦𝑧𝑥)=𝑥", &lib), "𝐹(𝑥 ; 𝑦 𝑧 𝑥) = 𝑥");
+}
+
+const DISTINCT_EQUALITIES: &str = "## Axiom ax\n# Hypotheses\n# Assertion\n(𝑥 = 𝑥 ⇒ 𝑦 = 𝑦)\n# Distinct\n𝑥 𝑦\n";
+
+#[test]
+fn distinct_variables_are_checked_and_propagated() {
+    // t1 inherits the condition on 𝑥 and 𝑧 from ax, which t2 then violates
+    let mut lib = library(&[
+        IMPLICATION, EQUALITY, DISTINCT_EQUALITIES,
+        "## Theorem t1\n# Hypotheses\n# Assertion\n(𝑥 = 𝑥 ⇒ 𝑧 = 𝑧)\n\n# Proof\n1; ; ax; (𝑥 = 𝑥 ⇒ 𝑧 = 𝑧)\n"
+    ], None);
+    check_theorems(&mut lib, &[
+        (
+            "## Theorem t0\n# Hypotheses\n# Assertion\n(𝑥 = 𝑥 ⇒ 𝑥 = 𝑥)\n\n# Proof\n1; ; ax; (𝑥 = 𝑥 ⇒ 𝑥 = 𝑥)\n",
+            "DistinctVariablesViolation(1)"
+        ),
+        (
+            "## Theorem t2\n# Hypotheses\n# Assertion\n(𝑦 = 𝑦 ⇒ 𝑦 = 𝑦)\n\n# Proof\n1; ; t1; (𝑦 = 𝑦 ⇒ 𝑦 = 𝑦)\n",
+            "DistinctVariablesViolation(1)"
+        ),
+        ("## Theorem t3\n# Hypotheses\n# Assertion\n(𝑦 = 𝑦 ⇒ 𝑥 = 𝑥)\n\n# Proof\n1; ; t1; (𝑦 = 𝑦 ⇒ 𝑥 = 𝑥)\n", "Ok")
+    ]);
+}
+
+// The names of the object variables with the given ids, in alphabetical order
+fn object_names(ids: &[usize], names: &VariableNames) -> String {
+    let mut chars = ids.iter().map(|id| names.objects[*id]).collect::<Vec<_>>();
+    chars.sort();
+    chars.into_iter().collect()
+}
+
+#[test]
+fn free_variables_skip_the_bound_occurrences() {
+    let (lib, _) = library(&[
+        IMPLICATION, FOR_ALL, EQUALITY, "## Syntax Definition (object, binds 𝑥) ab\n# Syntax\n{𝑥 | 𝜑}\n"
+    ], None);
+    let mut wffs = HashMap::new();
+    let mut objects = HashMap::new();
+    let wff = compile_formula(
+        parse_formula("(∀𝑥 𝑥 = 𝑦 ⇒ {𝑧 | 𝑧 = 𝑥} = 𝑤)"), &lib.sorts, &lib.syntaxes, &mut wffs, &mut objects
+    ).expect("uncompilable formula");
+    let names = variable_names(&wffs, &objects);
+    assert_eq!(object_names(&free_variables(&wff, &lib.syntaxes), &names), "𝑤𝑥𝑦");
+    let mut wffs = HashMap::new();
+    let mut objects = HashMap::new();
+    let obj = compile_object(parse_formula("{𝑧 | ∀𝑥 𝑧 = 𝑥}"), &lib.sorts, &lib.syntaxes, &mut wffs, &mut objects)
+        .expect("uncompilable object");
+    assert!(object_free_variables(&obj, &lib.syntaxes).is_empty());
+}
+
+#[test]
+fn sort_variables_cannot_collide_with_object_variables() {
+    let (mut lib, _) = library(&["## Sort setvar\n# Variables\nu v\n"], None);
+    // The parser already refuses control characters
+    let control = MathFile::Sort { name: "bad".to_owned(), variables: vec!['w', '\u{3}'] };
+    for (sort, expected) in [
+        (parse_file("## Sort bad\n# Variables\nw 𝑥\n".to_owned()), "InvalidSortVariable('𝑥')"),
+        (parse_file("## Sort bad\n# Variables\nw v\n".to_owned()), "InvalidSortVariable('v')"),
+        (parse_file("## Sort setvar\n# Variables\nw\n".to_owned()), "DuplicateSort(\"setvar\")"),
+        (Ok(control), "InvalidSortVariable('\\u{3}')")
+    ] {
+        let added = add_sort_to_lib(sort.expect("unparsable sort"), &mut lib);
+        assert_eq!(format!("{:?}", added.err()), format!("Some({expected})"));
+    };
+}
+
+#[test]
+fn operators_group_by_precedence_then_associativity() {
+    let lib = library(&[
+        "## Syntax Definition (formula, precedence 1, right) or\n# Syntax\n𝜑 ∨ 𝜓\n",
+        "## Syntax Definition (formula, precedence 2, left) an\n# Syntax\n𝜑 ∧ 𝜓\n",
+        "## Syntax Definition (formula, precedence 1) bi\n# Syntax\n𝜑 ↔ 𝜓\n"
+    ], None);
+    for (formula, grouped) in [
+        ("𝜑 ∨ 𝜓 ∧ 𝜒", "𝜑 ∨ (𝜓 ∧ 𝜒)"),
+        ("𝜑 ∧ 𝜓 ∨ 𝜒", "(𝜑 ∧ 𝜓) ∨ 𝜒"),
+        ("𝜑 ∧ 𝜓 ∧ 𝜒", "(𝜑 ∧ 𝜓) ∧ 𝜒"),
+        ("𝜑 ∨ 𝜓 ∨ 𝜒", "𝜑 ∨ (𝜓 ∨ 𝜒)")
+    ] {
+        assert_eq!(compile(formula, &lib).expect("uncompilable formula"), compile(grouped, &lib).unwrap());
+    };
+    assert_eq!(reprint("𝜑 ∧ (𝜓 ∧ 𝜒)", &lib), "𝜑 ∧ (𝜓 ∧ 𝜒)");
+    assert_eq!(reprint("(𝜑 ∨ 𝜓) ∨ 𝜒", &lib), "(𝜑 ∨ 𝜓) ∨ 𝜒");
+    assert!(matches!(compile("𝜑 ↔ 𝜓 ↔ 𝜒", &lib), Err(CompileError::NonAssociativeChain)));
+    assert!(compile("(𝜑 ↔ 𝜓) ↔ 𝜒", &lib).is_ok());
+}
+
+#[test]
+fn chart_parser_reports_ambiguities() {
+    // "𝜑 ∧ 𝜓", built directly as the ambiguity check of the syntax definitions would refuse it
+    let mut lib = library(&[], None);
+    lib.0.syntaxes.push(Syntax {
+        syntax_type: SyntaxType::Formula,
+        formula: vec![Placeholder::WellFormedFormula(0), Placeholder::LiteralChar('∧'), Placeholder::WellFormedFormula(1)],
+        spaced_after: vec![0, 1],
+        bound_objects: Vec::new(),
+        precedence: None,
+        associativity: Associativity::NonAssociative,
+        role: None,
+        distinct_wff_count: 2,
+        distinct_object_count: 0
+    });
+    let outcome = |formula: &str| format!("{:?}", compile(formula, &lib).err());
+    assert_eq!(outcome("𝜑 ∧ (𝜓 ∧ 𝜒)"), "None");
+    assert_eq!(outcome("𝜑 ∧ 𝜓 ∧ 𝜒"), "Some(AmbiguousFormula([[(0, 2, 5)], [(0, 0, 3)]]))");
+    // The 42 readings of the whole formula are more than a chart cell keeps
+    assert_eq!(outcome("𝜑 ∧ 𝜓 ∧ 𝜒 ∧ 𝜃 ∧ 𝜏 ∧ 𝜂"), "Some(TooManyParses(0, 11))");
+}
+
+#[test]
+fn variadic_syntaxes_take_their_elements_as_last_parameters() {
+    let mut lib = library(&[
+        EQUALITY,
+        "## Syntax Definition (object) set\n# Syntax\n{𝑎, …}\n",
+        "## Axiom pair\n# Hypotheses\n# Assertion\n{𝑥, 𝑦} = {𝑦, 𝑥}\n"
+    ], None);
+    assert_eq!(reprint("{𝑥,𝑦,𝑧}={𝑥}", &lib), "{𝑥, 𝑦, 𝑧} = {𝑥}");
+    let Ok(WellFormedFormula::SyntaxComposite { object_parameters, .. }) = compile("{𝑥, 𝑦, 𝑧} = {𝑥}", &lib) else {
+        panic!("uncompilable formula");
+    };
+    let arities = object_parameters.iter()
+        .map(|obj| match obj {
+            Object::SyntaxComposite { object_parameters, .. } => object_parameters.len(),
+            _ => 0
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(arities, vec![3, 1]);
+    // A repetition only matches applications with as many elements
+    check_theorems(&mut lib, &[
+        ("## Theorem swap\n# Hypotheses\n# Assertion\n{𝑧, 𝑤} = {𝑤, 𝑧}\n\n# Proof\n1; ; pair; {𝑧, 𝑤} = {𝑤, 𝑧}\n", "Ok"),
+        (
+            "## Theorem triple\n# Hypotheses\n# Assertion\n{𝑥, 𝑦, 𝑧} = {𝑦, 𝑥, 𝑧}\n\n# Proof\n\
+            1; ; pair; {𝑥, 𝑦, 𝑧} = {𝑦, 𝑥, 𝑧}\n",
+            "IncorrectResultingFormula(1)"
+        )
+    ]);
+}
+
+// Adds the syntax definition to the library, giving "Ok" or the debug form of the error
+fn define_syntax(text: &str, (lib, references): &mut TestLibrary) -> String {
+    match add_syndef_to_lib(parse_file(text.to_owned()).expect("unparsable syntax definition"), lib, references) {
+        Ok(()) => "Ok".to_owned(),
+        Err(e) => format!("{e:?}")
+    }
+}
+
+#[test]
+fn ambiguous_syntaxes_are_refused_with_an_example() {
+    let mut lib = library(&[IMPLICATION, NEGATION], None);
+    for (text, expected) in [
+        ("## Syntax Definition (formula) an\n# Syntax\n𝜑 ∧ 𝜓\n", "AmbiguousSyntax(\"an\", \"¬𝛼∧𝛽\")"),
+        ("## Syntax Definition (formula) neg\n# Syntax\n¬𝜑\n", "AmbiguousSyntax(\"neg\", \"¬𝛼\")"),
+        ("## Syntax Definition (formula, precedence 1, left) an\n# Syntax\n𝜑 ∧ 𝜓\n", "Ok")
+    ] {
+        assert_eq!(define_syntax(text, &mut lib), expected, "for\n{text}");
+    };
+}
+
+const EQUIVALENCE: &str = "## Syntax Definition (formula, equivalence) bi\n# Syntax\n(𝜑 ⇔ 𝜓)\n";
+
+#[test]
+fn definitions_must_be_conservative() {
+    let mut lib = library(&[IMPLICATION, NEGATION, EQUIVALENCE], None);
+    for (definition, expected) in [
+        ("((𝜑 ∨ 𝜓) ⇒ (¬𝜑 ⇒ 𝜓))", "DefinitionNotAnEquivalence(\"or\")"),
+        ("((𝜑 ∨ 𝜑) ⇔ (¬𝜑 ⇒ 𝜑))", "InvalidDefiniendum(\"or\")"),
+        ("((𝜑 ∨ 𝜓) ⇔ (¬𝜑 ⇒ (𝜓 ∨ 𝜓)))", "CircularDefinition(\"or\")"),
+        ("((𝜑 ∨ 𝜓) ⇔ (¬𝜑 ⇒ 𝜒))", "UndefinedDefiniensVariable(\"or\")"),
+        ("((𝜑 ∨ 𝜓) ⇔ (¬𝜑 ⇒ 𝜓))", "Ok")
+    ] {
+        let text = format!("## Syntax Definition (formula) or\n# Syntax\n(𝜑 ∨ 𝜓)\n# Definition\n{definition}\n");
+        assert_eq!(define_syntax(&text, &mut lib), expected, "for\n{text}");
+    };
+}
+
+const DISJUNCTION: &str = "## Syntax Definition (formula) or\n# Syntax\n(𝜑 ∨ 𝜓)\n# Definition\n((𝜑 ∨ 𝜓) ⇔ (¬𝜑 ⇒ 𝜓))\n";
+
+#[test]
+fn definitions_unfold_and_fold_once() {
+    let mut lib = library(&[IMPLICATION, NEGATION, EQUIVALENCE, DISJUNCTION], None);
+    check_theorems(&mut lib, &[
+        (
+            "## Theorem orcom\n# Hypotheses\nh: (𝜑 ∨ 𝜑)\n# Assertion\n(𝜑 ∨ 𝜑)\n\n# Proof\n\
+            1; ; h; (𝜑 ∨ 𝜑)\n2; 1; unfold or; (¬𝜑 ⇒ 𝜑)\n3; 2; fold or; (𝜑 ∨ 𝜑)\n",
+            "Ok"
+        ),
+        (
+            "## Theorem nested\n# Hypotheses\nh: ((𝜑 ∨ 𝜓) ∨ 𝜒)\n# Assertion\n(¬𝜒 ⇒ 𝜒)\n\n# Proof\n\
+            1; ; h; ((𝜑 ∨ 𝜓) ∨ 𝜒)\n2; 1; unfold or; (¬(¬𝜑 ⇒ 𝜓) ⇒ 𝜒)\n",
+            "IncorrectResultingFormula(2)"
+        )
+    ]);
+    // A statement named after a keyword couldn't be cited
+    let (lib, references) = &mut lib;
+    let axiom = parse_file("## Axiom unfold\n# Hypotheses\n# Assertion\n(𝜑 ⇒ 𝜑)\n".to_owned()).unwrap();
+    assert_eq!(format!("{:?}", add_axiom_to_lib(axiom, lib, references)), "Err(ReservedName(\"unfold\"))");
+}
+
+#[test]
+fn line_labels_designate_their_lines() {
+    let hypotheses = "# Hypotheses\nh1: 𝜑\nh2: (𝜑 ⇒ 𝜓)\n# Assertion\n𝜓\n\n# Proof\n";
+    check_theorems(&mut propositional_library(), &[
+        (&format!("## Theorem labels\n{hypotheses}min; ; h1; 𝜑\nmaj; ; h2; (𝜑 ⇒ 𝜓)\n3; min,maj; mp; 𝜓\n"), "Ok"),
+        (
+            &format!("## Theorem swapped\n{hypotheses}min; ; h1; 𝜑\nmaj; ; h2; (𝜑 ⇒ 𝜓)\n3; maj,min; mp; 𝜓\n"),
+            "IncorrectResultingFormula(3)"
+        ),
+        (
+            &format!("## Theorem forward\n{hypotheses}1; ; h1; 𝜑\n2; maj; h2; (𝜑 ⇒ 𝜓)\nmaj; 1,2; mp; 𝜓\n"),
+            "UnknownLineLabel(\"maj\", 2)"
+        ),
+        (
+            &format!("## Theorem twice\n{hypotheses}min; ; h1; 𝜑\nmin; ; h2; (𝜑 ⇒ 𝜓)\n3; 1,2; mp; 𝜓\n"),
+            "DuplicateLineLabel(\"min\", 2)"
+        )
+    ]);
+}
+
+#[test]
+fn unknown_formulas_are_inferred_from_the_lines_using_them() {
+    let (mut lib, references) = propositional_library();
+    let theorem = "## Theorem infer\n# Hypotheses\nh: 𝜑\n# Assertion\n(𝜓 ⇒ 𝜑)\n\n# Proof\n\
+        1; ; h; ?\n2; ; a1; ?\n3; 1,2; mp; (𝜓 ⇒ 𝜑)\n";
+    let formulas = infer_theo(parse_file(theorem.to_owned()).unwrap(), &mut lib, &references);
+    assert_eq!(formulas.unwrap(), vec!["𝜑", "(𝜑 ⇒ (𝜓 ⇒ 𝜑))", "(𝜓 ⇒ 𝜑)"]);
+    // Nothing fixes the formula of a line no other line uses
+    let theorem = "## Theorem loose\n# Hypotheses\n# Assertion\n(𝜑 ⇒ (𝜓 ⇒ 𝜑))\n\n# Proof\n\
+        1; ; a1; ?\n2; ; a1; (𝜑 ⇒ (𝜓 ⇒ 𝜑))\n";
+    let formulas = infer_theo(parse_file(theorem.to_owned()).unwrap(), &mut lib, &references);
+    assert_eq!(format!("{:?}", formulas), "Err(UninferableFormula(1))");
+}
+
+#[test]
+fn unification_finds_most_general_unifiers() {
+    let lib = propositional_library();
+    let mut wffs = HashMap::new();
+    let mut objects = HashMap::new();
+    let target = compile_formula(parse_formula("(𝜑 ⇒ (𝜑 ⇒ 𝜓))"), &lib.0.sorts, &lib.0.syntaxes, &mut wffs, &mut objects)
+        .unwrap();
+    let names = variable_names(&wffs, &objects);
+    // (?0 ⇒ ?1) against the fixed variables of the target
+    let mut unifier = Unifier { wffs: Vec::new(), objects: Vec::new() };
+    let pattern = rename_wff(&compile("(𝜑 ⇒ 𝜓)", &lib).unwrap(), new_metavariables(&mut unifier, 2, 0));
+    assert!(unify_wffs(&pattern, &target, &mut unifier, &lib.0.syntaxes).is_ok());
+    let unified = apply_unifier_wff(&pattern, &unifier);
+    assert_eq!(wff_to_string(&unified, &lib.0.syntaxes, &names), "(𝜑 ⇒ (𝜑 ⇒ 𝜓))");
+    assert_eq!(unifier.wffs[0], Some(WellFormedFormula::Atomic(0)));
+    // (?0 ⇒ ?0) against (?1 ⇒ (?1 ⇒ ?2)) would need ?1 = (?1 ⇒ ?2)
+    let mut unifier = Unifier { wffs: Vec::new(), objects: Vec::new() };
+    let loop_pattern = rename_wff(&compile("(𝜑 ⇒ 𝜑)", &lib).unwrap(), new_metavariables(&mut unifier, 1, 0));
+    let nested = rename_wff(&target, new_metavariables(&mut unifier, 2, 0));
+    assert!(unify_wffs(&loop_pattern, &nested, &mut unifier, &lib.0.syntaxes).is_err());
+}
+
+#[test]
+fn annotated_substitutions_are_checked_against_the_line() {
+    let hypotheses = "# Hypotheses\nh1: ¬𝜓\nh2: (¬𝜓 ⇒ 𝜒)\n# Assertion\n𝜒\n\n# Proof\n1; ; h1; ¬𝜓\n2; ; h2; (¬𝜓 ⇒ 𝜒)\n";
+    check_theorems(&mut propositional_library(), &[
+        (&format!("## Theorem agrees\n{hypotheses}3; 1,2; mp[𝜑:=¬𝜓, 𝜓:=𝜒]; 𝜒\n"), "Ok"),
+        (&format!("## Theorem fixes\n{hypotheses}3; 1,2; mp[𝜓:=𝜒]; ?\n"), "Ok"),
+        (&format!("## Theorem disagrees\n{hypotheses}3; 1,2; mp[𝜑:=𝜓, 𝜓:=𝜒]; 𝜒\n"), "SubstitutionMismatch('𝜑', 3)"),
+        (&format!("## Theorem unknown\n{hypotheses}3; 1,2; mp[𝜒:=𝜒]; 𝜒\n"), "UnknownSubstitutedVariable('𝜒', 3)"),
+        (
+            "## Theorem hypothesis\n# Hypotheses\nh1: ¬𝜓\n# Assertion\n¬𝜓\n\n# Proof\n1; ; h1[𝜓:=𝜓]; ¬𝜓\n",
+            "UnexpectedSubstitution(1)"
+        )
+    ]);
+}
+
+const IDENTITY_PROOF: &str = "1; ; a1; (𝜑 ⇒ ((𝜑 ⇒ 𝜑) ⇒ 𝜑))\n\
+    2; ; a2; ((𝜑 ⇒ ((𝜑 ⇒ 𝜑) ⇒ 𝜑)) ⇒ ((𝜑 ⇒ (𝜑 ⇒ 𝜑)) ⇒ (𝜑 ⇒ 𝜑)))\n\
+    3; 1,2; mp; ((𝜑 ⇒ (𝜑 ⇒ 𝜑)) ⇒ (𝜑 ⇒ 𝜑))\n4; ; a1; (𝜑 ⇒ (𝜑 ⇒ 𝜑))\n5; 4,3; mp; (𝜑 ⇒ 𝜑)\n";
+
+#[test]
+fn lemmas_are_private_to_their_file() {
+    let theorem = "## Theorem idn\n# Hypotheses\n# Assertion\n(¬𝜑 ⇒ ¬𝜑)\n\n# Proof\n1; ; id; (¬𝜑 ⇒ ¬𝜑)\n";
+    let lemma = |name: &str, proof: &str| format!("\n# Lemma {name}\n# Hypotheses\n# Assertion\n(𝜑 ⇒ 𝜑)\n\n# Proof\n{proof}");
+    let mut lib = library(&[
+        IMPLICATION, NEGATION, MODUS_PONENS, WEAKENING, DISTRIBUTION, &(theorem.to_owned() + &lemma("id", IDENTITY_PROOF))
+    ], Some(("mp", "a1", "a2")));
+    check_theorems(&mut lib, &[
+        ("## Theorem idi\n# Hypotheses\n# Assertion\n(𝜓 ⇒ 𝜓)\n\n# Proof\n1; ; id; (𝜓 ⇒ 𝜓)\n", "UnknownTheorem(\"id\", 1)"),
+        (&(theorem.to_owned() + &lemma("id", "1; ; a1; (𝜑 ⇒ 𝜑)\n")), "InLemma(\"id\", IncorrectResultingFormula(1))"),
+        (&(theorem.to_owned() + &lemma("id", IDENTITY_PROOF) + &lemma("id", IDENTITY_PROOF)), "DuplicateLemma(\"id\")"),
+        (
+            &(theorem.to_owned() + &lemma("id", "1; ; di; (𝜑 ⇒ 𝜑)\n") + &lemma("di", IDENTITY_PROOF)),
+            "InLemma(\"id\", UnknownTheorem(\"di\", 1))"
+        ),
+        (&(theorem.to_owned() + &lemma("di", IDENTITY_PROOF) + &lemma("id", "1; ; di; (𝜑 ⇒ 𝜑)\n")), "Ok")
+    ]);
+}
+
+#[test]
+fn assumption_blocks_expand_into_plain_proofs() {
+    let syllogism = "## Theorem syl\n# Hypotheses\nh1: (𝜑 ⇒ 𝜓)\nh2: (𝜓 ⇒ 𝜒)\n# Assertion\n(𝜑 ⇒ 𝜒)\n\n# Proof\n\
+        1; ; h1; (𝜑 ⇒ 𝜓)\n2; ; h2; (𝜓 ⇒ 𝜒)\n3; ; assume; 𝜑\n4; 3,1; mp; 𝜓\n5; 4,2; mp; 𝜒\n";
+    let mut lib = propositional_library();
+    check_theorems(&mut lib, &[
+        (&format!("{syllogism}6; 5; discharge; (𝜑 ⇒ 𝜒)\n"), "Ok"),
+        (&format!("{syllogism}6; 5; discharge; (𝜑 ⇒ 𝜒)\n7; 4,2; mp; 𝜒\n"), "InaccessibleHypothesis(4, 7)"),
+        (syllogism, "UndischargedAssumption(3)"),
+        (&format!("{syllogism}6; 5; discharge; (𝜑 ⇒ 𝜒)\n7; 6; discharge; (𝜑 ⇒ (𝜑 ⇒ 𝜒))\n"), "UnmatchedDischarge(7)")
+    ]);
+    let (lib, references) = &mut lib;
+    let file = parse_file(format!("{syllogism}6; 5; discharge; (𝜑 ⇒ 𝜒)\n")).unwrap();
+    add_theo_to_lib(file, lib, references).unwrap();
+    let theorem = lib.theorems.last().unwrap();
+    // The block only leaves steps citing the hypotheses and the deduction statements
+    assert!(theorem.proof.iter()
+        .all(|step| matches!(step.theorem_ref, Reference::HypothesisReference(_) | Reference::AxiomReference(..)))
+    );
+    let conclusion = &theorem.proof.last().unwrap().resulting_formula;
+    assert_eq!(wff_to_string(conclusion, &lib.syntaxes, &theorem.variable_names), "(𝜑 ⇒ 𝜒)");
+}
+
+#[test]
+fn proofs_are_linted_per_statement() {
+    let (mut lib, mut references) = propositional_library();
+    let theorem = "## Theorem lint\n# Hypotheses\nh1: 𝜑\nh2: (𝜑 ⇒ 𝜓)\nh3: 𝜒\n# Assertion\n𝜓\n\n# Proof\n\
+        1; ; h1; 𝜑\n2; ; h2; (𝜑 ⇒ 𝜓)\n3; ; h1; 𝜑\n4; ; a1; (𝜑 ⇒ (𝜓 ⇒ 𝜑))\n5; 3,2; mp; 𝜓\n\
+        \n# Lemma unused\n# Hypotheses\nh: 𝜓\n# Assertion\n(𝜑 ⇒ (𝜓 ⇒ 𝜑))\n\n# Proof\n1; ; a1; (𝜑 ⇒ (𝜓 ⇒ 𝜑))\n";
+    let warnings = verify_theo(parse_file(theorem.to_owned()).unwrap(), &mut lib, &mut references).unwrap();
+    let mut warnings = warnings.iter().map(|warning| format!("{warning:?}")).collect::<Vec<_>>();
+    warnings.sort();
+    assert_eq!(warnings, vec![
+        "DuplicateStep(\"lint\", 3, 1)",
+        "UnusedHypothesis(\"lint\", \"h3\")",
+        "UnusedHypothesis(\"unused\", \"h\")",
+        "UnusedStep(\"lint\", 1)",
+        "UnusedStep(\"lint\", 4)"
+    ]);
+}
+
+// The minimized proofs as "<original line>; <used lines>; <citation>" lines, counted from 1
+fn minimize(text: &str, (lib, references): &mut TestLibrary, cite_theorems: bool) -> Vec<Vec<String>> {
+    minimize_theo(parse_file(text.to_owned()).unwrap(), lib, references, cite_theorems)
+        .expect("invalid theorem")
+        .iter()
+        .map(|lines| lines.iter()
+            .map(|minimized| {
+                let used_lines = minimized.used_lines.iter().map(|used| (used + 1).to_string()).collect::<Vec<_>>();
+                let citation = minimized.citation.as_ref().map_or("", |(theo_name, _)| theo_name.as_str());
+                format!("{}; {}; {citation}", minimized.line + 1, used_lines.join(","))
+            })
+            .collect()
+        )
+        .collect()
+}
+
+#[test]
+fn minimized_proofs_drop_unused_and_duplicate_lines() {
+    let identity = format!("## Theorem id\n# Hypotheses\n# Assertion\n(𝜑 ⇒ 𝜑)\n\n# Proof\n{IDENTITY_PROOF}");
+    let mut lib = library(
+        &[IMPLICATION, NEGATION, MODUS_PONENS, WEAKENING, DISTRIBUTION, &identity],
+        Some(("mp", "a1", "a2"))
+    );
+    let theorem = "## Theorem lint\n# Hypotheses\nh1: 𝜑\nh2: (𝜑 ⇒ 𝜓)\n# Assertion\n𝜓\n\n# Proof\n\
+        1; ; h1; 𝜑\n2; ; h2; (𝜑 ⇒ 𝜓)\n3; ; h1; 𝜑\n4; ; a1; (𝜑 ⇒ (𝜓 ⇒ 𝜑))\n5; 3,2; mp; 𝜓\n";
+    assert_eq!(minimize(theorem, &mut lib, false), vec![vec!["1; ; ", "2; ; ", "5; 1,2; "]]);
+    // The identity is proven again, by a proof which citing the theorem replaces
+    let reproven = identity.replace("Theorem id\n", "Theorem idx\n").replace('𝜑', "¬𝜓");
+    assert_eq!(minimize(&reproven, &mut lib, false), vec![vec!["1; ; ", "2; ; ", "3; 1,2; ", "4; ; ", "5; 4,3; "]]);
+    assert_eq!(minimize(&reproven, &mut lib, true), vec![vec!["5; ; id"]]);
+}
+
+#[test]
+fn proof_search_finds_verified_proofs() {
+    let mut lib = propositional_library();
+    let search = |text: &str, max_depth: usize, (lib, references): &TestLibrary| {
+        let budget = SearchBudget { max_depth, max_steps: 100_000, deadline: Instant::now() + Duration::from_secs(60) };
+        prove_theo(parse_file(text.to_owned()).unwrap(), lib, references, &budget)
+    };
+    let statement = "## Theorem idx\n# Hypotheses\n# Assertion\n(𝜑 ⇒ 𝜑)\n";
+    let proof_lines = search(statement, 5, &lib).expect("no proof found");
+    let proven = format!("{statement}\n# Proof\n{}\n", proof_lines.join("\n"));
+    check_theorems(&mut lib, &[(&proven, "Ok")]);
+    assert!(matches!(search(statement, 1, &lib), Err(CompileError::ProofNotFound(0))));
+    assert!(matches!(search(&proven, 5, &lib), Err(CompileError::AlreadyProven)));
+}
+
+fn suggest(formula: &str, (lib, _): &TestLibrary) -> Vec<(String, Vec<String>)> {
+    suggest_statements(formula, lib).unwrap().into_iter()
+        .map(|suggestion| (suggestion.name, suggestion.hypotheses))
+        .collect()
+}
+
+#[test]
+fn suggestions_instantiate_the_hypotheses_of_matching_statements() {
+    let owned = |suggestions: &[(&str, &[&str])]| suggestions.iter()
+        .map(|(name, hypotheses)| (name.to_string(), hypotheses.iter().map(|hypot| hypot.to_string()).collect()))
+        .collect::<Vec<_>>();
+    let lib = propositional_library();
+    // The formula already uses 𝜑, the free hypothesis of mp gets the first unused name
+    assert_eq!(
+        suggest("(𝜓 ⇒ (𝜑 ⇒ 𝜓))", &lib),
+        owned(&[("mp", &["𝛼", "(𝛼 ⇒ (𝜓 ⇒ (𝜑 ⇒ 𝜓)))"]), ("a1", &[])])
+    );
+    let lib = library(&[IMPLICATION, EQUALITY, FOR_ALL, MODUS_PONENS, DISTINCT_EQUALITIES], None);
+    assert_eq!(suggest("(𝑥 = 𝑥 ⇒ 𝑥 = 𝑥)", &lib), owned(&[("mp", &["𝜑", "(𝜑 ⇒ (𝑥 = 𝑥 ⇒ 𝑥 = 𝑥))"])]));
+    assert_eq!(
+        suggest("(𝑥 = 𝑥 ⇒ 𝑧 = 𝑧)", &lib),
+        owned(&[("mp", &["𝜑", "(𝜑 ⇒ (𝑥 = 𝑥 ⇒ 𝑧 = 𝑧))"]), ("ax", &[])])
+    );
+}
+
+fn fixup(text: &str, (lib, references): &mut TestLibrary) -> Result<Vec<String>, CompileError> {
+    fixup_theo(parse_file(text.to_owned()).unwrap(), lib, references)
+}
+
+#[test]
+fn missing_used_lines_are_filled_with_the_earliest_matching_ones() {
+    let mut lib = propositional_library();
+    let statement = "## Theorem dup\n# Hypotheses\nh1: 𝜑\nh2: (𝜑 ⇒ 𝜓)\n# Assertion\n𝜓\n\n# Proof\n";
+    // Line 3 repeats line 1, which is preferred to it
+    let proof = "1; ; h1; 𝜑\n2; ; h2; (𝜑 ⇒ 𝜓)\n3; ; h1; 𝜑\n4; ; mp; 𝜓\n";
+    assert_eq!(fixup(&format!("{statement}{proof}"), &mut lib).unwrap(), vec!["", "", "", "1,2"]);
+    // Written used lines are kept
+    let proof = "1; ; h1; 𝜑\n2; ; h2; (𝜑 ⇒ 𝜓)\n3; ; h1; 𝜑\n4; 3,2; mp; 𝜓\n";
+    assert_eq!(fixup(&format!("{statement}{proof}"), &mut lib).unwrap(), vec!["", "", "", "3,2"]);
+    // The hypotheses must match consistently with the formula of the line
+    let proof = "1; ; h1; 𝜑\n2; ; h2; (𝜑 ⇒ 𝜓)\n3; ; mp; 𝜑\n";
+    assert!(matches!(fixup(&format!("{statement}{proof}"), &mut lib), Err(CompileError::UnfillableHypotheses(3))));
+    let proof = "1; ; h1; 𝜑\n2; ; h2; (𝜑 ⇒ 𝜓)\n3; ; mp; ?\n";
+    assert!(matches!(fixup(&format!("{statement}{proof}"), &mut lib), Err(CompileError::UninferableFormula(3))));
+}
+
+#[test]
+fn saturation_derives_formulas_by_depth() {
+    let mut lib = library(&[IMPLICATION, MODUS_PONENS], None);
+    let theorem = "## Theorem chain\n# Hypotheses\nh1: 𝜑\nh2: (𝜑 ⇒ 𝜓)\nh3: (𝜓 ⇒ 𝜒)\n# Assertion\n𝜒\n";
+    let saturate = |max_depth: usize, max_formulas: usize, (lib, references): &TestLibrary| {
+        let (lines, interrupted) = saturate_theo(parse_file(theorem.to_owned()).unwrap(), lib, references, max_depth, max_formulas)
+            .unwrap();
+        (lines.into_iter().map(|(depth, line)| format!("{depth}: {line}")).collect::<Vec<_>>(), interrupted)
+    };
+    let hypotheses = ["0: 1; ; h1; 𝜑", "0: 2; ; h2; (𝜑 ⇒ 𝜓)", "0: 3; ; h3; (𝜓 ⇒ 𝜒)"];
+    let (lines, interrupted) = saturate(3, 100, &lib);
+    assert_eq!(lines, [&hypotheses[..], &["1: 4; 1,2; mp; 𝜓", "2: 5; 4,3; mp; 𝜒"]].concat());
+    assert!(!interrupted);
+    // The depth bound ends the derivation, the formula bound interrupts it
+    let first_step = [&hypotheses[..], &["1: 4; 1,2; mp; 𝜓"]].concat();
+    let (depth_bounded, interrupted) = saturate(1, 100, &lib);
+    assert_eq!(depth_bounded, first_step);
+    assert!(!interrupted);
+    let (formula_bounded, interrupted) = saturate(3, 4, &lib);
+    assert_eq!(formula_bounded, first_step);
+    assert!(interrupted);
+    // The derived lines are a proof of the formulas they reach
+    let proof = lines.iter().map(|line| &line[3..]).collect::<Vec<_>>().join("\n");
+    check_theorems(&mut lib, &[(&format!("{theorem}\n# Proof\n{proof}\n"), "Ok")]);
+}
+
+#[test]
+fn tactics_expand_into_a_proof() {
+    let mut lib = propositional_library();
+    check_theorems(&mut lib, &[
+        (
+            "## Theorem mpx\n# Hypotheses\nh1: 𝜑\nh2: (𝜑 ⇒ 𝜓)\n# Assertion\n𝜓\n\n# Tactics\n\
+            try intro\napply mp\nrepeat exact\n",
+            "Ok"
+        ),
+        (
+            "## Theorem idi\n# Hypotheses\n# Assertion\n(𝜑 ⇒ (𝜓 ⇒ 𝜑))\n\n# Tactics\nintro h\nintro\nexact h\n",
+            "Ok"
+        ),
+        (
+            "## Theorem open\n# Hypotheses\nh1: 𝜑\nh2: (𝜑 ⇒ 𝜓)\n# Assertion\n𝜓\n\n# Tactics\napply mp\nexact h1\n",
+            "UnprovenGoals(1)"
+        ),
+        (
+            "## Theorem failed\n# Hypotheses\n# Assertion\n(𝜑 ⇒ 𝜓)\n\n# Tactics\nintro\nexact\n",
+            "TacticFailed(2, InapplicableStatement(\"exact\", 1))"
+        )
+    ]);
+}
@@ -1,21 +1,36 @@
+use crate::parsing::{Associativity, SyntaxRole};
+
 #[derive(Debug)]
+pub struct Sort {
+    pub name: String,
+    pub variables: Vec<char>
+}
+
+#[derive(Debug, Clone)]
 pub enum Placeholder {
     LiteralChar(char),
     WellFormedFormula(usize),
     Object(usize),
+    Sorted(usize, usize),  // (sort, object id)
     Repetition
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SyntaxType {
     Formula,
-    Object
+    Object,
+    Sort(usize)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Syntax {
     pub syntax_type: SyntaxType,
     pub formula: Vec<Placeholder>,
+    pub spaced_after: Vec<usize>,  // Positions in 'formula' written with a space after them
+    pub bound_objects: Vec<usize>,
+    pub precedence: Option<usize>,
+    pub associativity: Associativity,
+    pub role: Option<SyntaxRole>,
     pub distinct_wff_count: usize,
     pub distinct_object_count: usize
 }
@@ -33,6 +48,7 @@ pub enum WellFormedFormula {
 #[derive(Debug, PartialEq, Clone)]
 pub enum Object {
     Atomic(usize),
+    SortedAtomic(usize, usize),  // (sort, id), shares the ids of 'Atomic'
     SyntaxComposite {
         syntax_ref: usize,
         wff_parameters: Vec<WellFormedFormula>,
@@ -40,12 +56,26 @@ pub enum Object {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Variable {
+    Wff(usize),
+    Object(usize)
+}
+
+// Characters of the variables of a compiled statement, indexed by their ids
+#[derive(Debug)]
+pub struct VariableNames {
+    pub wffs: Vec<char>,
+    pub objects: Vec<char>
+}
+
 #[derive(Debug)]
 pub struct Definition {
     pub name: String,
     pub definition: WellFormedFormula,
     pub distinct_wff_count: usize,
-    pub distinct_object_count: usize
+    pub distinct_object_count: usize,
+    pub variable_names: VariableNames
 }
 
 #[derive(Debug)]
@@ -53,22 +83,36 @@ pub struct Axiom {
     pub name: String,
     pub hypotheses: Vec<WellFormedFormula>,
     pub assertions: Vec<WellFormedFormula>,
+    pub distinct_variables: Vec<(Variable, Variable)>,
     pub distinct_wff_count: usize,
-    pub distinct_object_count: usize
+    pub distinct_object_count: usize,
+    pub variable_names: VariableNames
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::enum_variant_names, reason = "each variant is a kind of reference")]
 pub enum Reference {
     HypothesisReference(usize),
     DefinitionReference(usize),
     AxiomReference(usize, usize),
-    TheoremReference(usize, usize)
+    TheoremReference(usize, usize),
+    LineReference(usize),  // A previous line of the proof
+    LemmaReference(usize, usize)  // A lemma of the theorem's file
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StepKind {
+    Assertion,  // The reference is applied as a whole
+    Unfold,  // One definiendum of the definition is replaced by its definiens
+    Fold,  // One definiens of the definition is replaced by its definiendum
+    Rewrite  // One side of the equivalence or equality is replaced by the other
 }
 
 #[derive(Debug)]
 pub struct LogicStep {
     pub used_hypotheses: Vec<usize>,
     pub theorem_ref: Reference,
+    pub kind: StepKind,
     pub resulting_formula: WellFormedFormula
 }
 
@@ -77,23 +121,38 @@ pub struct Theorem {
     pub name: String,
     pub hypotheses: Vec<WellFormedFormula>,
     pub assertions: Vec<WellFormedFormula>,
+    pub distinct_variables: Vec<(Variable, Variable)>,
     pub proof: Vec<LogicStep>,
     pub distinct_wff_count: usize,
-    pub distinct_object_count: usize
+    pub distinct_object_count: usize,
+    pub variable_names: VariableNames
 }
 
 use crate::parsing::FormulaChar;
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PartiallyCompiled {
     NotCompiled(FormulaChar),
     CompiledFormula(WellFormedFormula),
     CompiledObject(Object)
 }
 
+// Statements designated by the library to turn the assume/discharge blocks of proofs into plain proofs,
+//  following the proof of the deduction theorem
+#[derive(Debug)]
+pub struct DeductionRecipe {
+    pub implication: usize,  // The syntax of "(𝜑 ⇒ 𝜓)"
+    pub modus_ponens: Reference,  // Hypotheses 𝜑 and (𝜑 ⇒ 𝜓), assertion 𝜓
+    pub weakening: Reference,  // (𝜑 ⇒ (𝜓 ⇒ 𝜑))
+    pub distribution: Reference  // ((𝜑 ⇒ (𝜓 ⇒ 𝜒)) ⇒ ((𝜑 ⇒ 𝜓) ⇒ (𝜑 ⇒ 𝜒)))
+}
+
 #[derive(Debug)]
 pub struct Library {
+    pub sorts: Vec<Sort>,
     pub syntaxes: Vec<Syntax>,
     pub definitions: Vec<Definition>,
     pub axioms: Vec<Axiom>,
-    pub theorems: Vec<Theorem>
+    pub theorems: Vec<Theorem>,
+    pub lemmas: Vec<Theorem>,  // Private to the theorems whose files state them, they have no name reference
+    pub deduction: Option<DeductionRecipe>
 }
@@ -0,0 +1,285 @@
+use super::{Syntax, WellFormedFormula, Object, object_sort, same_arity};
+
+// Variables with an id of at least this offset are metavariables: unknown formulas or objects that unification
+//  may assign, while the other variables are the fixed variables of the statement being proven.
+// Each kind of variable gets half of the ids, whatever the width of usize
+pub const METAVARIABLE_OFFSET: usize = usize::MAX / 2 + 1;
+
+// Assignments of the metavariables, indexed by their id minus the offset.
+// An assignment may contain other metavariables, so it is only complete once applied repeatedly
+#[derive(Clone)]
+pub struct Unifier {
+    pub wffs: Vec<Option<WellFormedFormula>>,
+    pub objects: Vec<Option<Object>>
+}
+
+pub fn is_metavariable(id: usize) -> bool {
+    id >= METAVARIABLE_OFFSET
+}
+
+// Reserves metavariables for the variables of a cited statement, returns the offsets to rename them with
+pub fn new_metavariables(unifier: &mut Unifier, wff_count: usize, object_count: usize) -> (usize, usize) {
+    let offsets = (METAVARIABLE_OFFSET + unifier.wffs.len(), METAVARIABLE_OFFSET + unifier.objects.len());
+    unifier.wffs.extend((0..wff_count).map(|_| None));
+    unifier.objects.extend((0..object_count).map(|_| None));
+    offsets
+}
+
+pub fn rename_wff(wff: &WellFormedFormula, (wff_offset, object_offset): (usize, usize)) -> WellFormedFormula {
+    match wff {
+        WellFormedFormula::Atomic(id) => WellFormedFormula::Atomic(id + wff_offset),
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => WellFormedFormula::SyntaxComposite {
+            syntax_ref: *syntax_ref,
+            wff_parameters: wff_parameters.iter()
+                .map(|param_wff| rename_wff(param_wff, (wff_offset, object_offset)))
+                .collect(),
+            object_parameters: object_parameters.iter()
+                .map(|param_obj| rename_object(param_obj, (wff_offset, object_offset)))
+                .collect()
+        }
+    }
+}
+
+pub fn rename_object(obj: &Object, (wff_offset, object_offset): (usize, usize)) -> Object {
+    match obj {
+        Object::Atomic(id) => Object::Atomic(id + object_offset),
+        Object::SortedAtomic(sort, id) => Object::SortedAtomic(*sort, id + object_offset),
+        Object::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => Object::SyntaxComposite {
+            syntax_ref: *syntax_ref,
+            wff_parameters: wff_parameters.iter()
+                .map(|param_wff| rename_wff(param_wff, (wff_offset, object_offset)))
+                .collect(),
+            object_parameters: object_parameters.iter()
+                .map(|param_obj| rename_object(param_obj, (wff_offset, object_offset)))
+                .collect()
+        }
+    }
+}
+
+// Replaces the assigned metavariables, recursively
+pub fn apply_unifier_wff(wff: &WellFormedFormula, unifier: &Unifier) -> WellFormedFormula {
+    match wff {
+        WellFormedFormula::Atomic(id) => match unifier.wffs.get(id.wrapping_sub(METAVARIABLE_OFFSET)) {
+            Some(Some(assigned)) if is_metavariable(*id) => apply_unifier_wff(assigned, unifier),
+            _ => wff.clone()
+        },
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => WellFormedFormula::SyntaxComposite {
+            syntax_ref: *syntax_ref,
+            wff_parameters: wff_parameters.iter()
+                .map(|param_wff| apply_unifier_wff(param_wff, unifier))
+                .collect(),
+            object_parameters: object_parameters.iter()
+                .map(|param_obj| apply_unifier_object(param_obj, unifier))
+                .collect()
+        }
+    }
+}
+
+pub fn apply_unifier_object(obj: &Object, unifier: &Unifier) -> Object {
+    match obj {
+        Object::Atomic(id) | Object::SortedAtomic(_, id) => match unifier.objects.get(id.wrapping_sub(METAVARIABLE_OFFSET)) {
+            Some(Some(assigned)) if is_metavariable(*id) => apply_unifier_object(assigned, unifier),
+            _ => obj.clone()
+        },
+        Object::SyntaxComposite {
+            syntax_ref,
+            wff_parameters,
+            object_parameters
+        } => Object::SyntaxComposite {
+            syntax_ref: *syntax_ref,
+            wff_parameters: wff_parameters.iter()
+                .map(|param_wff| apply_unifier_wff(param_wff, unifier))
+                .collect(),
+            object_parameters: object_parameters.iter()
+                .map(|param_obj| apply_unifier_object(param_obj, unifier))
+                .collect()
+        }
+    }
+}
+
+pub fn has_metavariables(wff: &WellFormedFormula) -> bool {
+    match wff {
+        WellFormedFormula::Atomic(id) => is_metavariable(*id),
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref: _,
+            wff_parameters,
+            object_parameters
+        } => wff_parameters.iter().any(has_metavariables)
+            || object_parameters.iter().any(object_has_metavariables)
+    }
+}
+
+pub fn object_has_metavariables(obj: &Object) -> bool {
+    match obj {
+        Object::Atomic(id) | Object::SortedAtomic(_, id) => is_metavariable(*id),
+        Object::SyntaxComposite {
+            syntax_ref: _,
+            wff_parameters,
+            object_parameters
+        } => wff_parameters.iter().any(has_metavariables)
+            || object_parameters.iter().any(object_has_metavariables)
+    }
+}
+
+// Follows the assignments of a metavariable at the top of the formula
+fn walk_wff(wff: &WellFormedFormula, unifier: &Unifier) -> WellFormedFormula {
+    match wff {
+        WellFormedFormula::Atomic(id) if is_metavariable(*id) => match &unifier.wffs[id - METAVARIABLE_OFFSET] {
+            Some(assigned) => walk_wff(assigned, unifier),
+            None => wff.clone()
+        },
+        _ => wff.clone()
+    }
+}
+
+fn walk_object(obj: &Object, unifier: &Unifier) -> Object {
+    match obj {
+        Object::Atomic(id) | Object::SortedAtomic(_, id) if is_metavariable(*id) => {
+            match &unifier.objects[id - METAVARIABLE_OFFSET] {
+                Some(assigned) => walk_object(assigned, unifier),
+                None => obj.clone()
+            }
+        },
+        _ => obj.clone()
+    }
+}
+
+// Occurs check, 'variable' being either a wff or an object metavariable
+fn occurs_in_wff(variable: (bool, usize), wff: &WellFormedFormula, unifier: &Unifier) -> bool {
+    match walk_wff(wff, unifier) {
+        WellFormedFormula::Atomic(id) => variable == (true, id),
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref: _,
+            wff_parameters,
+            object_parameters
+        } => wff_parameters.iter().any(|param_wff| occurs_in_wff(variable, param_wff, unifier))
+            || object_parameters.iter().any(|param_obj| occurs_in_object(variable, param_obj, unifier))
+    }
+}
+
+fn occurs_in_object(variable: (bool, usize), obj: &Object, unifier: &Unifier) -> bool {
+    match walk_object(obj, unifier) {
+        Object::Atomic(id) | Object::SortedAtomic(_, id) => variable == (false, id),
+        Object::SyntaxComposite {
+            syntax_ref: _,
+            wff_parameters,
+            object_parameters
+        } => wff_parameters.iter().any(|param_wff| occurs_in_wff(variable, param_wff, unifier))
+            || object_parameters.iter().any(|param_obj| occurs_in_object(variable, param_obj, unifier))
+    }
+}
+
+// Assigns the metavariable 'obj' to 'value' if the sorts and the occurs check allow it
+fn bind_object(obj: &Object, value: &Object, unifier: &mut Unifier, syntaxes: &[Syntax]) -> Result<(), ()> {
+    let id = match obj {
+        Object::Atomic(id) => *id,
+        Object::SortedAtomic(sort, id) => match object_sort(value, syntaxes) == Some(*sort) {
+            true => *id,
+            false => return Err(())
+        },
+        Object::SyntaxComposite { .. } => return Err(())
+    };
+    if occurs_in_object((false, id), value, unifier) {
+        return Err(());
+    };
+    unifier.objects[id - METAVARIABLE_OFFSET] = Some(value.clone());
+    Ok(())
+}
+
+fn unify_parameters(
+    (wffs1, objects1): (&Vec<WellFormedFormula>, &Vec<Object>),
+    (wffs2, objects2): (&Vec<WellFormedFormula>, &Vec<Object>),
+    unifier: &mut Unifier, syntaxes: &Vec<Syntax>
+) -> Result<(), ()> {
+    if !same_arity((wffs1, objects1), (wffs2, objects2)) {
+        return Err(());
+    };
+    for (wff1, wff2) in wffs1.iter().zip(wffs2) {
+        unify_wffs(wff1, wff2, unifier, syntaxes)?;
+    };
+    for (obj1, obj2) in objects1.iter().zip(objects2) {
+        unify_objects(obj1, obj2, unifier, syntaxes)?;
+    };
+    Ok(())
+}
+
+// Extends the unifier into a most general unifier of both formulas, or fails.
+// On failure, the unifier may have been partially extended
+pub fn unify_wffs(
+    wff1: &WellFormedFormula, wff2: &WellFormedFormula, unifier: &mut Unifier, syntaxes: &Vec<Syntax>
+) -> Result<(), ()> {
+    match (walk_wff(wff1, unifier), walk_wff(wff2, unifier)) {
+        (WellFormedFormula::Atomic(id1), WellFormedFormula::Atomic(id2)) if id1 == id2 => Ok(()),
+        (WellFormedFormula::Atomic(id), other) | (other, WellFormedFormula::Atomic(id)) if is_metavariable(id) => {
+            if occurs_in_wff((true, id), &other, unifier) {
+                return Err(());
+            };
+            unifier.wffs[id - METAVARIABLE_OFFSET] = Some(other);
+            Ok(())
+        },
+        (
+            WellFormedFormula::SyntaxComposite {
+                syntax_ref: syntax1,
+                wff_parameters: wffs1,
+                object_parameters: objects1
+            },
+            WellFormedFormula::SyntaxComposite {
+                syntax_ref: syntax2,
+                wff_parameters: wffs2,
+                object_parameters: objects2
+            }
+        ) if syntax1 == syntax2 => unify_parameters((&wffs1, &objects1), (&wffs2, &objects2), unifier, syntaxes),
+        _ => Err(())
+    }
+}
+
+pub fn unify_objects(
+    obj1: &Object, obj2: &Object, unifier: &mut Unifier, syntaxes: &Vec<Syntax>
+) -> Result<(), ()> {
+    let (obj1, obj2) = (walk_object(obj1, unifier), walk_object(obj2, unifier));
+    match (&obj1, &obj2) {
+        (
+            Object::Atomic(id1) | Object::SortedAtomic(_, id1),
+            Object::Atomic(id2) | Object::SortedAtomic(_, id2)
+        ) if id1 == id2 => Ok(()),
+        (
+            Object::Atomic(id1) | Object::SortedAtomic(_, id1),
+            Object::Atomic(id2) | Object::SortedAtomic(_, id2)
+        ) if is_metavariable(*id1) && is_metavariable(*id2) => {
+            // The metavariable with the weakest sort constraint is the one assigned
+            bind_object(&obj1, &obj2, unifier, syntaxes)
+                .or_else(|()| bind_object(&obj2, &obj1, unifier, syntaxes))
+        },
+        (Object::Atomic(id) | Object::SortedAtomic(_, id), _) if is_metavariable(*id) => {
+            bind_object(&obj1, &obj2, unifier, syntaxes)
+        },
+        (_, Object::Atomic(id) | Object::SortedAtomic(_, id)) if is_metavariable(*id) => {
+            bind_object(&obj2, &obj1, unifier, syntaxes)
+        },
+        (
+            Object::SyntaxComposite {
+                syntax_ref: syntax1,
+                wff_parameters: wffs1,
+                object_parameters: objects1
+            },
+            Object::SyntaxComposite {
+                syntax_ref: syntax2,
+                wff_parameters: wffs2,
+                object_parameters: objects2
+            }
+        ) if syntax1 == syntax2 => unify_parameters((wffs1, objects1), (wffs2, objects2), unifier, syntaxes),
+        _ => Err(())
+    }
+}
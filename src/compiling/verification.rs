@@ -1,34 +1,14 @@
-use super::{Placeholder, WellFormedFormula, Object};
+use super::{WellFormedFormula, Object, Variable};
 
-
-fn equal_placeholders(p1: &Placeholder, p2: &Placeholder) -> bool {
-    match (p1, p2) {
-        (Placeholder::LiteralChar(c1), Placeholder::LiteralChar(c2)) => c1 == c2,
-        (Placeholder::WellFormedFormula(_), Placeholder::WellFormedFormula(_)) => true,
-        (Placeholder::Object(_), Placeholder::Object(_)) => true,
-        (Placeholder::Repetition, Placeholder::Repetition) => true,
-        _ => false
-    }
-}
-
-pub fn formula_is_contained(formula1: &Vec<Placeholder>, formula2: &Vec<Placeholder>) -> bool {
-    let max_len_to_check = match (formula1.len(), formula2.len()) {
-        (len1, _) if len1 == 0 => return true,
-        (len1, len2) if len1 > len2 => return false,
-        (len1, len2) => len2 - len1 + 1
-    };
-    for index in 0..max_len_to_check {
-        if formula2[index..].iter()
-            .zip(formula1)
-            .all(|(p1, p2)| equal_placeholders(p1, p2)) {
-            return true;
-        }
-    };
-    return false;
+// Whether two applications of the same syntax have as many parameters of each kind.
+// Variadic syntaxes can have a different number of parameters
+pub fn same_arity(
+    (wffs1, objects1): (&[WellFormedFormula], &[Object]), (wffs2, objects2): (&[WellFormedFormula], &[Object])
+) -> bool {
+    wffs1.len() == wffs2.len() && objects1.len() == objects2.len()
 }
 
-
-fn find_wff_substitutions<'a>(
+pub fn find_wff_substitutions<'a>(
     base: &WellFormedFormula, sub: &'a WellFormedFormula,
     wff_mapping: &mut Vec<Option<&'a WellFormedFormula>>, object_mapping: &mut Vec<Option<&'a Object>>
 ) -> Result<(), ()> {
@@ -52,6 +32,9 @@ fn find_wff_substitutions<'a>(
             }
         ) => {
             if sub_syn_ref != base_syn_ref { return Err(()); };
+            if !same_arity((base_wff_params, base_obj_params), (sub_wff_params, sub_obj_params)) {
+                return Err(());
+            };
             for (bwp, swp) in base_wff_params.into_iter().zip(sub_wff_params) {
                 match find_wff_substitutions(bwp, swp, wff_mapping, object_mapping) {
                     Ok(()) => (),
@@ -70,12 +53,13 @@ fn find_wff_substitutions<'a>(
     Ok(())
 }
 
-fn find_object_substitutions<'a>(
+pub fn find_object_substitutions<'a>(
     base: &Object, sub: &'a Object,
     wff_mapping: &mut Vec<Option<&'a WellFormedFormula>>, object_mapping: &mut Vec<Option<&'a Object>>
 ) -> Result<(), ()> {
     match (base, sub) {
-        (Object::Atomic(id), sub) => match object_mapping[*id] {
+        // Both formulas have been type-checked against the same syntaxes, so sorts always agree
+        (Object::Atomic(id) | Object::SortedAtomic(_, id), sub) => match object_mapping[*id] {
             None => object_mapping[*id] = Some(sub),
             Some(wff) => {
                 if sub != wff { return Err(()); }
@@ -94,6 +78,9 @@ fn find_object_substitutions<'a>(
             }
         ) => {
             if sub_syn_ref != base_syn_ref { return Err(()); };
+            if !same_arity((base_wff_params, base_obj_params), (sub_wff_params, sub_obj_params)) {
+                return Err(());
+            };
             for (bwp, swp) in base_wff_params.into_iter().zip(sub_wff_params) {
                 match find_wff_substitutions(bwp, swp, wff_mapping, object_mapping) {
                     Ok(()) => (),
@@ -112,21 +99,94 @@ fn find_object_substitutions<'a>(
     Ok(())
 }
 
-pub fn formula_is_substitution(
-    formula: &WellFormedFormula, used_hypotheses: &Vec<WellFormedFormula>,
+pub struct Substitution<'a> {
+    pub wffs: Vec<Option<&'a WellFormedFormula>>,
+    pub objects: Vec<Option<&'a Object>>
+}
+
+pub fn find_substitution<'a>(
+    formula: &'a WellFormedFormula, used_hypotheses: &'a Vec<WellFormedFormula>,
     theo_hypotheses: &Vec<WellFormedFormula>, theo_assertion: &WellFormedFormula,
     wff_count: usize, object_count: usize
-) -> bool {
+) -> Option<Substitution<'a>> {
     let mut wff_mapping = vec![None; wff_count];
     let mut object_mapping = vec![None; object_count];
     for (theo_hyp, used_hyp) in theo_hypotheses.into_iter().zip(used_hypotheses) {
         match find_wff_substitutions(theo_hyp, used_hyp, &mut wff_mapping, &mut object_mapping) {
             Ok(()) => (),
-            Err(()) => return false
+            Err(()) => return None
         };
     }
     match find_wff_substitutions(theo_assertion, formula, &mut wff_mapping, &mut object_mapping) {
-        Ok(()) => true,
-        Err(()) => false
+        Ok(()) => Some(Substitution { wffs: wff_mapping, objects: object_mapping }),
+        Err(()) => None
+    }
+}
+
+pub fn collect_wff_variables(wff: &WellFormedFormula, variables: &mut Vec<Variable>) {
+    match wff {
+        WellFormedFormula::Atomic(id) => variables.push(Variable::Wff(*id)),
+        WellFormedFormula::SyntaxComposite {
+            syntax_ref: _,
+            wff_parameters,
+            object_parameters
+        } => {
+            for param_wff in wff_parameters {
+                collect_wff_variables(param_wff, variables);
+            };
+            for param_obj in object_parameters {
+                collect_object_variables(param_obj, variables);
+            };
+        }
     }
 }
+
+pub fn collect_object_variables(obj: &Object, variables: &mut Vec<Variable>) {
+    match obj {
+        Object::Atomic(id) | Object::SortedAtomic(_, id) => variables.push(Variable::Object(*id)),
+        Object::SyntaxComposite {
+            syntax_ref: _,
+            wff_parameters,
+            object_parameters
+        } => {
+            for param_wff in wff_parameters {
+                collect_wff_variables(param_wff, variables);
+            };
+            for param_obj in object_parameters {
+                collect_object_variables(param_obj, variables);
+            };
+        }
+    }
+}
+
+fn substituted_variables(substitution: &Substitution, variable: Variable) -> Vec<Variable> {
+    let mut variables = Vec::new();
+    match variable {
+        Variable::Wff(id) => if let Some(Some(wff)) = substitution.wffs.get(id) {
+            collect_wff_variables(wff, &mut variables);
+        },
+        Variable::Object(id) => if let Some(Some(obj)) = substitution.objects.get(id) {
+            collect_object_variables(obj, &mut variables);
+        }
+    };
+    variables
+}
+
+// Checks the distinct variable conditions of the cited statement against the substitution.
+// On success, returns the pairs of variables that must in turn be distinct in the current theorem
+pub fn distinct_variables_requirements(
+    substitution: &Substitution, distinct_variables: &Vec<(Variable, Variable)>
+) -> Result<Vec<(Variable, Variable)>, ()> {
+    let mut requirements = Vec::new();
+    for (var1, var2) in distinct_variables {
+        let vars1 = substituted_variables(substitution, *var1);
+        let vars2 = substituted_variables(substitution, *var2);
+        for v1 in &vars1 {
+            for v2 in &vars2 {
+                if v1 == v2 { return Err(()); };
+                requirements.push((*v1.min(v2), *v1.max(v2)));
+            };
+        };
+    };
+    Ok(requirements)
+}
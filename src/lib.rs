@@ -0,0 +1,6 @@
+// The compiler, shared by the command line tool and the editor, which reports the warnings of the compiler
+#![allow(clippy::result_unit_err, reason = "the parsers report no details, the crate is only used by its binaries")]
+
+pub mod parsing;
+pub mod compiling;
+pub mod serializing;
@@ -1,12 +1,10 @@
-mod parsing;
-mod compiling;
-mod serializing;
+use math_lib::{parsing, compiling, serializing};
 
 mod commands;
 use commands::{
-    compile, add_syndef,
+    compile, add_sort, add_syndef,
     add_axiom, add_theo,
-    verify, open_editor
+    verify, infer, fixup, minimize, prove, saturate, taut, suggest, open_editor
 };
 
 mod flags;
@@ -20,6 +18,9 @@ cli!(
     const ARG_PARSER: ArgParser<"This is math_lib"> = [
         branch!(command as Command {
             "compile" |> Compile => {},
+            "add_so" |> AddSort => {
+                arg!(path as String)
+            },
             "add_sd" |> AddSyndef => {
                 arg!(path as String)
             },
@@ -32,6 +33,39 @@ cli!(
             "verify" |> Verify => {
                 arg!(path as String)
             },
+            "infer" |> Infer => {
+                arg!(path as String)
+            },
+            "fixup" |> Fixup => {
+                arg!(path as String)
+            },
+            "minimize" |> Minimize => {
+                arg!(path as String),
+                opt!(options as MinimizeOptions {
+                    cite: ["-c", "--cite"] -> Flag
+                })
+            },
+            "prove" |> Prove => {
+                arg!(path as String),
+                opt!(options as ProveOptions {
+                    depth: ["-d", "--depth"] -> (GrabLast<usize>),
+                    steps: ["-s", "--steps"] -> (GrabLast<usize>),
+                    time: ["-t", "--time"] -> (GrabLast<u64>)
+                })
+            },
+            "saturate" |> Saturate => {
+                arg!(path as String),
+                opt!(options as SaturateOptions {
+                    depth: ["-d", "--depth"] -> (GrabLast<usize>),
+                    max: ["-m", "--max"] -> (GrabLast<usize>)
+                })
+            },
+            "taut" |> Taut => {
+                arg!(formula as String)
+            },
+            "suggest" |> Suggest => {
+                arg!(formula as String)
+            },
             "edit" |> Edit => {},
             "flag" |> Flag => {
                 maybe!(flag_name as (Option<String>)),
@@ -59,10 +93,22 @@ fn main() {
     let dir = settings.lib_path.clone();
     let command_result = match command {
         Command::Compile() => compile(dir),
+        Command::AddSort(path) => add_sort(dir, path),
         Command::AddSyndef(path) => add_syndef(dir, path),
         Command::AddAxiom(path) => add_axiom(dir, path),
         Command::AddTheo(path) => add_theo(dir, path),
         Command::Verify(path) => verify(dir, path),
+        Command::Infer(path) => infer(dir, path),
+        Command::Fixup(path) => fixup(dir, path),
+        Command::Minimize(path, options) => minimize(dir, path, options.cite.state),
+        Command::Prove(path, options) => prove(
+            dir, path, options.depth.last.unwrap_or(5), options.steps.last.unwrap_or(100_000), options.time.last.unwrap_or(30)
+        ),
+        Command::Saturate(path, options) => saturate(
+            dir, path, options.depth.last.unwrap_or(2), options.max.last.unwrap_or(1000)
+        ),
+        Command::Taut(formula) => taut(dir, formula),
+        Command::Suggest(formula) => suggest(dir, formula),
         Command::Edit() => open_editor(dir),
         Command::Flag(name, value) => {
             handle_flag_command(name, value, &mut settings)
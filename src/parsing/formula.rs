@@ -1,6 +1,7 @@
 use super::{
     Formula, FormulaChar,  // Formula is an alias for 'Vec<FormulaChar>'
-    ProofLine  // ProofLine is an alias for '(u32, Vec<u32>, String, Formula)'
+    LineLabel, Tactic,
+    ProofLine  // ProofLine is an alias for '(LineLabel, Vec<LineLabel>, String, Vec<(char, Formula)>, Option<Formula>)'
 };
 
 pub fn parse_formula(fm: &str) -> Formula {
@@ -22,6 +23,21 @@ pub fn parse_formula(fm: &str) -> Formula {
     res
 }
 
+// The positions in the parsed formula of the characters written with a space after them
+pub fn parse_spacing(fm: &str) -> Vec<usize> {
+    let mut res = Vec::new();
+    let mut chars = fm.trim().chars().peekable();
+    let mut position = 0;
+    while let Some(c) = chars.next() {
+        if c == ' ' { continue; }
+        if chars.peek() == Some(&' ') {
+            res.push(position);
+        };
+        position += 1;
+    };
+    res
+}
+
 pub fn parse_named_formula(nfm: &str) -> Result<(String, Formula), ()> {
     let (name, formula) = match nfm.split_once(':') {
         Some((name, formula)) => (name, formula),
@@ -32,17 +48,76 @@ pub fn parse_named_formula(nfm: &str) -> Result<(String, Formula), ()> {
     Ok((name, formula))
 }
 
+pub fn parse_distinct_variables(line: &str) -> Result<Formula, ()> {
+    let mut res = Formula::new();
+    for c in parse_formula(line) {
+        match c {
+            FormulaChar::Char(',') => continue,
+            // Other characters may be variables of a user-declared sort, which is checked when compiling
+            FormulaChar::Wff(_) | FormulaChar::Object(_) | FormulaChar::Char(_) => res.push(c),
+            _ => return Err(())
+        };
+    };
+    Ok(res)
+}
+
+// A number, or a name made of ascii letters, digits and underscores which is not a number
+pub fn parse_line_label(label: &str) -> Result<LineLabel, ()> {
+    if let Ok(n) = label.parse::<usize>() {
+        return Ok(LineLabel::Number(n));
+    };
+    match !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        true => Ok(LineLabel::Name(label.to_owned())),
+        false => Err(())
+    }
+}
+
+// "<name>[𝜑:=¬𝜓, 𝜓:=𝜒]", the commas inside brackets belonging to the values
+pub fn parse_reference(reference: &str) -> Result<(String, Vec<(char, Formula)>), ()> {
+    let Some((name, annotation)) = reference.split_once('[') else {
+        return Ok((reference.to_owned(), Vec::new()));
+    };
+    let Some(annotation) = annotation.strip_suffix(']') else {
+        return Err(());
+    };
+    let mut entries = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in annotation.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                entries.push(&annotation[start..i]);
+                start = i + 1;
+            },
+            _ => ()
+        };
+    };
+    entries.push(&annotation[start..]);
+    let substitution = entries.into_iter()
+        .map(|entry| {
+            let (variable, value) = entry.split_once(":=").ok_or(())?;
+            let mut chars = variable.trim().chars();
+            match (chars.next(), chars.next(), value.trim().is_empty()) {
+                (Some(variable), None, false) => Ok((variable, parse_formula(value))),
+                _ => Err(())
+            }
+        }).collect::<Result<Vec<_>, ()>>()?;
+    Ok((name.trim_end().to_owned(), substitution))
+}
+
 pub fn parse_proof_line(prline: &str) -> Result<ProofLine, ()> {
     let mut split = prline.splitn(4, ';');
 
-    let Some(Ok(line_no)) = split.next().map(|s| s.trim().parse::<usize>()) else {
+    let Some(Ok(line_label)) = split.next().map(|s| parse_line_label(s.trim())) else {
         return Err(());
     };
     let used_hypots = match split.next() {
         Some(used_hypots) => {
             let splitted = used_hypots.split(',')
                 .filter(|s| s.trim().len() > 0)
-                .map(|s| s.trim().parse::<usize>())
+                .map(|s| parse_line_label(s.trim()))
                 .collect();
             match splitted {
                 Ok(vec) => vec,
@@ -51,11 +126,36 @@ pub fn parse_proof_line(prline: &str) -> Result<ProofLine, ()> {
         },
         None => return Err(())
     };
-    let Some(theorem_reference) = split.next().and_then(|s| Some(s.trim().to_string())) else {
+    let Some(Ok((theorem_reference, substitution))) = split.next().map(|s| parse_reference(s.trim())) else {
         return Err(());
     };
-    let Some(formula) = split.next().and_then(|fm| Some(parse_formula(fm))) else {
-        return Err(())
+    let formula = match split.next() {
+        Some(fm) if fm.trim() == "?" => None,
+        Some(fm) => Some(parse_formula(fm)),
+        None => return Err(())
+    };
+    Ok((line_label, used_hypots, theorem_reference, substitution, formula))
+}
+
+// "apply <name>", "exact [name]", "intro [name]", "repeat <tactic>" or "try <tactic>"
+pub fn parse_tactic(line: &str) -> Result<Tactic, ()> {
+    let line = line.trim();
+    let (keyword, argument) = match line.split_once(' ') {
+        Some((keyword, argument)) => (keyword, Some(argument.trim())),
+        None => (line, None)
+    };
+    let name = |argument: &str| match argument.chars().any(|c| c.is_whitespace()) {
+        true => Err(()),
+        false => Ok(argument.to_owned())
     };
-    Ok((line_no, used_hypots, theorem_reference, formula))
+    match (keyword, argument) {
+        ("apply", Some(argument)) => Ok(Tactic::Apply(name(argument)?)),
+        ("exact", Some(argument)) => Ok(Tactic::Exact(Some(name(argument)?))),
+        ("exact", None) => Ok(Tactic::Exact(None)),
+        ("intro", Some(argument)) => Ok(Tactic::Intro(Some(name(argument)?))),
+        ("intro", None) => Ok(Tactic::Intro(None)),
+        ("repeat", Some(argument)) => Ok(Tactic::Repeat(Box::new(parse_tactic(argument)?))),
+        ("try", Some(argument)) => Ok(Tactic::Try(Box::new(parse_tactic(argument)?))),
+        _ => Err(())
+    }
 }
@@ -1,16 +1,18 @@
 mod types;
 pub use types::{
-    MathFile, DefinitionType,
+    MathFile, DefinitionType, Associativity, SyntaxRole,
     FileType, FileSection,
-    FormulaChar, Formula, ProofLine
+    FormulaChar, Formula, LineLabel, ProofLine, Tactic
 };
 
 mod formula;
 use formula::{
-    parse_formula,
     parse_named_formula,
-    parse_proof_line
+    parse_distinct_variables,
+    parse_spacing,
+    parse_tactic
 };
+pub use formula::{parse_formula, parse_proof_line};
 
 mod read_file;
 pub use read_file::{parse_file, ParseError};
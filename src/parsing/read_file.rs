@@ -1,34 +1,56 @@
 use super::{
-    MathFile, DefinitionType,
+    MathFile, DefinitionType, Associativity, SyntaxRole,
     FileType, FileSection,
-    parse_formula, parse_named_formula, parse_proof_line
+    Formula, FormulaChar,
+    parse_formula, parse_named_formula, parse_spacing,
+    parse_distinct_variables, parse_proof_line, parse_tactic
 };
 
 #[derive(Debug)]
 pub enum ParseError {
     InvalidHeader(String),
+    InvalidAttribute(String),
     EmptyFile,
     InvalidSection(String, FileType),
     MultilineSection(FileSection),
     EmptySection(FileSection),
     InvalidSectionOrder,
     InvalidNamedHypothesis,
+    InvalidDistinctVariables,
+    InvalidSortVariable(String),
     InvalidProofLine,
+    InvalidTactic(String),
     InvalidName(String)
 }
 
+// Parses headers of the form '## Syntax Definition (<type>, <attribute>, ...) <name>'
+fn parse_syntax_header(header: &str) -> Option<(FileType, String, Vec<&str>)> {
+    let (attributes, name) = header.strip_prefix("## Syntax Definition (")?
+        .rsplit_once(") ")?;
+    let mut attributes = attributes.split(',').map(|s| s.trim());
+    let definition_type = match attributes.next()? {
+        "formula" => DefinitionType::Formula,
+        "object" => DefinitionType::Object,
+        sort_name if sort_name.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            DefinitionType::Sort(sort_name.to_owned())
+        },
+        _ => return None
+    };
+    let file_type = FileType::SyntaxDefinition(definition_type);
+    Some((file_type, name.to_owned(), attributes.collect()))
+}
+
 pub fn parse_file(content: String) -> Result<MathFile, ParseError> {
     let mut lines = content.lines();
-    let (file_type, name) = match lines.next() {
+    let (file_type, name, attributes) = match lines.next() {
         Some(header) => match header.split(' ').collect::<Vec<&str>>()[..] {
-            ["##", "Syntax", "Definition", "(formula)", name] => {
-                (FileType::FormulaSyntaxDefinition, name.to_owned())
-            },
-            ["##", "Syntax", "Definition", "(object)", name] => {
-                (FileType::ObjectSyntaxDefinition, name.to_owned())
+            ["##", "Syntax", "Definition", ..] => match parse_syntax_header(header) {
+                Some(parsed_header) => parsed_header,
+                None => return Err(ParseError::InvalidHeader(header.to_owned()))
             },
-            ["##", "Axiom", name] => (FileType::Axiom, name.to_owned()),
-            ["##", "Theorem", name] => (FileType::Theorem, name.to_owned()),
+            ["##", "Sort", name] => (FileType::Sort, name.to_owned(), vec![]),
+            ["##", "Axiom", name] => (FileType::Axiom, name.to_owned(), vec![]),
+            ["##", "Theorem", name] => (FileType::Theorem, name.to_owned(), vec![]),
             _ => return Err(ParseError::InvalidHeader(header.to_owned()))
         },
         None => return Err(ParseError::EmptyFile)
@@ -36,6 +58,35 @@ pub fn parse_file(content: String) -> Result<MathFile, ParseError> {
     if !name.chars().all(|c| c.is_ascii_alphanumeric()) {
         return Err(ParseError::InvalidName(name));
     };
+    let mut bound_variables = Formula::new();
+    let mut precedence = None;
+    let mut associativity = Associativity::NonAssociative;
+    let mut role = None;
+    for attribute in attributes {
+        match attribute {
+            "left" => { associativity = Associativity::Left; continue; },
+            "right" => { associativity = Associativity::Right; continue; },
+            "equivalence" => { role = Some(SyntaxRole::Equivalence); continue; },
+            "equality" => { role = Some(SyntaxRole::Equality); continue; },
+            _ => ()
+        };
+        match attribute.split_once(' ') {
+            Some(("precedence", level)) => match level.trim().parse::<usize>() {
+                Ok(level) => precedence = Some(level),
+                Err(_) => return Err(ParseError::InvalidAttribute(attribute.to_owned()))
+            },
+            Some(("binds", variables)) => {
+                for c in parse_formula(variables) {
+                    match c {
+                        // A sort variable is a literal character until the sorts are known
+                        FormulaChar::Object(_) | FormulaChar::Char(_) => bound_variables.push(c),
+                        _ => return Err(ParseError::InvalidAttribute(attribute.to_owned()))
+                    };
+                };
+            },
+            _ => return Err(ParseError::InvalidAttribute(attribute.to_owned()))
+        };
+    };
     let mut file_contents = Vec::new();
     let mut section_contents = Vec::new();
     let mut section = FileSection::None;
@@ -49,13 +100,23 @@ pub fn parse_file(content: String) -> Result<MathFile, ParseError> {
                 };
                 section_contents = Vec::new();
                 section = match line {
+                    "# Variables" => FileSection::VariableList,
                     "# Syntax" => FileSection::Syntax,
                     "# Definition" => FileSection::Definition,
                     "# Hypothesis" => FileSection::HypothesisList,
                     "# Hypotheses" => FileSection::HypothesisList,
                     "# Assertion" => FileSection::AssertionList,
                     "# Assertions" => FileSection::AssertionList,
+                    "# Distinct" => FileSection::DistinctList,
                     "# Proof" => FileSection::Proof,
+                    "# Tactics" if matches!(file_type, FileType::Theorem) => FileSection::Tactics,
+                    line if line.starts_with("# Lemma ") && matches!(file_type, FileType::Theorem) => {
+                        let lemma_name = line["# Lemma ".len()..].trim().to_owned();
+                        if lemma_name.is_empty() || !lemma_name.chars().all(|c| c.is_ascii_alphanumeric()) {
+                            return Err(ParseError::InvalidName(lemma_name));
+                        };
+                        FileSection::Lemma(lemma_name)
+                    },
                     line => {
                         return Err(ParseError::InvalidSection(line.to_owned(), file_type))
                     }
@@ -70,52 +131,25 @@ pub fn parse_file(content: String) -> Result<MathFile, ParseError> {
     };
 
     match file_type {
-        FileType::FormulaSyntaxDefinition => {
-            match &file_contents[..] {
-                [
-                    (FileSection::Syntax, syntax_lines)
-                ] => {
-                    match syntax_lines.len() {
-                        0 => return Err(ParseError::EmptySection(FileSection::Syntax)),
-                        1 => (),
-                        _ => return Err(ParseError::MultilineSection(FileSection::Syntax))
-                    };
-                    return Ok(
-                        MathFile::SyntaxDefinition {
-                            name,
-                            definition_type: DefinitionType::Formula,
-                            syntax: parse_formula(syntax_lines[0]),
-                            definition: None
-                        }
-                    );
-                },
-                [
-                    (FileSection::Syntax, syntax_lines),
-                    (FileSection::Definition, definition_lines)
-                ] => {
-                    match syntax_lines.len() {
-                        0 => return Err(ParseError::EmptySection(FileSection::Syntax)),
-                        1 => (),
-                        _ => return Err(ParseError::MultilineSection(FileSection::Syntax))
-                    };
-                    match definition_lines.len() {
-                        0 => return Err(ParseError::EmptySection(FileSection::Definition)),
-                        1 => (),
-                        _ => return Err(ParseError::MultilineSection(FileSection::Definition))
-                    };
-                    return Ok(
-                        MathFile::SyntaxDefinition {
-                            name,
-                            definition_type: DefinitionType::Formula,
-                            syntax: parse_formula(syntax_lines[0]),
-                            definition: Some(parse_formula(definition_lines[0]))
-                        }
-                    );
-                },
+        FileType::Sort => {
+            let variable_lines = match &file_contents[..] {
+                [(FileSection::VariableList, variable_lines)] => variable_lines,
                 _ => return Err(ParseError::InvalidSectionOrder)
             };
+            let mut variables = Vec::new();
+            for word in variable_lines.iter().flat_map(|line| line.split_whitespace()) {
+                let mut chars = word.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if !c.is_control() => variables.push(c),
+                    _ => return Err(ParseError::InvalidSortVariable(word.to_owned()))
+                };
+            };
+            if variables.is_empty() {
+                return Err(ParseError::EmptySection(FileSection::VariableList));
+            };
+            return Ok( MathFile::Sort { name, variables } );
         },
-        FileType::ObjectSyntaxDefinition => {
+        FileType::SyntaxDefinition(definition_type) => {
             match &file_contents[..] {
                 [
                     (FileSection::Syntax, syntax_lines)
@@ -128,8 +162,13 @@ pub fn parse_file(content: String) -> Result<MathFile, ParseError> {
                     return Ok(
                         MathFile::SyntaxDefinition {
                             name,
-                            definition_type: DefinitionType::Object,
+                            definition_type,
                             syntax: parse_formula(syntax_lines[0]),
+                            spacing: parse_spacing(syntax_lines[0]),
+                            bound_variables,
+                            precedence,
+                            associativity,
+                            role,
                             definition: None
                         }
                     );
@@ -151,8 +190,13 @@ pub fn parse_file(content: String) -> Result<MathFile, ParseError> {
                     return Ok(
                         MathFile::SyntaxDefinition {
                             name,
-                            definition_type: DefinitionType::Object,
+                            definition_type,
                             syntax: parse_formula(syntax_lines[0]),
+                            spacing: parse_spacing(syntax_lines[0]),
+                            bound_variables,
+                            precedence,
+                            associativity,
+                            role,
                             definition: Some(parse_formula(definition_lines[0]))
                         }
                     );
@@ -161,50 +205,123 @@ pub fn parse_file(content: String) -> Result<MathFile, ParseError> {
             };
         },
         FileType::Axiom => {
-            match &file_contents[..] {
+            let no_distinct = Vec::new();
+            let (hypots, asserts, distinct) = match &file_contents[..] {
                 [
                     (FileSection::HypothesisList, hypots),
                     (FileSection::AssertionList, asserts)
-                ] => {
-                    let hypotheses = hypots.into_iter()
-                        .map(|fm| parse_formula(fm))
-                        .collect();
-                    let assertions = asserts.into_iter()
-                        .map(|fm| parse_formula(fm))
-                        .collect();
-                    return Ok( MathFile::Axiom { name, hypotheses, assertions } );
-                },
-                _ => return Err(ParseError::InvalidSectionOrder)
-            }
-        },
-        FileType::Theorem => {
-            match &file_contents[..] {
+                ] => (hypots, asserts, &no_distinct),
                 [
                     (FileSection::HypothesisList, hypots),
                     (FileSection::AssertionList, asserts),
-                    (FileSection::Proof, proof_lines)
-                ] => {
-                    let try_hypotheses = hypots.into_iter()
-                        .map(|fm| parse_named_formula(fm))
-                        .collect();
-                    let hypotheses = match try_hypotheses {
-                        Ok(hypotheses) => hypotheses,
-                        Err(()) => return Err(ParseError::InvalidNamedHypothesis)
-                    };
-                    let assertions = asserts.into_iter()
-                        .map(|fm| parse_formula(fm))
-                        .collect();
-                    let try_proof = proof_lines.into_iter()
-                        .map(|prline| parse_proof_line(prline))
-                        .collect();
-                    let proof = match try_proof {
-                        Ok(proof) => proof,
-                        Err(()) => return Err(ParseError::InvalidProofLine)
-                    };
-                    return Ok( MathFile::Theorem { name, hypotheses, assertions, proof } );
-                },
+                    (FileSection::DistinctList, distinct)
+                ] => (hypots, asserts, distinct),
                 _ => return Err(ParseError::InvalidSectionOrder)
-            }
+            };
+            let hypotheses = hypots.into_iter()
+                .map(|fm| parse_formula(fm))
+                .collect();
+            let assertions = asserts.into_iter()
+                .map(|fm| parse_formula(fm))
+                .collect();
+            let try_distinct = distinct.iter()
+                .map(|line| parse_distinct_variables(line))
+                .collect();
+            let distinct_variables = match try_distinct {
+                Ok(distinct_variables) => distinct_variables,
+                Err(()) => return Err(ParseError::InvalidDistinctVariables)
+            };
+            return Ok( MathFile::Axiom { name, hypotheses, assertions, distinct_variables } );
+        },
+        FileType::Theorem => {
+            // The sections of the theorem come first, then the ones of each lemma
+            let mut blocks = vec![(name, Vec::new())];
+            for (section, lines) in file_contents {
+                match (section, blocks.last_mut()) {
+                    (FileSection::Lemma(lemma_name), _) => blocks.push((lemma_name, Vec::new())),
+                    (section, Some((_, sections))) => sections.push((section, lines)),
+                    (_, None) => return Err(ParseError::InvalidSectionOrder)
+                };
+            };
+            let mut blocks = blocks.into_iter();
+            let Some((name, sections)) = blocks.next() else {
+                return Err(ParseError::InvalidSectionOrder);
+            };
+            let lemmas = blocks
+                .map(|(lemma_name, lemma_sections)| parse_theorem_sections(lemma_name, lemma_sections, Vec::new()))
+                .collect::<Result<Vec<_>, _>>()?;
+            return parse_theorem_sections(name, sections, lemmas);
         }
     };
 }
+
+fn parse_theorem_sections(
+    name: String, sections: Vec<(FileSection, Vec<&str>)>, lemmas: Vec<MathFile>
+) -> Result<MathFile, ParseError> {
+    let no_distinct = Vec::new();
+    // Without a proof section the proof is empty, 'prove' can then search for one.
+    // A tactics section replaces the proof section
+    let no_proof = Vec::new();
+    let (hypots, asserts, distinct, proof_lines, tactic_lines) = match &sections[..] {
+        [
+            (FileSection::HypothesisList, hypots),
+            (FileSection::AssertionList, asserts)
+        ] => (hypots, asserts, &no_distinct, &no_proof, &no_proof),
+        [
+            (FileSection::HypothesisList, hypots),
+            (FileSection::AssertionList, asserts),
+            (FileSection::DistinctList, distinct)
+        ] => (hypots, asserts, distinct, &no_proof, &no_proof),
+        [
+            (FileSection::HypothesisList, hypots),
+            (FileSection::AssertionList, asserts),
+            (FileSection::Proof, proof_lines)
+        ] => (hypots, asserts, &no_distinct, proof_lines, &no_proof),
+        [
+            (FileSection::HypothesisList, hypots),
+            (FileSection::AssertionList, asserts),
+            (FileSection::DistinctList, distinct),
+            (FileSection::Proof, proof_lines)
+        ] => (hypots, asserts, distinct, proof_lines, &no_proof),
+        [
+            (FileSection::HypothesisList, hypots),
+            (FileSection::AssertionList, asserts),
+            (FileSection::Tactics, tactic_lines)
+        ] => (hypots, asserts, &no_distinct, &no_proof, tactic_lines),
+        [
+            (FileSection::HypothesisList, hypots),
+            (FileSection::AssertionList, asserts),
+            (FileSection::DistinctList, distinct),
+            (FileSection::Tactics, tactic_lines)
+        ] => (hypots, asserts, distinct, &no_proof, tactic_lines),
+        _ => return Err(ParseError::InvalidSectionOrder)
+    };
+    let try_hypotheses = hypots.into_iter()
+        .map(|fm| parse_named_formula(fm))
+        .collect();
+    let hypotheses = match try_hypotheses {
+        Ok(hypotheses) => hypotheses,
+        Err(()) => return Err(ParseError::InvalidNamedHypothesis)
+    };
+    let assertions = asserts.into_iter()
+        .map(|fm| parse_formula(fm))
+        .collect();
+    let try_distinct = distinct.iter()
+        .map(|line| parse_distinct_variables(line))
+        .collect();
+    let distinct_variables = match try_distinct {
+        Ok(distinct_variables) => distinct_variables,
+        Err(()) => return Err(ParseError::InvalidDistinctVariables)
+    };
+    let try_proof = proof_lines.into_iter()
+        .map(|prline| parse_proof_line(prline))
+        .collect();
+    let proof = match try_proof {
+        Ok(proof) => proof,
+        Err(()) => return Err(ParseError::InvalidProofLine)
+    };
+    let tactics = tactic_lines.iter()
+        .map(|line| parse_tactic(line).map_err(|()| ParseError::InvalidTactic(line.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok( MathFile::Theorem { name, hypotheses, assertions, distinct_variables, proof, tactics, lemmas } )
+}
@@ -1,7 +1,7 @@
 // --------------------------------------------- //
 // Types for mathematical formula representation //
 // --------------------------------------------- //
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum FormulaChar {
     Char(char),
     Wff(usize),
@@ -9,27 +9,49 @@ pub enum FormulaChar {
     RepetitionChar
 }
 
+// A proof line is designated by its number or by a name
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineLabel {
+    Number(usize),
+    Name(String)
+}
+
 pub type Formula = Vec<FormulaChar>;
-pub type ProofLine = (usize, Vec<usize>, String, Formula);
+// (label, used lines, reference, substituted variables and their values, formula or 'None' for "?")
+pub type ProofLine = (LineLabel, Vec<LineLabel>, String, Vec<(char, Formula)>, Option<Formula>);
+
+// A line of a "# Tactics" section, acting on the current goal
+#[derive(Debug, Clone)]
+pub enum Tactic {
+    Apply(String),  // The statement or hypothesis, its hypotheses becoming the next goals
+    Exact(Option<String>),  // Like 'Apply' without new goals, or with an assumption or a hypothesis if unnamed
+    Intro(Option<String>),  // Assumes the antecedent of the implication, which can be named
+    Repeat(Box<Tactic>),  // Until it fails
+    Try(Box<Tactic>)  // Ignoring a failure
+}
 
 // --------------------------------- //
 // Types for file type determination //
 // --------------------------------- //
 #[derive(Debug)]
 pub enum FileType {
-    FormulaSyntaxDefinition,
-    ObjectSyntaxDefinition,
+    Sort,
+    SyntaxDefinition(DefinitionType),
     Axiom,
     Theorem
 }
 
 #[derive(Debug)]
 pub enum FileSection {
+    VariableList,
     Syntax,
     Definition,
     HypothesisList,
     AssertionList,
+    DistinctList,
     Proof,
+    Tactics,
+    Lemma(String),  // Starts the sections of a lemma
     None
 }
 
@@ -40,26 +62,54 @@ pub enum FileSection {
 #[derive(Debug)]
 pub enum DefinitionType {
     Formula,
-    Object
+    Object,
+    Sort(String)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Associativity {
+    Left,
+    Right,
+    NonAssociative
+}
+
+// Syntaxes the compiler relies on to check definitions
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SyntaxRole {
+    Equivalence,
+    Equality
 }
 
 #[derive(Debug)]
 pub enum MathFile {
+    Sort {
+        name: String,
+        variables: Vec<char>
+    },
     SyntaxDefinition {
         name: String,
         definition_type: DefinitionType,
         syntax: Formula,
+        spacing: Vec<usize>,
+        bound_variables: Formula,
+        precedence: Option<usize>,
+        associativity: Associativity,
+        role: Option<SyntaxRole>,
         definition: Option<Formula>
     },
     Axiom {
         name: String,
         hypotheses: Vec<Formula>,
-        assertions: Vec<Formula>
+        assertions: Vec<Formula>,
+        distinct_variables: Vec<Formula>
     },
     Theorem {
         name: String,
         hypotheses: Vec<(String, Formula)>,
         assertions: Vec<Formula>,
-        proof: Vec<ProofLine>
+        distinct_variables: Vec<Formula>,
+        proof: Vec<ProofLine>,
+        tactics: Vec<Tactic>,  // Expanded into the proof when compiling, only given without a proof
+        lemmas: Vec<MathFile>  // Theorems only usable in this file, which have no lemmas of their own
     }
 }
\ No newline at end of file
@@ -4,9 +4,10 @@ use super::{
     wff_to_rpn, rpn_to_wff
 };
 use crate::compiling::{
-    Syntax, SyntaxType, Placeholder,
+    Sort, Syntax, SyntaxType, Placeholder, Associativity, SyntaxRole,
     Definition, Axiom,
-    Theorem, LogicStep, Reference
+    Theorem, LogicStep, StepKind, Reference, DeductionRecipe,
+    Variable, VariableNames
 };
 
 impl BinaryConvert<0> for u8 {
@@ -53,6 +54,23 @@ impl BinaryConvert<0> for String {
     }
 }
 
+impl BinaryConvert<0> for VariableNames {
+    fn to_binary(self) -> Vec<u8> {
+        let mut res = Vec::new();
+        res.append(&mut self.wffs.into_iter().collect::<String>().to_binary());
+        res.append(&mut self.objects.into_iter().collect::<String>().to_binary());
+        res
+    }
+    fn from_binary<I: Iterator<Item = u8>>(source: &mut I) -> Option<Self> {
+        let wffs = String::from_binary(source)?.chars().collect();
+        let objects = String::from_binary(source)?.chars().collect();
+        Some(VariableNames { wffs, objects })
+    }
+    fn from_binary_syntaxes<I: Iterator<Item = u8>>(source: &mut I, _syntaxes: &Vec<Syntax>) -> Option<Self> {
+        Self::from_binary(source)
+    }
+}
+
 impl<T, const N: usize> BinaryConvert<N> for Vec<T>
 where T: Vectorizable<BinaryForm = [u8; N]> {
     fn to_binary(self) -> Vec<u8> {
@@ -121,33 +139,103 @@ where T: Vectorizable<BinaryForm = [u8; N]> {
     }
 }
 
+impl BinaryConvert<0> for Sort {
+    fn to_binary(self) -> Vec<u8> {
+        let Sort { name, variables } = self;
+        let mut res = Vec::new();
+        res.append(&mut name.to_binary());
+        res.append(&mut variables.into_iter().collect::<String>().to_binary());
+        res
+    }
+    fn from_binary<I: Iterator<Item = u8>>(source: &mut I) -> Option<Self> {
+        let name = String::from_binary(source)?;
+        let variables = String::from_binary(source)?.chars().collect();
+        Some(Sort { name, variables })
+    }
+    fn from_binary_syntaxes<I: Iterator<Item = u8>>(source: &mut I, _syntaxes: &Vec<Syntax>) -> Option<Self> {
+        Self::from_binary(source)
+    }
+}
+
 impl BinaryConvert<0> for Syntax {
     fn to_binary(self) -> Vec<u8> {
         let Syntax {
             syntax_type,
             formula,
+            spaced_after,
+            bound_objects,
+            precedence,
+            associativity,
+            role,
             distinct_wff_count,
             distinct_object_count
         } = self;
         let mut res = match syntax_type {
             SyntaxType::Formula => vec![0x00],
-            SyntaxType::Object => vec![0x01]
+            SyntaxType::Object => vec![0x01],
+            SyntaxType::Sort(sort) => {
+                let mut res = vec![0x02];
+                res.append(&mut sort.to_binary());
+                res
+            }
         };
         res.append(&mut distinct_wff_count.to_binary());
         res.append(&mut distinct_object_count.to_binary());
         res.append(&mut formula.to_binary());
+        res.append(&mut spaced_after.to_binary());
+        res.append(&mut bound_objects.to_binary());
+        match precedence {
+            None => res.push(0x00),
+            Some(level) => {
+                res.push(0x01);
+                res.append(&mut level.to_binary());
+            }
+        };
+        res.push(match associativity {
+            Associativity::NonAssociative => 0x00,
+            Associativity::Left => 0x01,
+            Associativity::Right => 0x02
+        });
+        res.push(match role {
+            None => 0x00,
+            Some(SyntaxRole::Equivalence) => 0x01,
+            Some(SyntaxRole::Equality) => 0x02
+        });
         res
     }
     fn from_binary<I: Iterator<Item = u8>>(source: &mut I) -> Option<Self> {
         let syntax_type = match source.next() {
             Some(0x00) => SyntaxType::Formula,
             Some(0x01) => SyntaxType::Object,
+            Some(0x02) => SyntaxType::Sort(usize::from_binary(source)?),
             _ => return None
         };
         let distinct_wff_count = usize::from_binary(source)?;
         let distinct_object_count = usize::from_binary(source)?;
         let formula = Vec::<Placeholder>::from_binary(source)?;
-        Some(Syntax { syntax_type, formula, distinct_wff_count, distinct_object_count })
+        let spaced_after = Vec::<usize>::from_binary(source)?;
+        let bound_objects = Vec::<usize>::from_binary(source)?;
+        let precedence = match source.next()? {
+            0x00 => None,
+            0x01 => Some(usize::from_binary(source)?),
+            _ => return None
+        };
+        let associativity = match source.next()? {
+            0x00 => Associativity::NonAssociative,
+            0x01 => Associativity::Left,
+            0x02 => Associativity::Right,
+            _ => return None
+        };
+        let role = match source.next()? {
+            0x00 => None,
+            0x01 => Some(SyntaxRole::Equivalence),
+            0x02 => Some(SyntaxRole::Equality),
+            _ => return None
+        };
+        Some(Syntax {
+            syntax_type, formula, spaced_after, bound_objects, precedence, associativity, role,
+            distinct_wff_count, distinct_object_count
+        })
     }
     fn from_binary_syntaxes<I: Iterator<Item = u8>>(source: &mut I, _syntaxes: &Vec<Syntax>) -> Option<Self> {
         Self::from_binary(source)
@@ -160,7 +248,8 @@ impl BinaryConvert<0> for Definition {
             name,
             definition,
             distinct_wff_count,
-            distinct_object_count
+            distinct_object_count,
+            variable_names
         } = self;
         let definition_rpn = wff_to_rpn(definition);
         let mut res = Vec::new();
@@ -168,6 +257,7 @@ impl BinaryConvert<0> for Definition {
         res.append(&mut distinct_wff_count.to_binary());
         res.append(&mut distinct_object_count.to_binary());
         res.append(&mut definition_rpn.to_binary());
+        res.append(&mut variable_names.to_binary());
         res
     }
     fn from_binary<I>(_source: &mut I) -> Option<Self> { None }
@@ -176,8 +266,9 @@ impl BinaryConvert<0> for Definition {
         let distinct_wff_count = usize::from_binary(source)?;
         let distinct_object_count = usize::from_binary(source)?;
         let definition_rpn = Vec::<RpnBlock>::from_binary(source)?;
+        let variable_names = VariableNames::from_binary(source)?;
         let definition = rpn_to_wff(definition_rpn, syntaxes)?;
-        Some(Definition { name, definition, distinct_wff_count, distinct_object_count })
+        Some(Definition { name, definition, distinct_wff_count, distinct_object_count, variable_names })
     }
 }
 
@@ -187,8 +278,10 @@ impl BinaryConvert<0> for Axiom {
             name,
             hypotheses,
             assertions,
+            distinct_variables,
             distinct_wff_count,
-            distinct_object_count
+            distinct_object_count,
+            variable_names
         } = self;
         let hypots_rpn = hypotheses.into_iter().map(wff_to_rpn).collect::<Vec<_>>();
         let asserts_rpn = assertions.into_iter().map(wff_to_rpn).collect::<Vec<_>>();
@@ -198,6 +291,8 @@ impl BinaryConvert<0> for Axiom {
         res.append(&mut distinct_object_count.to_binary());
         res.append(&mut hypots_rpn.to_binary());
         res.append(&mut asserts_rpn.to_binary());
+        res.append(&mut distinct_variables.to_binary());
+        res.append(&mut variable_names.to_binary());
         res
     }
     fn from_binary<I>(_source: &mut I) -> Option<Self> { None }
@@ -207,13 +302,17 @@ impl BinaryConvert<0> for Axiom {
         let distinct_object_count = usize::from_binary(source)?;
         let hypots_rpn = Vec::<Vec<RpnBlock>>::from_binary(source)?;
         let asserts_rpn = Vec::<Vec<RpnBlock>>::from_binary(source)?;
+        let distinct_variables = Vec::<(Variable, Variable)>::from_binary(source)?;
+        let variable_names = VariableNames::from_binary(source)?;
         let hypotheses = hypots_rpn.into_iter()
             .map(|hyp| rpn_to_wff(hyp, syntaxes))
             .collect::<Option<Vec<_>>>()?;
         let assertions = asserts_rpn.into_iter()
             .map(|hyp| rpn_to_wff(hyp, syntaxes))
             .collect::<Option<Vec<_>>>()?;
-        Some(Axiom { name, hypotheses, assertions, distinct_wff_count, distinct_object_count })
+        Some(Axiom {
+            name, hypotheses, assertions, distinct_variables, distinct_wff_count, distinct_object_count, variable_names
+        })
     }
 }
 
@@ -223,22 +322,27 @@ impl BinaryConvert<0> for Theorem {
             name,
             hypotheses,
             assertions,
+            distinct_variables,
             proof,
             distinct_wff_count,
-            distinct_object_count
+            distinct_object_count,
+            variable_names
         } = self;
         let hypots_rpn = hypotheses.into_iter().map(wff_to_rpn).collect::<Vec<_>>();
         let asserts_rpn = assertions.into_iter().map(wff_to_rpn).collect::<Vec<_>>();
-        let (pr_hyps, pr_refs, pr_formulas) = transpose_steps(proof);
+        let (pr_hyps, pr_refs, pr_formulas, pr_kinds) = transpose_steps(proof);
         let mut res = Vec::new();
         res.append(&mut name.to_binary());
         res.append(&mut distinct_wff_count.to_binary());
         res.append(&mut distinct_object_count.to_binary());
         res.append(&mut hypots_rpn.to_binary());
         res.append(&mut asserts_rpn.to_binary());
+        res.append(&mut distinct_variables.to_binary());
         res.append(&mut pr_hyps.to_binary());
         res.append(&mut pr_refs.to_binary());
         res.append(&mut pr_formulas.to_binary());
+        res.append(&mut pr_kinds.to_binary());
+        res.append(&mut variable_names.to_binary());
         res
     }
     fn from_binary<I>(_source: &mut I) -> Option<Self> { None }
@@ -248,48 +352,87 @@ impl BinaryConvert<0> for Theorem {
         let distinct_object_count = usize::from_binary(source)?;
         let hypots_rpn = Vec::<Vec<RpnBlock>>::from_binary(source)?;
         let asserts_rpn = Vec::<Vec<RpnBlock>>::from_binary(source)?;
+        let distinct_variables = Vec::<(Variable, Variable)>::from_binary(source)?;
         let pr_hyps = Vec::<Vec<usize>>::from_binary(source)?;
         let pr_refs = Vec::<Reference>::from_binary(source)?;
         let pr_formulas = Vec::<Vec<RpnBlock>>::from_binary(source)?;
+        let pr_kinds = Vec::<StepKind>::from_binary(source)?;
+        let variable_names = VariableNames::from_binary(source)?;
         let hypotheses = hypots_rpn.into_iter()
             .map(|hyp| rpn_to_wff(hyp, syntaxes))
             .collect::<Option<Vec<_>>>()?;
         let assertions = asserts_rpn.into_iter()
             .map(|hyp| rpn_to_wff(hyp, syntaxes))
             .collect::<Option<Vec<_>>>()?;
-        let proof = transpose_3vec(pr_hyps, pr_refs, pr_formulas, syntaxes)?;
-        Some(Theorem { name, hypotheses, assertions, proof, distinct_wff_count, distinct_object_count })
+        let proof = transpose_4vec(pr_hyps, pr_refs, pr_formulas, pr_kinds, syntaxes)?;
+        Some(Theorem {
+            name, hypotheses, assertions, distinct_variables, proof,
+            distinct_wff_count, distinct_object_count, variable_names
+        })
     }
 }
 
-fn transpose_steps(proof: Vec<LogicStep>) -> (Vec<Vec<usize>>, Vec<Reference>, Vec<Vec<RpnBlock>>) {
+impl BinaryConvert<0> for DeductionRecipe {
+    fn to_binary(self) -> Vec<u8> {
+        let DeductionRecipe {
+            implication,
+            modus_ponens,
+            weakening,
+            distribution
+        } = self;
+        let mut res = Vec::new();
+        res.append(&mut implication.to_binary());
+        res.append(&mut vec![modus_ponens, weakening, distribution].to_binary());
+        res
+    }
+    fn from_binary<I: Iterator<Item = u8>>(source: &mut I) -> Option<Self> {
+        let implication = usize::from_binary(source)?;
+        let [modus_ponens, weakening, distribution]: [Reference; 3] = Vec::<Reference>::from_binary(source)?
+            .try_into()
+            .ok()?;
+        Some(DeductionRecipe { implication, modus_ponens, weakening, distribution })
+    }
+    fn from_binary_syntaxes<I: Iterator<Item = u8>>(source: &mut I, _syntaxes: &Vec<Syntax>) -> Option<Self> {
+        Self::from_binary(source)
+    }
+}
+
+// The proof steps split by field: (used hypotheses, references, formulas, kinds)
+type ProofColumns = (Vec<Vec<usize>>, Vec<Reference>, Vec<Vec<RpnBlock>>, Vec<StepKind>);
+
+fn transpose_steps(proof: Vec<LogicStep>) -> ProofColumns {
     let mut hyps = Vec::new();
     let mut refs = Vec::new();
     let mut formulas = Vec::new();
+    let mut kinds = Vec::new();
     for step in proof {
         let LogicStep {
             used_hypotheses,
             theorem_ref,
+            kind,
             resulting_formula
         } = step;
         hyps.push(used_hypotheses);
         refs.push(theorem_ref);
-        formulas.push(wff_to_rpn(resulting_formula))
+        formulas.push(wff_to_rpn(resulting_formula));
+        kinds.push(kind);
     };
-    (hyps, refs, formulas)
+    (hyps, refs, formulas, kinds)
 }
 
-fn transpose_3vec(
-    hyps: Vec<Vec<usize>>, refs: Vec<Reference>, formulas: Vec<Vec<RpnBlock>>, syntaxes: &Vec<Syntax>
+fn transpose_4vec(
+    hyps: Vec<Vec<usize>>, refs: Vec<Reference>, formulas: Vec<Vec<RpnBlock>>, kinds: Vec<StepKind>,
+    syntaxes: &Vec<Syntax>
 ) -> Option<Vec<LogicStep>> {
     let formulas = formulas.into_iter()
         .map(|formula_rpn| rpn_to_wff(formula_rpn, syntaxes))
         .collect::<Option<Vec<_>>>()?;
-    let res = hyps.into_iter().zip(refs).zip(formulas)
-        .map(|((used_hypotheses, theorem_ref), resulting_formula)|
+    let res = hyps.into_iter().zip(refs).zip(formulas).zip(kinds)
+        .map(|(((used_hypotheses, theorem_ref), resulting_formula), kind)|
             LogicStep {
                 used_hypotheses,
                 theorem_ref,
+                kind,
                 resulting_formula
             } 
         ).collect::<Vec<_>>();
@@ -3,22 +3,29 @@ use std::{
     fs::File, 
     io::{self, Write, BufReader, Read}};
 use crate::compiling::{
-    Syntax, Definition,
+    Sort, Syntax, Definition,
     Axiom, Theorem,
-    Reference,
+    Reference, DeductionRecipe,
     Library
 };
 use super::BinaryConvert;
 
 pub fn write_lib(path: String, lib: Library) -> io::Result<()> {
     let Library {
+        sorts,
         syntaxes,
         definitions,
         axioms,
-        theorems
+        theorems,
+        lemmas,
+        deduction
     } = lib;
     let mut file = File::create(path)?;
     let mut data = Vec::new();
+    for sort in sorts {
+        data.push(0xf5);
+        data.append(&mut sort.to_binary());
+    };
     for syntax in syntaxes {
         data.push(0xf0);
         data.append(&mut syntax.to_binary());
@@ -35,6 +42,14 @@ pub fn write_lib(path: String, lib: Library) -> io::Result<()> {
         data.push(0xf3);
         data.append(&mut theorem.to_binary());
     };
+    for lemma in lemmas {
+        data.push(0xf6);
+        data.append(&mut lemma.to_binary());
+    };
+    if let Some(deduction) = deduction {
+        data.push(0xf7);
+        data.append(&mut deduction.to_binary());
+    };
     data.push(0xf4);  // EOF
     file.write_all(&data)?;
     Ok(())
@@ -46,14 +61,23 @@ pub fn read_file(path: String) -> io::Result<(Library, HashMap<String, Reference
         .take_while(|item| item.is_ok())
         .filter_map(|item| item.ok());
     let mut lib = Library {
+        sorts: Vec::new(),
         syntaxes: Vec::new(),
         definitions: Vec::new(),
         axioms: Vec::new(),
-        theorems: Vec::new()
+        theorems: Vec::new(),
+        lemmas: Vec::new(),
+        deduction: None
     };
     let mut references = HashMap::new();
     loop {
         match source.next() {
+            Some(0xf5) => {
+                let Some(sort) = Sort::from_binary(&mut source) else {
+                    continue;
+                };
+                lib.sorts.push(sort)
+            },
             Some(0xf0) => {
                 let Some(syntax) = Syntax::from_binary(&mut source) else {
                     continue;
@@ -84,6 +108,15 @@ pub fn read_file(path: String) -> io::Result<(Library, HashMap<String, Reference
                 references.insert(theorem.name.clone(), theo_ref);
                 lib.theorems.push(theorem)
             },
+            Some(0xf6) => {
+                let Some(lemma) = Theorem::from_binary_syntaxes(&mut source, &lib.syntaxes) else {
+                    continue;
+                };
+                lib.lemmas.push(lemma)
+            },
+            Some(0xf7) => {
+                lib.deduction = DeductionRecipe::from_binary(&mut source);
+            },
             Some(0xf4) => {
                 break;
             },
@@ -1,13 +1,15 @@
 use crate::compiling::{
-    Syntax,
-    WellFormedFormula, Object
+    Syntax, Placeholder,
+    WellFormedFormula, Object,
+    repetition_group
 };
 
 pub enum RpnBlock {
     WffAtomic(usize),
-    WffComposite(usize),
+    WffComposite(usize, usize),  // (syntax, number of parameters)
     ObjectAtomic(usize),
-    ObjectComposite(usize)
+    ObjectComposite(usize, usize),  // (syntax, number of parameters)
+    SortedAtomic(usize, usize)
 }
 
 pub fn wff_to_rpn(wff: WellFormedFormula) -> Vec<RpnBlock> {
@@ -24,13 +26,14 @@ fn __wff_to_rpn(wff: WellFormedFormula, res: &mut Vec<RpnBlock>) {
             wff_parameters,
             object_parameters
         } => {
+            let parameter_count = wff_parameters.len() + object_parameters.len();
             for param_wff in wff_parameters {
                 __wff_to_rpn(param_wff, res);
             };
             for param_obj in object_parameters {
                 __obj_to_rpn(param_obj, res);
             };
-            res.push(RpnBlock::WffComposite(syntax_ref))
+            res.push(RpnBlock::WffComposite(syntax_ref, parameter_count))
         }
     }
 }
@@ -38,22 +41,36 @@ fn __wff_to_rpn(wff: WellFormedFormula, res: &mut Vec<RpnBlock>) {
 fn __obj_to_rpn(obj: Object, res: &mut Vec<RpnBlock>) {
     match obj {
         Object::Atomic(id) => res.push(RpnBlock::ObjectAtomic(id)),
+        Object::SortedAtomic(sort, id) => res.push(RpnBlock::SortedAtomic(sort, id)),
         Object::SyntaxComposite {
             syntax_ref,
             wff_parameters,
             object_parameters
         } => {
+            let parameter_count = wff_parameters.len() + object_parameters.len();
             for param_wff in wff_parameters {
                 __wff_to_rpn(param_wff, res);
             };
             for param_obj in object_parameters {
                 __obj_to_rpn(param_obj, res);
             };
-            res.push(RpnBlock::ObjectComposite(syntax_ref))
+            res.push(RpnBlock::ObjectComposite(syntax_ref, parameter_count))
         }
     }
 }
 
+// Number of wff and object parameters of an application of the syntax,
+//  the elements of a repetition taking the place of its placeholder
+fn parameter_counts(syntax: &Syntax, parameter_count: usize) -> Option<(usize, usize)> {
+    let (wffc, objc) = (syntax.distinct_wff_count, syntax.distinct_object_count);
+    match repetition_group(&syntax.formula).map(|(repeated, _)| &syntax.formula[repeated]) {
+        Some(Placeholder::WellFormedFormula(_)) if parameter_count >= wffc + objc => Some((parameter_count - objc, objc)),
+        Some(Placeholder::Object(_) | Placeholder::Sorted(_, _)) if parameter_count >= wffc + objc => Some((wffc, parameter_count - wffc)),
+        Some(_) => None,
+        None => Some((wffc, objc))
+    }
+}
+
 pub fn rpn_to_wff(rpn: Vec<RpnBlock>, syntaxes: &Vec<Syntax>) -> Option<WellFormedFormula> {
     let mut wff_stack = Vec::new();
     let mut obj_stack = Vec::new();
@@ -61,13 +78,9 @@ pub fn rpn_to_wff(rpn: Vec<RpnBlock>, syntaxes: &Vec<Syntax>) -> Option<WellForm
         match block {
             RpnBlock::WffAtomic(id) => wff_stack.push(WellFormedFormula::Atomic(id)),
             RpnBlock::ObjectAtomic(id) => obj_stack.push(Object::Atomic(id)),
-            RpnBlock::WffComposite(syntax_ref) => {
-                let &Syntax {
-                    syntax_type: _,
-                    formula: _,
-                    distinct_wff_count: wffc,
-                    distinct_object_count: objc
-                } = syntaxes.get(syntax_ref)?;
+            RpnBlock::SortedAtomic(sort, id) => obj_stack.push(Object::SortedAtomic(sort, id)),
+            RpnBlock::WffComposite(syntax_ref, parameter_count) => {
+                let (wffc, objc) = parameter_counts(syntaxes.get(syntax_ref)?, parameter_count)?;
                 let new_wff_stack_len = wff_stack.len().checked_sub(wffc)?;
                 let new_obj_stack_len = obj_stack.len().checked_sub(objc)?;
                 let wff = WellFormedFormula::SyntaxComposite {
@@ -77,13 +90,8 @@ pub fn rpn_to_wff(rpn: Vec<RpnBlock>, syntaxes: &Vec<Syntax>) -> Option<WellForm
                 };
                 wff_stack.push(wff);
             },
-            RpnBlock::ObjectComposite(syntax_ref) => {
-                let &Syntax {
-                    syntax_type: _,
-                    formula: _,
-                    distinct_wff_count: wffc,
-                    distinct_object_count: objc
-                } = syntaxes.get(syntax_ref)?;
+            RpnBlock::ObjectComposite(syntax_ref, parameter_count) => {
+                let (wffc, objc) = parameter_counts(syntaxes.get(syntax_ref)?, parameter_count)?;
                 let new_wff_stack_len = wff_stack.len().checked_sub(wffc)?;
                 let new_obj_stack_len = obj_stack.len().checked_sub(objc)?;
                 let obj = Object::SyntaxComposite {
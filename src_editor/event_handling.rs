@@ -37,10 +37,13 @@ impl Default for EditorData {
             dimensions: (80, 24),
             indent: 4,
             lib_data: LibraryData {
+                sorts: Vec::new(),
                 syntaxes: Vec::new(),
                 definitions: Vec::new(),
                 axioms: Vec::new(),
-                theorems: Vec::new()
+                theorems: Vec::new(),
+                lemmas: Vec::new(),
+                has_deduction: false
             },
             references: HashMap::new()
         }
@@ -26,7 +26,17 @@ pub fn get_menu() -> Result<MenuGraphics, ()> {
     for line in contents.lines() {
         let menu_line = match line {
             "" => continue,
+            "# Sorts" => {
+                MenuLine::SubDirectory("sorts".to_string(), false)
+            },
             "# Syntax Definitions" => {
+                // If the last object is a file, encode the fact it is last
+                let mut last_item = result_lines.pop().ok_or(())?;
+                if let MenuLine::File(name, path, _, _) = last_item {
+                    last_item = MenuLine::File(name, path, false, true);
+                };
+                result_lines.push(last_item);
+
                 MenuLine::SubDirectory("syntax_defintions".to_string(), false)
             },
             "# Axioms" => {
@@ -1,7 +1,7 @@
 use super::{
-    Syntax, SyntaxType, Placeholder,
+    Sort, Syntax, SyntaxType, Placeholder, Associativity, SyntaxRole,
     Definition, Axiom, Theorem,
-    LogicStep, Reference,
+    LogicStep, StepKind, Reference, Variable,
     
     RpnBlock, rpn_to_wff,
 
@@ -46,6 +46,13 @@ impl FromBinary<0> for String {
     }
 }
 
+// Statements end with the characters of their variables, which the editor doesn't need
+fn skip_variable_names<I: Iterator<Item = u8>>(source: &mut I) -> Option<()> {
+    String::from_binary(source)?;
+    String::from_binary(source)?;
+    Some(())
+}
+
 impl<T, const N: usize> FromBinary<N> for Vec<T>
 where T: Vectorizable<BinaryForm = [u8; N]> {
     fn from_binary<I: Iterator<Item = u8>>(source: &mut I) -> Option<Self> {
@@ -95,17 +102,51 @@ where T: Vectorizable<BinaryForm = [u8; N]> {
     }
 }
 
+impl FromBinary<0> for Sort {
+    fn from_binary<I: Iterator<Item = u8>>(source: &mut I) -> Option<Self> {
+        let name = String::from_binary(source)?;
+        let variables = String::from_binary(source)?.chars().collect();
+        Some(Sort { name, variables })
+    }
+    fn from_binary_syntaxes<I: Iterator<Item = u8>>(source: &mut I, _syntaxes: &Vec<Syntax>) -> Option<Self> {
+        Self::from_binary(source)
+    }
+}
+
 impl FromBinary<0> for Syntax {
     fn from_binary<I: Iterator<Item = u8>>(source: &mut I) -> Option<Self> {
         let syntax_type = match source.next() {
             Some(0x00) => SyntaxType::Formula,
             Some(0x01) => SyntaxType::Object,
+            Some(0x02) => SyntaxType::Sort(usize::from_binary(source)?),
             _ => return None
         };
         let distinct_wff_count = usize::from_binary(source)?;
         let distinct_object_count = usize::from_binary(source)?;
         let formula = Vec::<Placeholder>::from_binary(source)?;
-        Some(Syntax { syntax_type, formula, distinct_wff_count, distinct_object_count })
+        let spaced_after = Vec::<usize>::from_binary(source)?;
+        let bound_objects = Vec::<usize>::from_binary(source)?;
+        let precedence = match source.next()? {
+            0x00 => None,
+            0x01 => Some(usize::from_binary(source)?),
+            _ => return None
+        };
+        let associativity = match source.next()? {
+            0x00 => Associativity::NonAssociative,
+            0x01 => Associativity::Left,
+            0x02 => Associativity::Right,
+            _ => return None
+        };
+        let role = match source.next()? {
+            0x00 => None,
+            0x01 => Some(SyntaxRole::Equivalence),
+            0x02 => Some(SyntaxRole::Equality),
+            _ => return None
+        };
+        Some(Syntax {
+            syntax_type, formula, spaced_after, bound_objects, precedence, associativity, role,
+            distinct_wff_count, distinct_object_count
+        })
     }
     fn from_binary_syntaxes<I: Iterator<Item = u8>>(source: &mut I, _syntaxes: &Vec<Syntax>) -> Option<Self> {
         Self::from_binary(source)
@@ -119,6 +160,7 @@ impl FromBinary<0> for Definition {
         let distinct_wff_count = usize::from_binary(source)?;
         let distinct_object_count = usize::from_binary(source)?;
         let definition_rpn = Vec::<RpnBlock>::from_binary(source)?;
+        skip_variable_names(source)?;
         let definition = rpn_to_wff(definition_rpn, syntaxes)?;
         Some(Definition { name, definition, distinct_wff_count, distinct_object_count })
     }
@@ -132,13 +174,17 @@ impl FromBinary<0> for Axiom {
         let distinct_object_count = usize::from_binary(source)?;
         let hypots_rpn = Vec::<Vec<RpnBlock>>::from_binary(source)?;
         let asserts_rpn = Vec::<Vec<RpnBlock>>::from_binary(source)?;
+        let distinct_variables = Vec::<(Variable, Variable)>::from_binary(source)?;
+        skip_variable_names(source)?;
         let hypotheses = hypots_rpn.into_iter()
             .map(|hyp| rpn_to_wff(hyp, syntaxes))
             .collect::<Option<Vec<_>>>()?;
         let assertions = asserts_rpn.into_iter()
             .map(|hyp| rpn_to_wff(hyp, syntaxes))
             .collect::<Option<Vec<_>>>()?;
-        Some(Axiom { name, hypotheses, assertions, distinct_wff_count, distinct_object_count })
+        Some(Axiom {
+            name, hypotheses, assertions, distinct_variables, distinct_wff_count, distinct_object_count
+        })
     }
 }
 
@@ -150,31 +196,39 @@ impl FromBinary<0> for Theorem {
         let distinct_object_count = usize::from_binary(source)?;
         let hypots_rpn = Vec::<Vec<RpnBlock>>::from_binary(source)?;
         let asserts_rpn = Vec::<Vec<RpnBlock>>::from_binary(source)?;
+        let distinct_variables = Vec::<(Variable, Variable)>::from_binary(source)?;
         let pr_hyps = Vec::<Vec<usize>>::from_binary(source)?;
         let pr_refs = Vec::<Reference>::from_binary(source)?;
         let pr_formulas = Vec::<Vec<RpnBlock>>::from_binary(source)?;
+        let pr_kinds = Vec::<StepKind>::from_binary(source)?;
+        skip_variable_names(source)?;
         let hypotheses = hypots_rpn.into_iter()
             .map(|hyp| rpn_to_wff(hyp, syntaxes))
             .collect::<Option<Vec<_>>>()?;
         let assertions = asserts_rpn.into_iter()
             .map(|hyp| rpn_to_wff(hyp, syntaxes))
             .collect::<Option<Vec<_>>>()?;
-        let proof = transpose_3vec(pr_hyps, pr_refs, pr_formulas, syntaxes)?;
-        Some(Theorem { name, hypotheses, assertions, proof, distinct_wff_count, distinct_object_count })
+        let proof = transpose_4vec(pr_hyps, pr_refs, pr_formulas, pr_kinds, syntaxes)?;
+        Some(Theorem {
+            name, hypotheses, assertions, distinct_variables, proof,
+            distinct_wff_count, distinct_object_count
+        })
     }
 }
 
-fn transpose_3vec(
-    hyps: Vec<Vec<usize>>, refs: Vec<Reference>, formulas: Vec<Vec<RpnBlock>>, syntaxes: &Vec<Syntax>
+fn transpose_4vec(
+    hyps: Vec<Vec<usize>>, refs: Vec<Reference>, formulas: Vec<Vec<RpnBlock>>, kinds: Vec<StepKind>,
+    syntaxes: &Vec<Syntax>
 ) -> Option<Vec<LogicStep>> {
     let formulas = formulas.into_iter()
         .map(|formula_rpn| rpn_to_wff(formula_rpn, syntaxes))
         .collect::<Option<Vec<_>>>()?;
-    let res = hyps.into_iter().zip(refs).zip(formulas)
-        .map(|((used_hypotheses, theorem_ref), resulting_formula)|
+    let res = hyps.into_iter().zip(refs).zip(formulas).zip(kinds)
+        .map(|(((used_hypotheses, theorem_ref), resulting_formula), kind)|
             LogicStep {
                 used_hypotheses,
                 theorem_ref,
+                kind,
                 resulting_formula
             } 
         ).collect::<Vec<_>>();
@@ -1,14 +1,15 @@
 mod types;
 pub use types::{
-    Syntax, SyntaxType, Placeholder,
-    WellFormedFormula, Object,
+    Sort, Syntax, SyntaxType, Placeholder, Associativity, SyntaxRole,
+    WellFormedFormula, Object, Variable,
     Definition, Axiom, Theorem,
-    LogicStep, Reference,
+    LogicStep, StepKind, Reference,
     LibraryData
 };
 
 mod rpn;
 use rpn::{rpn_to_wff, RpnBlock};
+pub use rpn::repetition_group;
 
 mod binary_conversion;
 use binary_conversion::FromBinary;
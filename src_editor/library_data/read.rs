@@ -4,7 +4,7 @@ use std::{
     collections::HashMap
 };
 use super::{
-    Syntax, Definition, Axiom, Theorem,
+    Sort, Syntax, Definition, Axiom, Theorem,
     Reference, LibraryData,
 
     FromBinary
@@ -16,14 +16,23 @@ pub fn read_lib_data() -> io::Result<(LibraryData, HashMap<String, Reference>)>
         .take_while(|item| item.is_ok())
         .filter_map(|item| item.ok());
     let mut lib = LibraryData {
+        sorts: Vec::new(),
         syntaxes: Vec::new(),
         definitions: Vec::new(),
         axioms: Vec::new(),
-        theorems: Vec::new()
+        theorems: Vec::new(),
+        lemmas: Vec::new(),
+        has_deduction: false
     };
     let mut references = HashMap::new();
     loop {
         match source.next() {
+            Some(0xf5) => {
+                let Some(sort) = Sort::from_binary(&mut source) else {
+                    continue;
+                };
+                lib.sorts.push(sort)
+            },
             Some(0xf0) => {
                 let Some(syntax) = Syntax::from_binary(&mut source) else {
                     continue;
@@ -54,6 +63,18 @@ pub fn read_lib_data() -> io::Result<(LibraryData, HashMap<String, Reference>)>
                 references.insert(theorem.name.clone(), theo_ref);
                 lib.theorems.push(theorem)
             },
+            Some(0xf6) => {
+                let Some(lemma) = Theorem::from_binary_syntaxes(&mut source, &lib.syntaxes) else {
+                    continue;
+                };
+                lib.lemmas.push(lemma)
+            },
+            Some(0xf7) => {
+                // Only the presence of the recipe matters to the editor, its implication syntax and statements are skipped
+                let implication = usize::from_binary(&mut source);
+                let statements = Vec::<Reference>::from_binary(&mut source);
+                lib.has_deduction = implication.is_some() && statements.is_some_and(|statements| statements.len() == 3);
+            },
             Some(0xf4) => {
                 break;
             },
@@ -1,13 +1,35 @@
 use super::{
-    Syntax,
+    Syntax, Placeholder,
     WellFormedFormula, Object
 };
 
+// The repetition '…' repeats the last placeholder before it, along with the characters between them,
+//  returns the positions of the repeated placeholder and of the repetition
+pub fn repetition_group(formula: &[Placeholder]) -> Option<(usize, usize)> {
+    let repetition = formula.iter().position(|pl| matches!(pl, Placeholder::Repetition))?;
+    let repeated = formula[..repetition].iter()
+        .rposition(|pl| !matches!(pl, Placeholder::LiteralChar(_)))?;
+    Some((repeated, repetition))
+}
+
 pub enum RpnBlock {
     WffAtomic(usize),
-    WffComposite(usize),
+    WffComposite(usize, usize),  // (syntax, number of parameters)
     ObjectAtomic(usize),
-    ObjectComposite(usize)
+    ObjectComposite(usize, usize),  // (syntax, number of parameters)
+    SortedAtomic(usize, usize)
+}
+
+// Number of wff and object parameters of an application of the syntax,
+//  the elements of a repetition taking the place of its placeholder
+fn parameter_counts(syntax: &Syntax, parameter_count: usize) -> Option<(usize, usize)> {
+    let (wffc, objc) = (syntax.distinct_wff_count, syntax.distinct_object_count);
+    match repetition_group(&syntax.formula).map(|(repeated, _)| &syntax.formula[repeated]) {
+        Some(Placeholder::WellFormedFormula(_)) if parameter_count >= wffc + objc => Some((parameter_count - objc, objc)),
+        Some(Placeholder::Object(_) | Placeholder::Sorted(_, _)) if parameter_count >= wffc + objc => Some((wffc, parameter_count - wffc)),
+        Some(_) => None,
+        None => Some((wffc, objc))
+    }
 }
 
 pub fn rpn_to_wff(rpn: Vec<RpnBlock>, syntaxes: &Vec<Syntax>) -> Option<WellFormedFormula> {
@@ -17,13 +39,9 @@ pub fn rpn_to_wff(rpn: Vec<RpnBlock>, syntaxes: &Vec<Syntax>) -> Option<WellForm
         match block {
             RpnBlock::WffAtomic(id) => wff_stack.push(WellFormedFormula::Atomic(id)),
             RpnBlock::ObjectAtomic(id) => obj_stack.push(Object::Atomic(id)),
-            RpnBlock::WffComposite(syntax_ref) => {
-                let &Syntax {
-                    syntax_type: _,
-                    formula: _,
-                    distinct_wff_count: wffc,
-                    distinct_object_count: objc
-                } = syntaxes.get(syntax_ref)?;
+            RpnBlock::SortedAtomic(sort, id) => obj_stack.push(Object::SortedAtomic(sort, id)),
+            RpnBlock::WffComposite(syntax_ref, parameter_count) => {
+                let (wffc, objc) = parameter_counts(syntaxes.get(syntax_ref)?, parameter_count)?;
                 let new_wff_stack_len = wff_stack.len().checked_sub(wffc)?;
                 let new_obj_stack_len = obj_stack.len().checked_sub(objc)?;
                 let wff = WellFormedFormula::SyntaxComposite {
@@ -33,13 +51,8 @@ pub fn rpn_to_wff(rpn: Vec<RpnBlock>, syntaxes: &Vec<Syntax>) -> Option<WellForm
                 };
                 wff_stack.push(wff);
             },
-            RpnBlock::ObjectComposite(syntax_ref) => {
-                let &Syntax {
-                    syntax_type: _,
-                    formula: _,
-                    distinct_wff_count: wffc,
-                    distinct_object_count: objc
-                } = syntaxes.get(syntax_ref)?;
+            RpnBlock::ObjectComposite(syntax_ref, parameter_count) => {
+                let (wffc, objc) = parameter_counts(syntaxes.get(syntax_ref)?, parameter_count)?;
                 let new_wff_stack_len = wff_stack.len().checked_sub(wffc)?;
                 let new_obj_stack_len = obj_stack.len().checked_sub(objc)?;
                 let obj = Object::SyntaxComposite {
@@ -1,21 +1,47 @@
+#[derive(Debug)]
+pub struct Sort {
+    pub name: String,
+    pub variables: Vec<char>
+}
+
 #[derive(Debug)]
 pub enum Placeholder {
     LiteralChar(char),
     WellFormedFormula(usize),
     Object(usize),
+    Sorted(usize, usize),
     Repetition
 }
 
 #[derive(Debug)]
 pub enum SyntaxType {
     Formula,
-    Object
+    Object,
+    Sort(usize)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Associativity {
+    Left,
+    Right,
+    NonAssociative
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SyntaxRole {
+    Equivalence,
+    Equality
 }
 
 #[derive(Debug)]
 pub struct Syntax {
     pub syntax_type: SyntaxType,
     pub formula: Vec<Placeholder>,
+    pub spaced_after: Vec<usize>,  // Positions in 'formula' written with a space after them
+    pub bound_objects: Vec<usize>,
+    pub precedence: Option<usize>,
+    pub associativity: Associativity,
+    pub role: Option<SyntaxRole>,
     pub distinct_wff_count: usize,
     pub distinct_object_count: usize
 }
@@ -33,6 +59,7 @@ pub enum WellFormedFormula {
 #[derive(Debug, PartialEq, Clone)]
 pub enum Object {
     Atomic(usize),
+    SortedAtomic(usize, usize),
     SyntaxComposite {
         syntax_ref: usize,
         wff_parameters: Vec<WellFormedFormula>,
@@ -40,6 +67,12 @@ pub enum Object {
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Variable {
+    Wff(usize),
+    Object(usize)
+}
+
 #[derive(Debug)]
 pub struct Definition {
     pub name: String,
@@ -53,22 +86,35 @@ pub struct Axiom {
     pub name: String,
     pub hypotheses: Vec<WellFormedFormula>,
     pub assertions: Vec<WellFormedFormula>,
+    pub distinct_variables: Vec<(Variable, Variable)>,
     pub distinct_wff_count: usize,
     pub distinct_object_count: usize
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::enum_variant_names, reason = "each variant is a kind of reference")]
 pub enum Reference {
     HypothesisReference(usize),
     DefinitionReference(usize),
     AxiomReference(usize, usize),
-    TheoremReference(usize, usize)
+    TheoremReference(usize, usize),
+    LineReference(usize),  // A previous line of the proof
+    LemmaReference(usize, usize)  // A lemma of the theorem's file
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StepKind {
+    Assertion,  // The reference is applied as a whole
+    Unfold,  // One definiendum of the definition is replaced by its definiens
+    Fold,  // One definiens of the definition is replaced by its definiendum
+    Rewrite  // One side of the equivalence or equality is replaced by the other
 }
 
 #[derive(Debug)]
 pub struct LogicStep {
     pub used_hypotheses: Vec<usize>,
     pub theorem_ref: Reference,
+    pub kind: StepKind,
     pub resulting_formula: WellFormedFormula
 }
 
@@ -77,6 +123,7 @@ pub struct Theorem {
     pub name: String,
     pub hypotheses: Vec<WellFormedFormula>,
     pub assertions: Vec<WellFormedFormula>,
+    pub distinct_variables: Vec<(Variable, Variable)>,
     pub proof: Vec<LogicStep>,
     pub distinct_wff_count: usize,
     pub distinct_object_count: usize
@@ -84,8 +131,11 @@ pub struct Theorem {
 
 #[derive(Debug)]
 pub struct LibraryData {
+    pub sorts: Vec<Sort>,
     pub syntaxes: Vec<Syntax>,
     pub definitions: Vec<Definition>,
     pub axioms: Vec<Axiom>,
-    pub theorems: Vec<Theorem>
+    pub theorems: Vec<Theorem>,
+    pub lemmas: Vec<Theorem>,  // Private to the theorems whose files state them, they have no name reference
+    pub has_deduction: bool  // Whether the library designates the statements of the assume/discharge blocks
 }
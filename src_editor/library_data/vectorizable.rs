@@ -1,5 +1,5 @@
 use super::{
-    Placeholder, Reference,
+    Placeholder, Reference, StepKind, Variable,
     RpnBlock
 };
 
@@ -23,45 +23,53 @@ impl Vectorizable for usize {
 }
 
 impl Vectorizable for Placeholder {
-    type BinaryForm = [u8; 5];
+    type BinaryForm = [u8; 9];
     fn from_binary_in_vec(source: Self::BinaryForm) -> Option<Self> {
         let first_byte = source[0];
-        let mut bytes = [0; 4];
+        let mut bytes = [0; 8];
         for (i, data) in source.into_iter().skip(1).enumerate() {
             bytes[i] = data
         };
+        let num = u64::from_le_bytes(bytes);
+        let id = (num & 0xFF_FF_FF_FF) as u32;
+        let sub_id = (num >> 32) as usize;
         let res = match first_byte {
-            0x00 => Placeholder::LiteralChar(char::from_u32(u32::from_le_bytes(bytes))?),
-            0x01 => Placeholder::WellFormedFormula(u32::from_le_bytes(bytes) as usize),
-            0x02 => Placeholder::Object(u32::from_le_bytes(bytes) as usize),
+            0x00 => Placeholder::LiteralChar(char::from_u32(id)?),
+            0x01 => Placeholder::WellFormedFormula(id as usize),
+            0x02 => Placeholder::Object(id as usize),
             0x03 => Placeholder::Repetition,
+            0x04 => Placeholder::Sorted(sub_id, id as usize),
             _ => return None
         };
         Some(res)
     }
-    const TERMINATOR: Self::BinaryForm = [0xfe; 5];
-    const TERMINATOR2: Self::BinaryForm = [0xff; 5];
+    const TERMINATOR: Self::BinaryForm = [0xfe; 9];
+    const TERMINATOR2: Self::BinaryForm = [0xff; 9];
 }
 
 impl Vectorizable for RpnBlock {
-    type BinaryForm = [u8; 5];
+    type BinaryForm = [u8; 9];
     fn from_binary_in_vec(source: Self::BinaryForm) -> Option<Self> {
         let first_byte = source[0];
-        let mut bytes = [0; 4];
+        let mut bytes = [0; 8];
         for (i, data) in source.into_iter().skip(1).enumerate() {
             bytes[i] = data
         };
+        let num = u64::from_le_bytes(bytes);
+        let id = (num & 0xFF_FF_FF_FF) as usize;
+        let sub_id = (num >> 32) as usize;
         let res = match first_byte {
-            0x00 => RpnBlock::WffAtomic(u32::from_le_bytes(bytes) as usize),
-            0x01 => RpnBlock::WffComposite(u32::from_le_bytes(bytes) as usize),
-            0x02 => RpnBlock::ObjectAtomic(u32::from_le_bytes(bytes) as usize),
-            0x03 => RpnBlock::ObjectComposite(u32::from_le_bytes(bytes) as usize),
+            0x00 => RpnBlock::WffAtomic(id),
+            0x01 => RpnBlock::WffComposite(id, sub_id),
+            0x02 => RpnBlock::ObjectAtomic(id),
+            0x03 => RpnBlock::ObjectComposite(id, sub_id),
+            0x04 => RpnBlock::SortedAtomic(sub_id, id),
             _ => return None
         };
         Some(res)
     }
-    const TERMINATOR: Self::BinaryForm = [0xfe; 5];
-    const TERMINATOR2: Self::BinaryForm = [0xff; 5];
+    const TERMINATOR: Self::BinaryForm = [0xfe; 9];
+    const TERMINATOR2: Self::BinaryForm = [0xff; 9];
 }
 
 impl Vectorizable for Reference {
@@ -80,6 +88,8 @@ impl Vectorizable for Reference {
             0x01 => Reference::DefinitionReference(id),
             0x02 => Reference::AxiomReference(id, sub_id),
             0x03 => Reference::TheoremReference(id, sub_id),
+            0x04 => Reference::LineReference(id),
+            0x05 => Reference::LemmaReference(id, sub_id),
             _ => return None
         };
         Some(res)
@@ -87,3 +97,43 @@ impl Vectorizable for Reference {
     const TERMINATOR: Self::BinaryForm = [0xfe; 9];
     const TERMINATOR2: Self::BinaryForm = [0xff; 9];
 }
+
+impl Vectorizable for (Variable, Variable) {
+    type BinaryForm = [u8; 9];
+    fn from_binary_in_vec(source: Self::BinaryForm) -> Option<Self> {
+        let first_byte = source[0];
+        let mut bytes = [0; 8];
+        for (i, data) in source.into_iter().skip(1).enumerate() {
+            bytes[i] = data
+        };
+        let num = u64::from_le_bytes(bytes);
+        let id1 = (num & 0xFF_FF_FF_FF) as usize;
+        let id2 = (num >> 32) as usize;
+        let res = match first_byte {
+            0x00 => (Variable::Wff(id1), Variable::Wff(id2)),
+            0x01 => (Variable::Object(id1), Variable::Wff(id2)),
+            0x02 => (Variable::Wff(id1), Variable::Object(id2)),
+            0x03 => (Variable::Object(id1), Variable::Object(id2)),
+            _ => return None
+        };
+        Some(res)
+    }
+    const TERMINATOR: Self::BinaryForm = [0xfe; 9];
+    const TERMINATOR2: Self::BinaryForm = [0xff; 9];
+}
+
+impl Vectorizable for StepKind {
+    type BinaryForm = [u8; 1];
+    fn from_binary_in_vec(source: Self::BinaryForm) -> Option<Self> {
+        let res = match source[0] {
+            0x00 => StepKind::Assertion,
+            0x01 => StepKind::Unfold,
+            0x02 => StepKind::Fold,
+            0x03 => StepKind::Rewrite,
+            _ => return None
+        };
+        Some(res)
+    }
+    const TERMINATOR: Self::BinaryForm = [0xfe];
+    const TERMINATOR2: Self::BinaryForm = [0xff];
+}
@@ -4,9 +4,10 @@ use super::{
     ColorInfo, Color
 };
 use crate::library_data::{
-    Syntax, SyntaxType,
+    Sort, Syntax, SyntaxType, Associativity,
     Placeholder,
-    LibraryData
+    LibraryData,
+    repetition_group
 };
 
 const WFF_VAR_COLOR: ColorInfo = ColorInfo::fg_color(Color::Blue).bold();
@@ -16,8 +17,15 @@ const OBJ_VAR_COLOR: ColorInfo = ColorInfo::fg_color(Color::Red).bold();
 const OBJ_SINGLETON_COLOR: ColorInfo = ColorInfo::fg_color(Color::Yellow);
 const OBJ_SYNTAX_COLOR: ColorInfo = ColorInfo::fg_color(Color::Magenta);
 const NEW_SYNTAX_COLOR: ColorInfo = ColorInfo::fg_color(Color::White);
+const REPETITION_COLOR: ColorInfo = ColorInfo::fg_color(Color::White).bold();
 
-pub fn parse_new_syntax(line: &str, syntax_type: SyntaxType) -> (FileLine, Option<Syntax>) {
+fn is_sort_variable(c: char, sorts: &[Sort]) -> bool {
+    sorts.iter().any(|sort| sort.variables.contains(&c))
+}
+
+pub fn parse_new_syntax(
+    line: &str, syntax_type: SyntaxType, sorts: &[Sort]
+) -> (FileLine, Option<Syntax>) {
     if line.len() == 0 {
         return (
             FileLine { context: LineContext::SyntaxDefinition, chars: vec![], colors: vec![] },
@@ -29,19 +37,37 @@ pub fn parse_new_syntax(line: &str, syntax_type: SyntaxType) -> (FileLine, Optio
     let mut formula = Vec::new();
     let mut wff_mapping = HashMap::new();
     let mut obj_mapping = HashMap::new();
+    let mut sorted_mapping = HashMap::new();
+    let mut spaced_after = Vec::new();
     for c in line.chars() {
         if c == ' ' {
             chars.push(c);
             colors.push(ColorInfo::NO_COLOR);
+            if let Some(position) = formula.len().checked_sub(1) {
+                if spaced_after.last() != Some(&position) {
+                    spaced_after.push(position);
+                };
+            };
             continue;
         };
         if c == '…' {
             chars.push(c);
-            colors.push(ColorInfo::fg_color(Color::Black));
+            colors.push(REPETITION_COLOR);
             formula.push(Placeholder::Repetition);
         } else if '𝑎' <= c && c <= '𝑧' {  // '𝑎' and '𝑧' here are NOT ascii
             chars.push(c);
             colors.push(OBJ_VAR_COLOR);
+            match obj_mapping.get(&c) {
+                Some(id) => formula.push(Placeholder::Object(*id)),
+                None => {
+                    let next_id = obj_mapping.len() + sorted_mapping.len();
+                    formula.push(Placeholder::Object(next_id));
+                    obj_mapping.insert(c, next_id);
+                }
+            };
+        } else if '𝛼' <= c && c <= '𝜔' {
+            chars.push(c);
+            colors.push(WFF_VAR_COLOR);
             match wff_mapping.get(&c) {
                 Some(id) => formula.push(Placeholder::WellFormedFormula(*id)),
                 None => {
@@ -49,14 +75,16 @@ pub fn parse_new_syntax(line: &str, syntax_type: SyntaxType) -> (FileLine, Optio
                     wff_mapping.insert(c, wff_mapping.len());
                 }
             };
-        } else if '𝛼' <= c && c <= '𝜔' {
+        } else if let Some(sort) = sorts.iter().position(|sort| sort.variables.contains(&c)) {
             chars.push(c);
-            colors.push(WFF_VAR_COLOR);
-            match obj_mapping.get(&c) {
-                Some(id) => formula.push(Placeholder::Object(*id)),
+            colors.push(OBJ_VAR_COLOR);
+            // Sorted placeholders share their ids with the other objects
+            let next_id = obj_mapping.len() + sorted_mapping.len();
+            match sorted_mapping.get(&c) {
+                Some(id) => formula.push(Placeholder::Sorted(sort, *id)),
                 None => {
-                    formula.push(Placeholder::Object(obj_mapping.len()));
-                    obj_mapping.insert(c, obj_mapping.len());
+                    formula.push(Placeholder::Sorted(sort, next_id));
+                    sorted_mapping.insert(c, next_id);
                 }
             };
         } else {
@@ -68,12 +96,50 @@ pub fn parse_new_syntax(line: &str, syntax_type: SyntaxType) -> (FileLine, Optio
     let syntax = Syntax {
         syntax_type,
         formula,
+        spaced_after,
+        // Binders, precedences and roles are declared in the title, they are not used to highlight the definition
+        bound_objects: vec![],
+        precedence: None,
+        associativity: Associativity::NonAssociative,
+        role: None,
         distinct_wff_count: wff_mapping.len(),
-        distinct_object_count: obj_mapping.len()
+        distinct_object_count: obj_mapping.len() + sorted_mapping.len()
     };
     (FileLine { context: LineContext::SyntaxDefinition, chars, colors }, Some(syntax))
 }
 
+pub fn parse_sort_variables(line: &str) -> FileLine {
+    let mut chars = Vec::new();
+    let mut colors = Vec::new();
+    for c in line.chars() {
+        let color = match c {
+            ' ' => ColorInfo::NO_COLOR,
+            '𝑎'..='𝑧' | '𝛼'..='𝜔' | '…' => ColorInfo::fg_color(Color::Red),
+            _ => OBJ_VAR_COLOR
+        };
+        chars.push(c);
+        colors.push(color);
+    };
+    FileLine { context: LineContext::SortVariables, chars, colors }
+}
+
+pub fn parse_distinct_variables(line: &str, sorts: &[Sort]) -> FileLine {
+    let mut chars = Vec::new();
+    let mut colors = Vec::new();
+    for c in line.chars() {
+        let color = match c {
+            ' ' | ',' => ColorInfo::NO_COLOR,
+            '𝑎'..='𝑧' => OBJ_VAR_COLOR,
+            '𝛼'..='𝜔' => WFF_VAR_COLOR,
+            c if is_sort_variable(c, sorts) => OBJ_VAR_COLOR,
+            _ => ColorInfo::fg_color(Color::Red)
+        };
+        chars.push(c);
+        colors.push(color);
+    };
+    FileLine { context: LineContext::DistinctVariables, chars, colors }
+}
+
 #[derive(Debug)]
 enum PartiallyCompiled {
     NotCompiled(char),
@@ -91,6 +157,169 @@ fn monochromatic_formula(line: &str, color: Color, context: LineContext) -> File
     }
 }
 
+// Whether the element can start the separator of a repetition, or its next element when there is no separator
+fn continues_repetition(element: Option<&PartiallyCompiled>, next_placeholder: &Placeholder) -> bool {
+    match (element, next_placeholder) {
+        (Some(PartiallyCompiled::NotCompiled(c1)), Placeholder::LiteralChar(c2)) => c1 == c2,
+        (Some(PartiallyCompiled::CompiledFormula { .. }), Placeholder::WellFormedFormula(_)) => true,
+        (
+            Some(PartiallyCompiled::CompiledObject { .. }),
+            Placeholder::Object(_) | Placeholder::Sorted(_, _)
+        ) => true,
+        _ => false
+    }
+}
+
+// Matches the syntax at the start of the elements, skipping spaces,
+//  returns the highlighted characters and the number of elements covered
+fn match_syntax(
+    elements: &[PartiallyCompiled], syntax: &Syntax, syntax_color: ColorInfo
+) -> Option<(Vec<char>, Vec<ColorInfo>, usize)> {
+    let mut wffs = vec![None; syntax.distinct_wff_count];
+    let mut objects = vec![None; syntax.distinct_object_count];
+    let repetition = repetition_group(&syntax.formula);
+
+    let mut i = 0;
+    let mut k = 0;
+    let mut matched_count = 0;
+    let mut chars = Vec::new();
+    let mut colors = Vec::new();
+    while let Some(pl) = syntax.formula.get(k) {
+        if let (Placeholder::Repetition, Some((repeated, _))) = (pl, repetition) {
+            k = repeated;  // The separator has been matched, another element follows
+            continue;
+        };
+        let c = loop {
+            match elements.get(i) {
+                Some(PartiallyCompiled::Space) => {
+                    chars.push(' ');
+                    colors.push(ColorInfo::NO_COLOR);
+                    i += 1;
+                },
+                Some(chr) => break chr,
+                None => return None
+            };
+        };
+        // Elements of a repetition can differ from each other
+        if repetition.is_some_and(|(repeated, _)| repeated == k) {
+            match pl {
+                Placeholder::WellFormedFormula(id) => wffs[*id] = None,
+                Placeholder::Object(id) | Placeholder::Sorted(_, id) => objects[*id] = None,
+                _ => ()
+            };
+        };
+        let valid = match (c, pl) {
+            (
+                PartiallyCompiled::NotCompiled(c1),
+                Placeholder::LiteralChar(c2)
+            ) => {
+                chars.push(*c1);
+                colors.push(syntax_color);
+                c1 == c2
+            },
+            (
+                PartiallyCompiled::CompiledFormula {
+                    chars: chs, colors: cols
+                },
+                Placeholder::WellFormedFormula(id)
+            ) => {
+                chars.extend(chs);
+                colors.extend(cols);
+                match &wffs[*id] {
+                    Some(chrs) => chs == *chrs,
+                    None => { wffs[*id] = Some(chs); true }
+                }
+            },
+            (
+                PartiallyCompiled::CompiledObject {
+                    chars: chs, colors: cols
+                },
+                Placeholder::Object(id) | Placeholder::Sorted(_, id)
+            ) => {
+                chars.extend(chs);
+                colors.extend(cols);
+                match &objects[*id] {
+                    Some(chrs) => chs == *chrs,
+                    None => { objects[*id] = Some(chs); true }
+                }
+            },
+            _ => false
+        };
+        if !valid { return None; };
+        i += 1;
+        matched_count += 1;
+        k = match repetition {
+            // The repetition goes on as long as its separator follows
+            Some((repeated, end)) if repeated == k => {
+                let next_element = elements[i..].iter()
+                    .find(|element| !matches!(element, PartiallyCompiled::Space));
+                let next_placeholder = match k + 1 == end {
+                    true => pl,  // No separator
+                    false => &syntax.formula[k+1]
+                };
+                match continues_repetition(next_element, next_placeholder) {
+                    true => k + 1,
+                    false => end + 1
+                }
+            },
+            _ => k + 1
+        };
+    };
+    // A single element of a repetition would be matched again and again
+    if matched_count == 1 && repetition.is_some_and(|(repeated, _)| repeated == 0) {
+        return None;
+    };
+    Some((chars, colors, i))
+}
+
+// Highest precedence first (syntaxes without one bind tightest), then leftmost for left associative syntaxes
+//  and rightmost for right associative ones. This greedy choice only approximates the chart parser of the compiler,
+//  the highlighting can group differently when:
+//  - candidates of equal precedence have different associativities, the leftmost one's applies to all of them
+//  - several syntaxes without precedence match, the first one is reduced where the compiler reports an ambiguity
+//  - a reduction leads to a dead end, the formula is red even if the compiler finds another parse
+fn choose_candidate(candidates: &[(usize, usize, usize)], syntaxes: &[&Syntax]) -> Option<(usize, usize)> {
+    let strength = |id: usize| syntaxes[id].precedence.unwrap_or(usize::MAX);
+    let strongest = candidates.iter().map(|(id, _, _)| strength(*id)).max()?;
+    let mut strongest_candidates = candidates.iter()
+        .filter(|(id, _, _)| strength(*id) == strongest)
+        .copied()
+        .collect::<Vec<_>>();
+    if strongest == usize::MAX {
+        return strongest_candidates.first().map(|(id, index, _)| (*id, *index));
+    };
+    strongest_candidates.sort_by_key(|(_, index, _)| *index);
+    let (first_id, _, _) = strongest_candidates[0];
+    let chosen = match syntaxes[first_id].associativity {
+        Associativity::Left => strongest_candidates.first(),
+        Associativity::Right => strongest_candidates.last(),
+        Associativity::NonAssociative => {
+            let overlapping = strongest_candidates.windows(2)
+                .any(|pair| pair[0].1 + pair[0].2 > pair[1].1);
+            match overlapping {
+                true => None,
+                false => strongest_candidates.first()
+            }
+        }
+    };
+    chosen.map(|(id, index, _)| (*id, *index))
+}
+
+// Finds a '(' <compiled element> ')' group, returns its position and its length
+fn find_grouping(elements: &[PartiallyCompiled]) -> Option<(usize, usize)> {
+    let non_spaces = elements.iter().enumerate()
+        .filter(|(_, element)| !matches!(element, PartiallyCompiled::Space))
+        .collect::<Vec<_>>();
+    non_spaces.windows(3).find_map(|window| match window {
+        [
+            (start, PartiallyCompiled::NotCompiled('(')),
+            (_, PartiallyCompiled::CompiledFormula { .. } | PartiallyCompiled::CompiledObject { .. }),
+            (end, PartiallyCompiled::NotCompiled(')'))
+        ] => Some((*start, end - start + 1)),
+        _ => None
+    })
+}
+
 pub fn parse_formula(
     line: &str, lib_data: &LibraryData, additional_syntax: Option<Syntax>, context: LineContext
 ) -> FileLine {
@@ -106,6 +335,9 @@ pub fn parse_formula(
             c @ '𝛼'..='𝜔' => Ok(PartiallyCompiled::CompiledFormula {
                 chars: vec![ c ], colors: vec![ WFF_VAR_COLOR ]
             }),
+            c if is_sort_variable(c, &lib_data.sorts) => Ok(PartiallyCompiled::CompiledObject {
+                chars: vec![ c ], colors: vec![ OBJ_VAR_COLOR ]
+            }),
             c => Ok(PartiallyCompiled::NotCompiled(c))
         })
         .collect::<Result<Vec<_>, _>>();
@@ -116,109 +348,92 @@ pub fn parse_formula(
     let syntaxes = additional_syntax.iter()
         .chain(lib_data.syntaxes.iter())
         .collect::<Vec<_>>();
-    'try_find_patterns: while partially_compiled.len() > 1 {
-        'try_syntax: for (syntax_id, syntax) in syntaxes.iter().enumerate() {
-            'try_position: for index in 0..partially_compiled.len() {
-                if partially_compiled.len() - index < syntax.formula.len() {
-                    continue 'try_syntax;  // If the syntax does not fit in the rest of the text, go to the next syntax
+    while partially_compiled.len() > 1 {
+        let mut candidates = Vec::new();
+        for (syntax_id, syntax) in syntaxes.iter().enumerate() {
+            // A repetition can stop after its first element
+            let minimal_length = match repetition_group(&syntax.formula) {
+                Some((repeated, end)) => syntax.formula.len() - (end - repeated),
+                None => syntax.formula.len()
+            };
+            for index in 0..partially_compiled.len() {
+                if partially_compiled.len() - index < minimal_length {
+                    break;  // If the syntax does not fit in the rest of the text, go to the next syntax
                 };
                 if let Some(PartiallyCompiled::Space) = partially_compiled.get(index) {
-                    continue 'try_position;  // We ignore spaces so we skip any leading space
+                    continue;  // We ignore spaces so we skip any leading space
                 };
-                let syntax_color = match (
-                    &syntax.syntax_type, syntax.distinct_wff_count, syntax.distinct_object_count
-                ) {
-                    (_, _, _) if syntax_id == 0 && additional_syntax.is_some() => NEW_SYNTAX_COLOR,
-                    (SyntaxType::Formula, 0, 0) => WFF_SINGLETON_COLOR,
-                    (SyntaxType::Formula, _, _) => WFF_SYNTAX_COLOR,
-                    (SyntaxType::Object, 0, 0) => OBJ_SINGLETON_COLOR,
-                    (SyntaxType::Object, _, _) => OBJ_SYNTAX_COLOR
+                if let Some(matched) = match_syntax(&partially_compiled[index..], syntax, NEW_SYNTAX_COLOR) {
+                    candidates.push((syntax_id, index, matched.2));
                 };
-
-                let mut wffs = vec![None; syntax.distinct_wff_count];
-                let mut objects = vec![None; syntax.distinct_object_count];
-                
-                let mut i = index;
-                let mut chars = Vec::new();
-                let mut colors = Vec::new();
-                let mut syntax_length = 0;
-                for pl in &syntax.formula {
-                    let c = loop {
-                        match partially_compiled.get(i) {
-                            Some(PartiallyCompiled::Space) => {
-                                chars.push(' ');
+            };
+        };
+        let Some((syntax_id, index)) = choose_candidate(&candidates, &syntaxes) else {
+            // Parentheses can group any compiled element, once nothing else applies
+            match find_grouping(&partially_compiled) {
+                Some((index, length)) => {
+                    let mut chars = Vec::new();
+                    let mut colors = Vec::new();
+                    let mut is_formula = false;
+                    for element in partially_compiled.drain(index..index+length) {
+                        match element {
+                            PartiallyCompiled::NotCompiled(c) => {
+                                chars.push(c);
                                 colors.push(ColorInfo::NO_COLOR);
-                                syntax_length += 1;
-                                i += 1;
                             },
-                            Some(chr) => break chr,
-                            None => continue 'try_syntax
-                        };
-                    };
-                    let valid = match (c, pl) {
-                        (
-                            PartiallyCompiled::NotCompiled(c1),
-                            Placeholder::LiteralChar(c2)
-                        ) => {
-                            chars.push(*c1);
-                            colors.push(syntax_color);
-                            syntax_length += 1;
-                            c1 == c2
-                        },
-                        (
-                            PartiallyCompiled::CompiledFormula {
-                                chars: chs, colors: cols
+                            PartiallyCompiled::Space => {
+                                chars.push(' ');
+                                colors.push(ColorInfo::NO_COLOR);
                             },
-                            Placeholder::WellFormedFormula(id)
-                        ) => {
-                            chars.extend(chs);
-                            colors.extend(cols);
-                            syntax_length += 1;
-                            match &wffs[*id] {
-                                Some(chrs) => chs == *chrs,
-                                None => { wffs[*id] = Some(chs); true }
-                            }
-                        },
-                        (
-                            PartiallyCompiled::CompiledObject {
-                                chars: chs, colors: cols
+                            PartiallyCompiled::CompiledFormula { chars: chs, colors: cols } => {
+                                chars.extend(chs);
+                                colors.extend(cols);
+                                is_formula = true;
                             },
-                            Placeholder::Object(id)
-                        ) => {
-                            chars.extend(chs);
-                            colors.extend(cols);
-                            syntax_length += 1;
-                            match &objects[*id] {
-                                Some(chrs) => chs == *chrs,
-                                None => { objects[*id] = Some(chs); true }
+                            PartiallyCompiled::CompiledObject { chars: chs, colors: cols } => {
+                                chars.extend(chs);
+                                colors.extend(cols);
                             }
-                        },
-                        _ => false
+                        };
                     };
-                    match valid {
-                        true => (),
-                        false => continue 'try_position  // Syntax did not match here, try next position
+                    let element_to_insert = match is_formula {
+                        true => PartiallyCompiled::CompiledFormula { chars, colors },
+                        false => PartiallyCompiled::CompiledObject { chars, colors }
                     };
-                    i += 1;
-                };
-                for _ in 0..syntax_length {
-                    partially_compiled.remove(index);
-                };
-                let element_to_insert = match syntax.syntax_type {
-                    SyntaxType::Formula => PartiallyCompiled::CompiledFormula {
-                        chars, colors
-                    },
-                    SyntaxType::Object => PartiallyCompiled::CompiledObject {
-                        chars, colors
-                    }
-                };
-                partially_compiled.insert(index, element_to_insert);
-                // Once a syntax has been compiled, we iterate through the list again from the beginning
-                continue 'try_find_patterns;
+                    partially_compiled.insert(index, element_to_insert);
+                    continue;
+                },
+                // We only can get here if no syntax has matched
+                None => return monochromatic_formula(line, Color::Red, context)
             };
         };
-        // We only can get here if no syntax has matched
-        return monochromatic_formula(line, Color::Red, context);
+        let syntax = syntaxes[syntax_id];
+        let syntax_color = match (
+            &syntax.syntax_type, syntax.distinct_wff_count, syntax.distinct_object_count
+        ) {
+            (_, _, _) if syntax_id == 0 && additional_syntax.is_some() => NEW_SYNTAX_COLOR,
+            (SyntaxType::Formula, 0, 0) => WFF_SINGLETON_COLOR,
+            (SyntaxType::Formula, _, _) => WFF_SYNTAX_COLOR,
+            (SyntaxType::Object | SyntaxType::Sort(_), 0, 0) => OBJ_SINGLETON_COLOR,
+            (SyntaxType::Object | SyntaxType::Sort(_), _, _) => OBJ_SYNTAX_COLOR
+        };
+        let Some((chars, colors, syntax_length)) = match_syntax(
+            &partially_compiled[index..], syntax, syntax_color
+        ) else {
+            return monochromatic_formula(line, Color::Red, context);
+        };
+        for _ in 0..syntax_length {
+            partially_compiled.remove(index);
+        };
+        let element_to_insert = match syntax.syntax_type {
+            SyntaxType::Formula => PartiallyCompiled::CompiledFormula {
+                chars, colors
+            },
+            SyntaxType::Object | SyntaxType::Sort(_) => PartiallyCompiled::CompiledObject {
+                chars, colors
+            }
+        };
+        partially_compiled.insert(index, element_to_insert);
     };
 
     match partially_compiled.pop() {
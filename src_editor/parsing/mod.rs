@@ -10,15 +10,21 @@ pub use parser::parse_file;
 
 mod sections;
 use sections::{
+    parse_variables_section,
     parse_syntax_section,
     parse_definition_section,
     parse_hypotesis_section,
     parse_assertion_section,
-    parse_proof_section
+    parse_distinct_section,
+    parse_proof_section,
+    parse_tactics_section,
+    mark_warning
 };
 
 mod formula;
 use formula::{
     parse_new_syntax,
-    parse_formula
+    parse_formula,
+    parse_sort_variables,
+    parse_distinct_variables
 };
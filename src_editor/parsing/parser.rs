@@ -1,20 +1,30 @@
 use std::{
     fs, io,
-    collections::HashMap
+    collections::HashMap,
+    iter::Peekable
 };
 
 use super::{
     FileLine, LineContext, FileType,
     ColorInfo, Color,
+    parse_variables_section,
     parse_syntax_section,
     parse_definition_section,
     parse_hypotesis_section,
     parse_assertion_section,
-    parse_proof_section
+    parse_distinct_section,
+    parse_proof_section,
+    parse_tactics_section,
+    mark_warning
 };
 use crate::library_data::{
     LibraryData, Reference, SyntaxType
 };
+use math_lib::{
+    parsing::parse_file as parse_math_file,
+    compiling::{verify_theo, CompileWarning},
+    serializing::read_file
+};
 
 pub fn parse_title(lines: &mut std::str::Lines) -> (FileLine, FileType) {
     let first_line = match lines.next() {
@@ -43,11 +53,17 @@ pub fn parse_title(lines: &mut std::str::Lines) -> (FileLine, FileType) {
         },
         Some((title, name)) => (title, name)
     };
-    let (title_bg_color, file_type) = match title {
-        "## Syntax Definition (formula)" => (Color::Blue, FileType::SyntaxDefinitionFormula),
-        "## Syntax Definition (object)" => (Color::Blue, FileType::SyntaxDefinitionObject),
-        "## Axiom" => (Color::Blue, FileType::Axiom),
-        "## Theorem" => (Color::Blue, FileType::Theorem),
+    // Syntax definitions may list attributes after their type, e.g. "(formula, binds 𝑥)"
+    let syntax_type = title.strip_prefix("## Syntax Definition (")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .map(|attributes| attributes.split(',').next().unwrap_or("").trim());
+    let (title_bg_color, file_type) = match (title, syntax_type) {
+        (_, Some("formula")) => (Color::Blue, FileType::SyntaxDefinitionFormula),
+        (_, Some("object")) => (Color::Blue, FileType::SyntaxDefinitionObject),
+        (_, Some(sort_name)) => (Color::Blue, FileType::SyntaxDefinitionSort(sort_name.to_owned())),
+        ("## Sort", _) => (Color::Blue, FileType::Sort),
+        ("## Axiom", _) => (Color::Blue, FileType::Axiom),
+        ("## Theorem", _) => (Color::Blue, FileType::Theorem),
         _ => (Color::Red, FileType::Unknown)
     };
     let name_color = match name.chars().all(|c| c.is_ascii_alphanumeric()) {
@@ -69,6 +85,105 @@ pub fn parse_title(lines: &mut std::str::Lines) -> (FileLine, FileType) {
     )
 }
 
+fn is_lemma_section(section: &Vec<&str>) -> bool {
+    section.first().is_some_and(|line| line.starts_with("# Lemma "))
+}
+
+fn parse_lemma_header(section: Vec<&str>) -> Vec<FileLine> {
+    let mut result_lines = Vec::new();
+    for (i, line) in section.into_iter().enumerate() {
+        let (context, color) = match i {
+            0 => (LineContext::Section, ColorInfo::fg_color(Color::White).bold_underlined()),
+            _ => (LineContext::UnexpectedLine, ColorInfo::fg_color(Color::Red))
+        };
+        let chars = line.chars().collect::<Vec<_>>();
+        let colors = chars.iter().map(|_| color).collect();
+        result_lines.push( FileLine { context, chars, colors } );
+    };
+    result_lines
+}
+
+// The warnings of the compiler about the proofs of the theorem file, none when it doesn't compile
+fn compiler_warnings(contents: &str) -> Vec<CompileWarning> {
+    let Ok((mut lib, mut references)) = read_file("library.math".to_owned()) else {
+        return Vec::new();
+    };
+    let Ok(math_file) = parse_math_file(contents.to_owned()) else {
+        return Vec::new();
+    };
+    verify_theo(math_file, &mut lib, &mut references).unwrap_or_default()
+}
+
+// Hypotheses, assertions, distinct variables and proof, of a theorem or of one of its lemmas.
+// The names of the unused hypotheses and the numbers of the lines the compiler warns about are yellow
+fn parse_theorem_sections<'a, I: Iterator<Item = Vec<&'a str>>>(
+    sections: &mut Peekable<I>, lib_data: &LibraryData, references: &HashMap<String, Reference>,
+    lemma_names: &[String], (statement, warnings): (&str, &[CompileWarning]), result_lines: &mut Vec<FileLine>
+) {
+    let is_distinct_section = |section: &Vec<&str>| section.first() == Some(&"# Distinct");
+    let is_tactics_section = |section: &Vec<&str>| section.first() == Some(&"# Tactics");
+    let Some(hypothesis_section) = sections.next_if(|section| !is_lemma_section(section)) else {
+        return;
+    };
+    let (
+        mut hypot_lines, mut cited_names
+    ) = parse_hypotesis_section(hypothesis_section, lib_data);
+    if let Some(assertion_section) = sections.next_if(|section| !is_lemma_section(section)) {
+        let context = LineContext::UnprovenAssertion;
+        let mut assertion_lines = parse_assertion_section(assertion_section, lib_data, context);
+        let mut distinct_lines = match sections.next_if(is_distinct_section) {
+            Some(distinct_section) => parse_distinct_section(distinct_section, &lib_data.sorts),
+            None => Vec::new()
+        };
+        if let Some(tactics_section) = sections.next_if(is_tactics_section) {
+            cited_names.extend_from_slice(lemma_names);
+            let mut tactic_lines = parse_tactics_section(tactics_section, lib_data, references, cited_names);
+            result_lines.append( &mut hypot_lines );
+            result_lines.append( &mut assertion_lines );
+            result_lines.append( &mut distinct_lines );
+            result_lines.append( &mut tactic_lines );
+            return;
+        };
+        if let Some(proof_section) = sections.next_if(|section| !is_lemma_section(section)) {
+            cited_names.extend_from_slice(lemma_names);
+            let mut proof_lines = parse_proof_section(proof_section, lib_data, references, cited_names);
+            // Proof lines are counted from 1, after the header of the section
+            let flagged_lines = warnings.iter().filter_map(|warning| match warning {
+                CompileWarning::UnusedStep(name, line) | CompileWarning::DuplicateStep(name, line, _)
+                    if name == statement => Some(*line),
+                _ => None
+            });
+            for line in flagged_lines {
+                if let Some(proof_line) = proof_lines.get_mut(line) {
+                    mark_warning(proof_line, ' ');
+                };
+            };
+            let unused_hypotheses = warnings.iter()
+                .filter_map(|warning| match warning {
+                    CompileWarning::UnusedHypothesis(name, hypot_name) if name == statement => Some(hypot_name.as_str()),
+                    _ => None
+                })
+                .collect::<Vec<_>>();
+            for hypot_line in hypot_lines.iter_mut().skip(1) {
+                let name = hypot_line.chars.iter().take_while(|c| **c != ':').collect::<String>();
+                if unused_hypotheses.contains(&name.trim()) {
+                    mark_warning(hypot_line, ':');
+                };
+            };
+            result_lines.append( &mut hypot_lines );
+            result_lines.append( &mut assertion_lines );
+            result_lines.append( &mut distinct_lines );
+            result_lines.append( &mut proof_lines );
+            return;
+        };
+        result_lines.append( &mut hypot_lines );
+        result_lines.append( &mut assertion_lines );
+        result_lines.append( &mut distinct_lines );
+        return;
+    };
+    result_lines.append( &mut hypot_lines );
+}
+
 pub fn parse_file(
     path: String, lib_data: &LibraryData, references: &HashMap<String, Reference>
 ) -> io::Result<Vec<FileLine>> {
@@ -87,7 +202,13 @@ pub fn parse_file(
         temp.push(line);
     };
     sections.push(temp);
-    let mut sections = sections.into_iter();
+    // The lemmas of a theorem are stated after its proof, which can already cite them
+    let lemma_names = sections.iter()
+        .filter_map(|section| section.first()?.strip_prefix("# Lemma "))
+        .map(|name| name.trim().to_owned())
+        .collect::<Vec<_>>();
+    let mut sections = sections.into_iter().peekable();
+    let is_distinct_section = |section: &Vec<&str>| section.first() == Some(&"# Distinct");
 
     let mut result_lines = vec![ title ];
     if let Some(empty_first_section) = sections.next() {
@@ -98,13 +219,18 @@ pub fn parse_file(
         };
     };
     match file_type {
+        FileType::Sort => {
+            if let Some(variables_section) = sections.next() {
+                result_lines.append( &mut parse_variables_section(variables_section) );
+            };
+        },
         FileType::SyntaxDefinitionFormula => {
             let Some(syntax_section) = sections.next() else {
                 return Ok(result_lines);
             };
             let (
                 mut syntax_lines, new_syntax
-            ) = parse_syntax_section(syntax_section, SyntaxType::Formula);
+            ) = parse_syntax_section(syntax_section, SyntaxType::Formula, &lib_data.sorts);
             result_lines.append( &mut syntax_lines );
             if let Some(definition_section) = sections.next() {
                 result_lines.append(
@@ -118,7 +244,7 @@ pub fn parse_file(
             };
             let (
                 mut syntax_lines, new_syntax
-            ) = parse_syntax_section(syntax_section, SyntaxType::Object);
+            ) = parse_syntax_section(syntax_section, SyntaxType::Object, &lib_data.sorts);
             result_lines.append( &mut syntax_lines );
             if let Some(definition_section) = sections.next() {
                 result_lines.append(
@@ -126,6 +252,23 @@ pub fn parse_file(
                 );
             };
         },
+        FileType::SyntaxDefinitionSort(sort_name) => {
+            // Syntaxes of sorts unknown to the library are shown as unexpected lines below
+            if let Some(sort) = lib_data.sorts.iter().position(|sort| sort.name == sort_name) {
+                let Some(syntax_section) = sections.next() else {
+                    return Ok(result_lines);
+                };
+                let (
+                    mut syntax_lines, new_syntax
+                ) = parse_syntax_section(syntax_section, SyntaxType::Sort(sort), &lib_data.sorts);
+                result_lines.append( &mut syntax_lines );
+                if let Some(definition_section) = sections.next() {
+                    result_lines.append(
+                        &mut parse_definition_section(definition_section, lib_data, new_syntax)
+                    );
+                };
+            };
+        },
         FileType::Axiom => {
             if let Some(hypothesis_section) = sections.next() {
                 let context = LineContext::AxiomHypothesis;
@@ -141,25 +284,29 @@ pub fn parse_file(
                     &mut parse_assertion_section(assertion_section, lib_data, context)
                 );
             };
+            if let Some(distinct_section) = sections.next_if(is_distinct_section) {
+                result_lines.append( &mut parse_distinct_section(distinct_section, &lib_data.sorts) );
+            };
         },
         FileType::Theorem => {
-            let Some(hypothesis_section) = sections.next() else {
-                return Ok(result_lines);
-            };
-            let (
-                mut hypot_lines, hypot_names
-            ) = parse_hypotesis_section(hypothesis_section, lib_data);
-            result_lines.append( &mut hypot_lines );
-            if let Some(assertion_section) = sections.next() {
-                let context = LineContext::UnprovenAssertion;
-                result_lines.append(
-                    &mut parse_assertion_section(assertion_section, lib_data, context)
-                );
-            };
-            if let Some(proof_section) = sections.next() {
-                result_lines.append(
-                    &mut parse_proof_section(proof_section, lib_data, references, hypot_names)
+            // The warnings about a lemma are given with its name
+            let warnings = compiler_warnings(&contents);
+            let theorem_name = contents.lines().next()
+                .and_then(|line| line.rsplit_once(' '))
+                .map_or("", |(_, name)| name);
+            parse_theorem_sections(
+                &mut sections, lib_data, references, &lemma_names, (theorem_name, &warnings), &mut result_lines
+            );
+            // Each lemma can use the preceding ones
+            let mut lemma_count = 0;
+            while let Some(lemma_section) = sections.next_if(is_lemma_section) {
+                result_lines.append( &mut parse_lemma_header(lemma_section) );
+                let cited_lemmas = lemma_names[..lemma_count].to_vec();
+                let lemma_name = lemma_names[lemma_count].as_str();
+                parse_theorem_sections(
+                    &mut sections, lib_data, references, &cited_lemmas, (lemma_name, &warnings), &mut result_lines
                 );
+                lemma_count += 1;
             };
         },
         FileType::Unknown => {
@@ -4,14 +4,41 @@ use super::{
     FileLine, LineContext,
     ColorInfo, Color,
     parse_new_syntax,
-    parse_formula
+    parse_formula,
+    parse_sort_variables,
+    parse_distinct_variables
 };
 use crate::library_data::{
     LibraryData, Reference,
-    Syntax, SyntaxType
+    Sort, Syntax, SyntaxType
 };
 
-pub fn parse_syntax_section(section: Vec<&str>, syntax_type: SyntaxType) -> (Vec<FileLine>, Option<Syntax>) {
+pub fn parse_variables_section(section: Vec<&str>) -> Vec<FileLine> {
+    let mut lines = section.into_iter();
+
+    let section_name_line = match lines.next() {
+        Some(section_name) => {
+            let name_color = match section_name == "# Variables" {
+                true => ColorInfo::fg_color(Color::White).bold_underlined(),
+                false => ColorInfo::fg_color(Color::Red)
+            };
+            let chars = section_name.chars().collect::<Vec<_>>();
+            let colors = chars.iter().map(|_| name_color).collect();
+            FileLine { context: LineContext::Section, chars, colors }
+        },
+        None => return vec![]
+    };
+
+    let mut result_lines = vec![ section_name_line ];
+    for line in lines {
+        result_lines.push( parse_sort_variables(line) );
+    };
+    result_lines
+}
+
+pub fn parse_syntax_section(
+    section: Vec<&str>, syntax_type: SyntaxType, sorts: &[Sort]
+) -> (Vec<FileLine>, Option<Syntax>) {
     let mut lines = section.into_iter();
 
     let section_name_line = match lines.next() {
@@ -27,7 +54,7 @@ pub fn parse_syntax_section(section: Vec<&str>, syntax_type: SyntaxType) -> (Vec
         None => return (vec![], None)
     };
     let (syntax_def_line, syntax) = match lines.next() {
-        Some(line) => parse_new_syntax(line, syntax_type),
+        Some(line) => parse_new_syntax(line, syntax_type, sorts),
         None => return (vec![ section_name_line ], None)
     };
     
@@ -160,22 +187,38 @@ pub fn parse_assertion_section(
     result_lines
 }
 
+pub fn parse_distinct_section(section: Vec<&str>, sorts: &[Sort]) -> Vec<FileLine> {
+    let mut lines = section.into_iter();
 
-fn parse_used_hypots(used_hypots: &str, line_no: &str) -> Vec<(char, ColorInfo)> {
-    let line_num = match line_no.parse::<usize>() {
-        Ok(n) => n,
-        Err(_) => {
-            return used_hypots.chars()
-                .map(|c| (c, ColorInfo::NO_COLOR))
-                .collect::<Vec<_>>()
-        }
+    let section_name_line = match lines.next() {
+        Some(section_name) => {
+            let name_color = match section_name == "# Distinct" {
+                true => ColorInfo::fg_color(Color::White).bold_underlined(),
+                false => ColorInfo::fg_color(Color::Red)
+            };
+            let chars = section_name.chars().collect::<Vec<_>>();
+            let colors = chars.iter().map(|_| name_color).collect();
+            FileLine { context: LineContext::Section, chars, colors }
+        },
+        None => return vec![]
     };
+
+    let mut result_lines = vec![ section_name_line ];
+    for line in lines {
+        result_lines.push( parse_distinct_variables(line, sorts) );
+    };
+    result_lines
+}
+
+// A used line is either the number or the name of a preceding line
+fn parse_used_hypots(used_hypots: &str, line_num: usize, line_labels: &[String]) -> Vec<(char, ColorInfo)> {
     used_hypots.split(',')
         .map(|s| {
             let leading_spaces = s.len() - s.trim_start().len();
             let trailing_spaces = s.len() - s.trim_end().len();
             let color = match s.trim().parse::<usize>() {
                 Ok(n) if n < line_num => ColorInfo::NO_COLOR,
+                Err(_) if line_labels.iter().any(|label| label == s.trim()) => ColorInfo::NO_COLOR,
                 _ => ColorInfo::fg_color(Color::Red)
             };
             Some((',', ColorInfo::NO_COLOR)).into_iter()
@@ -195,9 +238,27 @@ fn theo_is_valid(
     theo_ref: &str, hypot_names: &Vec<String>,
     lib_data: &LibraryData, references: &HashMap<String, Reference>
 ) -> bool {
+    // The substitution annotation, as in "mp[𝜑:=¬𝜓, 𝜓:=𝜒]", is checked by the compiler
+    let theo_ref = match theo_ref.split_once('[') {
+        Some((name, _)) => name.trim_end(),
+        None => theo_ref
+    };
     if hypot_names.contains(&theo_ref.to_owned()) {
         return true;
     };
+    if theo_ref == "rw" {
+        return true;
+    };
+    if theo_ref == "assume" || theo_ref == "discharge" {
+        return lib_data.has_deduction;
+    };
+    match theo_ref.split_once(' ') {
+        Some(("unfold" | "fold", def_name)) => {
+            return matches!(references.get(def_name.trim()), Some(Reference::DefinitionReference(_)));
+        },
+        Some(("rw", rel_name)) => return theo_is_valid(rel_name.trim(), &vec![], lib_data, references),
+        _ => {}
+    };
     let (name, sub_id) = match theo_ref.split_once('.') {
         Some((name, num)) => {
             let Ok(sub_id) = num.parse::<usize>() else {
@@ -243,19 +304,27 @@ pub fn parse_proof_section(
     let mut max_used_hypots_len = 2;
     let mut max_theo_ref_len = 2;
     let mut preparsed_lines = Vec::new();
+    let mut line_labels = Vec::new();
     for (i, line) in lines.enumerate() {
         let mut parts = line.splitn(4, ';')
             .map(|s| s.trim());
         let line_no = parts.next().unwrap_or("");
-        let line_no_color = match line_no.parse::<usize>() == Ok(i+1) {
-            true => ColorInfo::NO_COLOR,
-            false => ColorInfo::fg_color(Color::Red)
+        let is_label = !line_no.is_empty()
+            && line_no.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !line_labels.iter().any(|label| label == line_no);
+        let line_no_color = match line_no.parse::<usize>() {
+            Ok(n) if n == i+1 => ColorInfo::NO_COLOR,
+            Err(_) if is_label => ColorInfo::NO_COLOR,
+            _ => ColorInfo::fg_color(Color::Red)
         };
         let line_no_len = line_no.chars().count();
         if line_no_len > max_line_no_len { max_line_no_len = line_no_len; };
 
         let used_hypots = parts.next().unwrap_or("");
-        let used_hypots_color = parse_used_hypots(used_hypots, line_no);
+        let used_hypots_color = parse_used_hypots(used_hypots, i+1, &line_labels);
+        if line_no.parse::<usize>().is_err() && is_label {
+            line_labels.push(line_no.to_owned());
+        };
         let used_hypots_len = used_hypots.chars().count();
         if used_hypots_len > max_used_hypots_len { max_used_hypots_len = used_hypots_len; };
 
@@ -269,9 +338,11 @@ pub fn parse_proof_section(
         if theo_ref_len > max_theo_ref_len { max_theo_ref_len = theo_ref_len; };
 
         let context = LineContext::ProofLine;
-        let resulting_formula = parse_formula(
-            parts.next().unwrap_or(""), lib_data, None, context
-        );
+        let resulting_formula = match parts.next().unwrap_or("") {
+            // Inferred by the compiler
+            "?" => FileLine { context, chars: vec!['?'], colors: vec![ColorInfo::NO_COLOR] },
+            formula => parse_formula(formula, lib_data, None, context)
+        };
 
         preparsed_lines.push((
             (line_no, line_no_color),
@@ -323,3 +394,88 @@ pub fn parse_proof_section(
     };
     result_lines
 }
+
+// Colors a tactic after its "repeat" and "try" prefixes, its name being red if it can't be cited.
+// The names given by "intro" are added to 'assumption_names'
+fn parse_tactic(
+    tactic: &str, hypot_names: &Vec<String>, assumption_names: &mut Vec<String>,
+    lib_data: &LibraryData, references: &HashMap<String, Reference>
+) -> Vec<ColorInfo> {
+    let (keyword, argument) = match tactic.split_once(' ') {
+        Some((keyword, argument)) => (keyword, Some(argument)),
+        None => (tactic, None)
+    };
+    let keyword_colors = keyword.chars().map(|_| ColorInfo::NO_COLOR);
+    let argument_colors = match (keyword, argument) {
+        ("repeat" | "try", Some(argument)) => {
+            return keyword_colors
+                .chain(Some(ColorInfo::NO_COLOR))
+                .chain(parse_tactic(argument, hypot_names, assumption_names, lib_data, references))
+                .collect();
+        },
+        (_, None) if keyword == "exact" || keyword == "intro" => Vec::new(),
+        ("apply" | "exact" | "intro", Some(argument)) => {
+            let name = argument.trim();
+            let name_valid = match keyword {
+                "intro" => {
+                    assumption_names.push(name.to_owned());
+                    true
+                },
+                "exact" if assumption_names.iter().any(|assumption| assumption == name) => true,
+                _ => theo_is_valid(name, hypot_names, lib_data, references)
+            };
+            let name_color = match name_valid && !name.chars().any(|c| c.is_whitespace()) {
+                true => ColorInfo::NO_COLOR,
+                false => ColorInfo::fg_color(Color::Red)
+            };
+            Some(ColorInfo::NO_COLOR).into_iter()
+                .chain(argument.chars().map(|c| match c.is_whitespace() {
+                    true => ColorInfo::NO_COLOR,
+                    false => name_color
+                }))
+                .collect()
+        },
+        _ => return tactic.chars().map(|_| ColorInfo::fg_color(Color::Red)).collect()
+    };
+    keyword_colors.chain(argument_colors).collect()
+}
+
+pub fn parse_tactics_section(
+    section: Vec<&str>, lib_data: &LibraryData,
+    references: &HashMap<String, Reference>, hypot_names: Vec<String>
+) -> Vec<FileLine> {
+    let mut lines = section.into_iter();
+
+    let section_name_line = match lines.next() {
+        Some(section_name) => {
+            let name_color = match section_name == "# Tactics" {
+                true => ColorInfo::fg_color(Color::White).bold_underlined(),
+                false => ColorInfo::fg_color(Color::Red)
+            };
+            let chars = section_name.chars().collect::<Vec<_>>();
+            let colors = chars.iter().map(|_| name_color).collect();
+            FileLine { context: LineContext::Section, chars, colors }
+        },
+        None => return vec![]
+    };
+
+    let mut result_lines = vec![ section_name_line ];
+    let mut assumption_names = Vec::new();
+    for line in lines {
+        let tactic = line.trim();
+        let chars = tactic.chars().collect::<Vec<_>>();
+        let colors = parse_tactic(tactic, &hypot_names, &mut assumption_names, lib_data, references);
+        result_lines.push( FileLine { context: LineContext::TacticLine, chars, colors } );
+    };
+    result_lines
+}
+
+// Colors the start of the line until the first separator, unless it is already colored
+pub fn mark_warning(line: &mut FileLine, separator: char) {
+    let end = line.chars.iter().position(|c| *c == separator).unwrap_or(line.chars.len());
+    for color in line.colors[..end].iter_mut() {
+        if *color == ColorInfo::NO_COLOR {
+            *color = ColorInfo::fg_color(Color::Yellow);
+        };
+    };
+}
@@ -1,6 +1,8 @@
 pub enum FileType {
+    Sort,
     SyntaxDefinitionFormula,
     SyntaxDefinitionObject,
+    SyntaxDefinitionSort(String),
     Axiom,
     Theorem,
     Unknown
@@ -11,13 +13,16 @@ pub enum LineContext {
     Raw,
     Title,
     Section,
+    SortVariables,
     SyntaxDefinition,
     AxiomHypothesis,
     Hypothesis,
     UnprovenAssertion,
     // ProvenAssertion,  // Not implemented for now
     AssumedAssertion,
+    DistinctVariables,
     ProofLine,
+    TacticLine,
     UnexpectedLine
 }
 